@@ -0,0 +1,60 @@
+use {
+    serde::{Serialize, Deserialize},
+    uuid::Uuid,
+};
+
+// Identifies the chain of messages a message belongs to, from discovery through the normalizer.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TraceContext {
+    pub correlation_id: String,
+    // W3C traceparent of the publishing span, only set when traces are exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+}
+
+impl TraceContext {
+    pub fn new() -> TraceContext {
+        TraceContext{
+            correlation_id: Uuid::new_v4().to_string(),
+            traceparent: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Envelope<T> {
+    pub trace: TraceContext,
+    // Provenance of the payload: the datasource that extracted it, or manual for events curators submitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub payload: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(trace: TraceContext, payload: T) -> Envelope<T> {
+        Envelope{trace: trace, source: None, payload: payload}
+    }
+
+    pub fn with_source(mut self, source: &str) -> Envelope<T> {
+        self.source = Some(source.to_owned());
+        self
+    }
+}
+
+impl Envelope<serde_json::Value> {
+    // Splits a bus message into its trace context and the payload a datasource expects.
+    // Messages published by hand (e.g. a discovery url) have no envelope and start a new trace.
+    pub fn open(data: &[u8]) -> (TraceContext, Vec<u8>) {
+        let envelope: Envelope<serde_json::Value> = match serde_json::from_slice(data) {
+            Ok(envelope) => envelope,
+            Err(_) => return (TraceContext::new(), data.to_vec()),
+        };
+
+        let payload = match envelope.payload {
+            serde_json::Value::String(payload) => payload.into_bytes(),
+            payload => payload.to_string().into_bytes(),
+        };
+
+        (envelope.trace, payload)
+    }
+}
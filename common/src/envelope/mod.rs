@@ -0,0 +1,6 @@
+mod envelope;
+
+#[cfg(test)]
+mod tests;
+
+pub use envelope::{Envelope, TraceContext};
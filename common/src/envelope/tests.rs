@@ -0,0 +1,47 @@
+use {
+    super::{Envelope, TraceContext},
+};
+
+fn trace() -> TraceContext {
+    TraceContext{correlation_id: "c0ffee".to_owned(), traceparent: None}
+}
+
+#[test]
+fn test_open_bare_message() {
+    let data = "https://bachtrack.com/find-concerts/".as_bytes();
+
+    let (trace, payload) = Envelope::open(data);
+
+    assert_eq!(payload, data.to_vec());
+    assert_eq!(trace.traceparent, None);
+    assert!(!trace.correlation_id.is_empty());
+    assert_ne!(Envelope::open(data).0, trace);
+}
+
+#[test]
+fn test_open_enveloped_messages() {
+    let configuration = serde_json::json!({
+        "ds_name": "datasource.bachtrack_listing",
+        "value": "/concert-event/318719",
+    });
+
+    let cases = vec![
+        (serde_json::to_vec(&Envelope::new(trace(), &configuration)).unwrap(), serde_json::to_vec(&configuration).unwrap()),
+        (serde_json::to_vec(&Envelope::new(trace(), "https://bachtrack.com/find-concerts/")).unwrap(), b"https://bachtrack.com/find-concerts/".to_vec()),
+    ];
+
+    for (data, expected_payload) in cases {
+        let (trace, payload) = Envelope::open(&data);
+        assert_eq!(trace, self::trace());
+        assert_eq!(payload, expected_payload);
+    }
+}
+
+#[test]
+fn test_traceparent_is_kept() {
+    let mut trace = trace();
+    trace.traceparent = Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_owned());
+    let data = serde_json::to_vec(&Envelope::new(trace.clone(), "payload")).unwrap();
+
+    assert_eq!(Envelope::open(&data), (trace, b"payload".to_vec()));
+}
@@ -11,7 +11,12 @@ use {
 mod metadata;
 mod simple_types;
 mod value;
+mod shutdown;
+mod envelope;
+pub mod metrics;
 
 pub use value::Value;
 pub use simple_types::SimpleTypes;
-pub use metadata::Metadata;
\ No newline at end of file
+pub use metadata::Metadata;
+pub use shutdown::Shutdown;
+pub use envelope::{Envelope, TraceContext};
\ No newline at end of file
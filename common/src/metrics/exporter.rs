@@ -0,0 +1,67 @@
+use {
+    std::convert::Infallible,
+    std::net::SocketAddr,
+    std::sync::Arc,
+    hyper::{Body, Request, Response, Server, StatusCode},
+    hyper::header::CONTENT_TYPE,
+    hyper::service::{make_service_fn, service_fn},
+    prometheus::{Encoder, TextEncoder},
+    tracing::{info, error},
+    crate::Shutdown,
+};
+
+const METRICS_PATH: &str = "/metrics";
+
+// A JSON page served next to the metrics, e.g. the extractor's circuit breakers
+pub trait StatusPage: Send + Sync {
+    fn path(&self) -> &str;
+    fn status(&self) -> serde_json::Value;
+}
+
+// Serves the registered metrics on /metrics and the pages on their paths until shutdown
+pub async fn serve(addr: SocketAddr, pages: Vec<Arc<dyn StatusPage>>, shutdown: Shutdown) -> Result<(), hyper::Error> {
+    let pages = Arc::new(pages);
+    let make_service = make_service_fn(move |_| {
+        let pages = Arc::clone(&pages);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle(request, Arc::clone(&pages))))
+        }
+    });
+
+    info!(%addr, "serving metrics");
+    Server::bind(&addr)
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown.wait())
+        .await
+}
+
+pub(super) async fn handle(request: Request<Body>, pages: Arc<Vec<Arc<dyn StatusPage>>>) -> Result<Response<Body>, Infallible> {
+    if let Some(page) = pages.iter().find(|page| page.path() == request.uri().path()) {
+        return Ok(status(page.as_ref()));
+    }
+    if request.uri().path() != METRICS_PATH {
+        let mut not_found = Response::new(Body::empty());
+        *not_found.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(not_found);
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!(error = %e, "Error encoding metrics");
+        let mut error = Response::new(Body::empty());
+        *error.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        return Ok(error);
+    }
+
+    let mut response = Response::new(Body::from(buffer));
+    response.headers_mut().insert(CONTENT_TYPE, encoder.format_type().parse().unwrap());
+    Ok(response)
+}
+
+fn status(page: &dyn StatusPage) -> Response<Body> {
+    let status = serde_json::to_vec(&page.status()).unwrap_or_default();
+    let mut response = Response::new(Body::from(status));
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
@@ -0,0 +1,7 @@
+// The Prometheus exporter every binary serves its metrics with.
+mod exporter;
+
+#[cfg(test)]
+mod tests;
+
+pub use exporter::{serve, StatusPage};
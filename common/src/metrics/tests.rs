@@ -0,0 +1,51 @@
+use {
+    std::sync::Arc,
+    hyper::{Body, Request, Response, StatusCode},
+    hyper::header::CONTENT_TYPE,
+    prometheus::{IntCounter, register_int_counter},
+    super::StatusPage,
+    super::exporter::handle,
+};
+
+struct Breakers;
+
+impl StatusPage for Breakers {
+    fn path(&self) -> &str {
+        "/circuit-breakers"
+    }
+
+    fn status(&self) -> serde_json::Value {
+        serde_json::json!([{"host": "bachtrack.com", "state": "open"}])
+    }
+}
+
+async fn get(path: &str) -> Response<Body> {
+    let pages: Vec<Arc<dyn StatusPage>> = vec![Arc::new(Breakers)];
+    let request = Request::get(path).body(Body::empty()).unwrap();
+    handle(request, Arc::new(pages)).await.unwrap()
+}
+
+async fn body(response: Response<Body>) -> String {
+    String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let counter: IntCounter = register_int_counter!("common_test_requests_total", "Requests in the exporter test").unwrap();
+    counter.inc();
+
+    let response = get("/metrics").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    assert!(body(response).await.contains("common_test_requests_total 1"));
+}
+
+#[tokio::test]
+async fn test_status_pages() {
+    let response = get("/circuit-breakers").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(body(response).await, r#"[{"host":"bachtrack.com","state":"open"}]"#);
+
+    assert_eq!(get("/").await.status(), StatusCode::NOT_FOUND);
+}
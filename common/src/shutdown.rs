@@ -0,0 +1,55 @@
+use {
+    std::future::Future,
+    tokio::sync::watch,
+    tokio::signal::unix::{signal, SignalKind},
+    tracing::{info, error},
+};
+
+// Notifies every clone once SIGINT or SIGTERM arrives.
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn listen() -> Shutdown {
+        Shutdown::when(wait_for_signal())
+    }
+
+    // Shuts down once the trigger completes
+    pub fn when<F: Future<Output = ()> + Send + 'static>(trigger: F) -> Shutdown {
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            trigger.await;
+            info!("shutting down");
+            // Nobody listening anymore is fine, everything already stopped
+            let _ = sender.broadcast(true);
+        });
+
+        Shutdown{receiver: receiver}
+    }
+
+    pub async fn wait(mut self) {
+        while let Some(shutting_down) = self.receiver.recv().await {
+            if shutting_down {
+                return;
+            }
+        }
+    }
+}
+
+async fn wait_for_signal() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            error!(error = %e, "Couldn't listen to SIGTERM, only SIGINT will shut down");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
+    }
+}
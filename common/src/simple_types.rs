@@ -8,8 +8,8 @@ use {
 };
 
 
-#[serde(rename = "simpleTypes")]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename = "simpleTypes")]
 pub enum SimpleTypes{  
     #[serde(rename = "boolean")]
     Boolean,
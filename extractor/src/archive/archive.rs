@@ -0,0 +1,93 @@
+use {
+    std::io::{self, Write, BufRead, BufReader},
+    std::fs::{self, File, OpenOptions},
+    std::path::{Path, PathBuf},
+    chrono::{NaiveDate, NaiveDateTime, Utc},
+    serde::{Serialize, Deserialize},
+    sha2::{Sha256, Digest},
+};
+
+pub const DEFAULT_ARCHIVE_DIR: &str = "archive";
+const INDEX_FILE: &str = "index.jsonl";
+const OBJECTS_DIR: &str = "objects";
+
+// One fetch of a page, the body is stored once per distinct content
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ArchiveRecord {
+    pub datasource: String,
+    pub url: String,
+    pub fetched_at: NaiveDateTime,
+    pub digest: String,
+    // The configuration the page was fetched for, when the page alone doesn't say everything it was extracted with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub configuration: Option<String>,
+}
+
+// Every fetched page on the local filesystem: bodies under objects/ named by their sha256,
+// and an append-only index of which url was fetched when.
+pub struct PageArchive {
+    root: PathBuf,
+}
+
+impl PageArchive {
+    pub fn new<P: AsRef<Path>>(root: P) -> PageArchive {
+        PageArchive{root: root.as_ref().to_path_buf()}
+    }
+
+    pub fn store(&self, datasource: &str, url: &str, body: &str, configuration: Option<&str>) -> io::Result<ArchiveRecord> {
+        let digest = hex::encode(Sha256::digest(body.as_bytes()));
+        let object_path = self.object_path(&digest);
+        if !object_path.exists() {
+            fs::create_dir_all(object_path.parent().unwrap())?;
+            // Written aside and renamed, so a crash never leaves a truncated object behind
+            let partial_path = object_path.with_extension("partial");
+            fs::write(&partial_path, body)?;
+            fs::rename(&partial_path, &object_path)?;
+        }
+
+        let record = ArchiveRecord{
+            datasource: datasource.to_owned(),
+            url: url.to_owned(),
+            fetched_at: Utc::now().naive_utc(),
+            digest: digest,
+            configuration: configuration.map(|configuration| configuration.to_owned()),
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        // A single append per record keeps concurrent fetches from interleaving
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join(INDEX_FILE))?
+            .write_all(&line)?;
+
+        Ok(record)
+    }
+
+    // Records of the datasource fetched between the two days, both included
+    pub fn records(&self, datasource: &str, from: NaiveDate, to: NaiveDate) -> io::Result<Vec<ArchiveRecord>> {
+        let index = match File::open(self.root.join(INDEX_FILE)) {
+            Ok(index) => index,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(index).lines() {
+            let record: ArchiveRecord = serde_json::from_str(&line?)?;
+            let day = record.fetched_at.date();
+            if record.datasource == datasource && from <= day && day <= to {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn load(&self, digest: &str) -> io::Result<String> {
+        fs::read_to_string(self.object_path(digest))
+    }
+
+    fn object_path(&self, digest: &str) -> PathBuf {
+        self.root.join(OBJECTS_DIR).join(&digest[..2]).join(digest)
+    }
+}
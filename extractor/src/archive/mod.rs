@@ -0,0 +1,6 @@
+mod archive;
+
+#[cfg(test)]
+mod tests;
+
+pub use archive::{PageArchive, ArchiveRecord, DEFAULT_ARCHIVE_DIR};
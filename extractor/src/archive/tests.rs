@@ -0,0 +1,41 @@
+use {
+    std::path::PathBuf,
+    chrono::{NaiveDate, Utc},
+    uuid::Uuid,
+    super::PageArchive,
+};
+
+fn temporary_archive_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("archive-{}", Uuid::new_v4()))
+}
+
+#[test]
+fn test_store_and_load() {
+    let dir = temporary_archive_dir();
+    let archive = PageArchive::new(&dir);
+
+    let first = archive.store("datasource.bachtrack_listing", "https://bachtrack.com/concert-event/1", "<html>1</html>", None).unwrap();
+    let again = archive.store("datasource.bachtrack_listing", "https://bachtrack.com/concert-event/1", "<html>1</html>", None).unwrap();
+    let other = archive.store("datasource.bachtrack_discovery", "https://bachtrack.com/find-concerts/", "<html>2</html>", None).unwrap();
+
+    // Identical bodies are stored once
+    assert_eq!(first.digest, again.digest);
+    assert_ne!(first.digest, other.digest);
+    assert_eq!(archive.load(&first.digest).unwrap(), "<html>1</html>");
+
+    let today = Utc::now().naive_utc().date();
+    let records = archive.records("datasource.bachtrack_listing", today, today).unwrap();
+    assert_eq!(records, vec![first, again]);
+
+    let long_ago = NaiveDate::from_ymd(2019, 1, 1);
+    assert_eq!(archive.records("datasource.bachtrack_listing", long_ago, long_ago).unwrap(), vec![]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_empty_archive() {
+    let archive = PageArchive::new(temporary_archive_dir());
+    let today = Utc::now().naive_utc().date();
+    assert_eq!(archive.records("datasource.bachtrack_listing", today, today).unwrap(), vec![]);
+}
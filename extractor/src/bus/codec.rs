@@ -0,0 +1,118 @@
+use {
+    std::io::{self, Read},
+};
+
+// Encoded messages start like a NATS header block, the client we use can't send real headers
+pub const FRAME_PREFIX: &[u8] = b"NATS/1.0\r\n";
+const HEADERS_END: &[u8] = b"\r\n\r\n";
+const CONTENT_ENCODING: &str = "Content-Encoding";
+const CONTENT_LENGTH: &str = "Content-Length";
+const CHUNKS: &str = "Chunks";
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ContentEncoding {
+    Identity,
+    Zstd,
+}
+
+impl ContentEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentEncoding::Identity => "identity",
+            ContentEncoding::Zstd => "zstd",
+        }
+    }
+
+    fn parse(name: &str) -> io::Result<ContentEncoding> {
+        match name {
+            "identity" => Ok(ContentEncoding::Identity),
+            "zstd" => Ok(ContentEncoding::Zstd),
+            _ => Err(invalid_frame(&format!("unsupported content encoding '{}'", name))),
+        }
+    }
+}
+
+// A message whose payload is encoded, carried in the body or, when too large, in chunks stored aside
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub encoding: ContentEncoding,
+    // Of the decoded payload
+    pub content_length: usize,
+    pub chunks: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+impl Frame {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut headers = format!("{}: {}\r\n{}: {}\r\n", CONTENT_ENCODING, self.encoding.name(), CONTENT_LENGTH, self.content_length);
+        if !self.chunks.is_empty() {
+            headers.push_str(&format!("{}: {}\r\n", CHUNKS, self.chunks.join(",")));
+        }
+
+        let mut data = FRAME_PREFIX.to_vec();
+        data.extend_from_slice(headers.as_bytes());
+        data.extend_from_slice(b"\r\n");
+        data.extend_from_slice(&self.body);
+        data
+    }
+
+    // None for plain payloads
+    pub fn parse(data: &[u8]) -> io::Result<Option<Frame>> {
+        if !data.starts_with(FRAME_PREFIX) {
+            return Ok(None);
+        }
+        // From the end of the status line, so a frame without headers still has its blank line
+        let data = &data[FRAME_PREFIX.len() - 2..];
+        let headers_end = data.windows(HEADERS_END.len())
+            .position(|window| window == HEADERS_END)
+            .ok_or(invalid_frame("the headers don't end"))?;
+        let headers = std::str::from_utf8(&data[..headers_end]).map_err(|_| invalid_frame("the headers aren't utf-8"))?;
+
+        let mut frame = Frame{encoding: ContentEncoding::Identity, content_length: 0, chunks: Vec::new(), body: data[headers_end + HEADERS_END.len()..].to_vec()};
+        let mut content_length = None;
+        for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = match line.find(':') {
+                Some(colon) => (line[..colon].trim(), line[colon + 1..].trim()),
+                None => return Err(invalid_frame(&format!("invalid header '{}'", line))),
+            };
+            match name {
+                CONTENT_ENCODING => frame.encoding = ContentEncoding::parse(value)?,
+                CONTENT_LENGTH => content_length = Some(value.parse().map_err(|_| invalid_frame("invalid content length"))?),
+                CHUNKS => frame.chunks = value.split(',').map(|key| key.trim().to_owned()).collect(),
+                // Headers from newer publishers
+                _ => {},
+            }
+        }
+        frame.content_length = content_length.ok_or(invalid_frame("the content length is missing"))?;
+        Ok(Some(frame))
+    }
+}
+
+pub fn compress(payload: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::stream::encode_all(payload, ZSTD_LEVEL)
+}
+
+// Decodes no more than the frame announced, a small message can't expand into an unbounded one.
+// The announced length is only a bound, the buffer grows with what's actually decoded
+pub fn decode(encoding: ContentEncoding, body: Vec<u8>, content_length: usize) -> io::Result<Vec<u8>> {
+    let payload = match encoding {
+        ContentEncoding::Identity => body,
+        ContentEncoding::Zstd => {
+            let mut payload = Vec::new();
+            zstd::stream::read::Decoder::new(&body[..])?
+                .take(content_length as u64 + 1)
+                .read_to_end(&mut payload)
+                .map_err(|e| invalid_frame(&e.to_string()))?;
+            payload
+        },
+    };
+    if payload.len() != content_length {
+        return Err(invalid_frame("the payload doesn't have the announced length"));
+    }
+    Ok(payload)
+}
+
+fn invalid_frame(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid encoded message, {}", reason))
+}
@@ -0,0 +1,162 @@
+use {
+    std::io,
+    std::sync::Arc,
+    std::time::Duration,
+    async_trait::async_trait,
+    futures::future,
+    futures::stream::StreamExt,
+    tracing::warn,
+    crate::metrics,
+    super::message_bus::{MessageBus, Outcome, Subscription, subject_matches},
+    super::codec::{self, Frame, ContentEncoding},
+};
+
+// The default max_payload of a NATS server
+pub const DEFAULT_MAX_PAYLOAD: usize = 1024 * 1024;
+// The extractor consumes these itself
+pub const DEFAULT_ENCODED_SUBJECTS: &str = "datasource.*";
+// Larger announced payloads are rejected before anything is read or allocated for them
+pub const DEFAULT_MAX_DECODED_SIZE: usize = 64 * 1024 * 1024;
+// Smaller payloads aren't worth a frame
+const COMPRESS_ABOVE: usize = 16 * 1024;
+// Room left in every chunked message for the headers
+const HEADERS_ALLOWANCE: usize = 4 * 1024;
+
+pub struct EncodingConfiguration {
+    // Subjects whose consumers decode frames, anything else is published as is for consumers that don't
+    pub subjects: Vec<String>,
+    pub max_payload: usize,
+    pub max_decoded_size: usize,
+}
+
+impl EncodingConfiguration {
+    fn accepts(&self, subject: &str) -> bool {
+        self.subjects.iter().any(|pattern| subject_matches(pattern, subject))
+    }
+}
+
+// Where EncodedBus sets aside the parts of a payload too large for one message for a while
+#[async_trait]
+pub trait ChunkStore: Send + Sync {
+    async fn store_chunk(&self, chunk: &[u8]) -> io::Result<String>;
+    async fn load_chunk(&self, key: &str) -> io::Result<Vec<u8>>;
+}
+
+// Compresses large payloads and sets aside the ones still too large for a message as chunks,
+// consumers get the payloads back as they were published. Plain payloads pass through.
+pub struct EncodedBus<B: MessageBus + ChunkStore> {
+    inner: Arc<B>,
+    configuration: EncodingConfiguration,
+}
+
+impl<B: MessageBus + ChunkStore + 'static> EncodedBus<B> {
+    pub fn new(inner: B, configuration: EncodingConfiguration) -> EncodedBus<B> {
+        EncodedBus{inner: Arc::new(inner), configuration: configuration}
+    }
+
+    async fn encode(&self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        if payload.len() <= COMPRESS_ABOVE {
+            return Ok(payload.to_vec());
+        }
+
+        let compressed = codec::compress(payload)?;
+        let (encoding, body) = if compressed.len() < payload.len() {
+            (ContentEncoding::Zstd, compressed)
+        } else {
+            (ContentEncoding::Identity, payload.to_vec())
+        };
+        let mut frame = Frame{encoding: encoding, content_length: payload.len(), chunks: Vec::new(), body: body};
+
+        if frame.body.len() + HEADERS_ALLOWANCE > self.configuration.max_payload {
+            let chunk_size = self.configuration.max_payload.saturating_sub(HEADERS_ALLOWANCE).max(1);
+            for chunk in frame.body.chunks(chunk_size) {
+                frame.chunks.push(self.inner.store_chunk(chunk).await?);
+            }
+            frame.body = Vec::new();
+            metrics::BUS_CHUNKED_MESSAGES.with_label_values(&["published"]).inc();
+        }
+        metrics::BUS_ENCODED_MESSAGES.with_label_values(&["published", encoding.name()]).inc();
+        Ok(frame.to_bytes())
+    }
+}
+
+async fn decode<B: ChunkStore>(inner: &B, data: &[u8], max_decoded_size: usize) -> io::Result<Vec<u8>> {
+    let frame = match Frame::parse(data)? {
+        Some(frame) => frame,
+        None => return Ok(data.to_vec()),
+    };
+    if frame.content_length > max_decoded_size {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid encoded message, announced {} bytes, more than the {} allowed", frame.content_length, max_decoded_size)));
+    }
+
+    let mut body = frame.body;
+    for key in &frame.chunks {
+        body.extend(inner.load_chunk(key).await?);
+        // Chunks of a payload within bounds can't add up to much more than it
+        if body.len() > max_decoded_size + HEADERS_ALLOWANCE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid encoded message, its chunks are larger than announced"));
+        }
+    }
+    if !frame.chunks.is_empty() {
+        metrics::BUS_CHUNKED_MESSAGES.with_label_values(&["consumed"]).inc();
+    }
+    metrics::BUS_ENCODED_MESSAGES.with_label_values(&["consumed", frame.encoding.name()]).inc();
+    codec::decode(frame.encoding, body, frame.content_length)
+}
+
+#[async_trait]
+impl<B: MessageBus + ChunkStore + 'static> MessageBus for EncodedBus<B> {
+    async fn subscribe(&self, subject: &str, queue_group: &str) -> io::Result<Subscription> {
+        let inner = Arc::clone(&self.inner);
+        let max_decoded_size = self.configuration.max_decoded_size;
+        let subscription = self.inner.subscribe(subject, queue_group).await?;
+
+        Ok(subscription.map_deliveries(move |deliveries| deliveries.then(move |delivery| {
+            let inner = Arc::clone(&inner);
+            async move {
+                metrics::BUS_PAYLOAD_BYTES.with_label_values(&["consumed", "wire"]).observe(delivery.data.len() as f64);
+                match decode(inner.as_ref(), &delivery.data, max_decoded_size).await {
+                    Ok(data) => {
+                        metrics::BUS_PAYLOAD_BYTES.with_label_values(&["consumed", "payload"]).observe(data.len() as f64);
+                        Some(delivery.with_data(data))
+                    },
+                    Err(e) => {
+                        warn!(subject = %delivery.subject, error = %e, "Error decoding a message");
+                        metrics::ERRORS.with_label_values(&["decode"]).inc();
+                        // Missing chunks may still be readable later, a broken frame won't be
+                        let outcome = match e.kind() {
+                            io::ErrorKind::InvalidData => Outcome::Fail(e.to_string()),
+                            _ => Outcome::Retry(e.to_string()),
+                        };
+                        if let Err(e) = delivery.settle(&outcome).await {
+                            warn!(subject = %delivery.subject, error = %e, "Error settling an undecodable message");
+                        }
+                        None
+                    },
+                }
+            }
+        }).filter_map(future::ready).boxed()))
+    }
+
+    async fn publish(&self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        metrics::BUS_PAYLOAD_BYTES.with_label_values(&["published", "payload"]).observe(payload.len() as f64);
+        let data = if self.configuration.accepts(subject) {
+            self.encode(payload).await?
+        } else {
+            if payload.len() > self.configuration.max_payload {
+                warn!(%subject, size = payload.len(), "Publishing a payload over max_payload to a subject without encoding");
+            }
+            payload.to_vec()
+        };
+        metrics::BUS_PAYLOAD_BYTES.with_label_values(&["published", "wire"]).observe(data.len() as f64);
+        self.inner.publish(subject, &data).await
+    }
+
+    async fn request(&self, subject: &str, payload: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+        self.inner.request(subject, payload, timeout).await
+    }
+
+    async fn close(&self) -> io::Result<()> {
+        self.inner.close().await
+    }
+}
@@ -0,0 +1,235 @@
+use {
+    std::io,
+    std::sync::{Arc, Mutex},
+    std::time::Duration,
+    async_trait::async_trait,
+    futures::channel::{mpsc, oneshot},
+    futures::stream::StreamExt,
+    crate::jetstream::{DeadLetter, MAX_DELIVER},
+    super::message_bus::{MessageBus, Outcome, Delivery, DeliveryHandle, Subscription, SubscriptionHandle, subject_matches},
+    super::encoded_bus::ChunkStore,
+};
+
+// An in-process broker with the delivery guarantees of the JetStream setup: 
+// a queue group sees every message published since it first subscribed, like a new durable,
+// and keeps them while no member listens. Retries are redelivered and messages failing
+// for good end up on `dlq.<subject>`.
+#[derive(Clone)]
+pub struct InMemoryBus {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    groups: Vec<Group>,
+    chunks: Vec<Vec<u8>>,
+    subscriptions: usize,
+}
+
+struct Group {
+    pattern: String,
+    name: String,
+    // Keyed by subscription, so a drained one can be told apart
+    members: Vec<(usize, mpsc::UnboundedSender<Delivery>)>,
+    next_member: usize,
+    // Deliveries waiting for a member to subscribe
+    pending: Vec<Delivery>,
+}
+
+type Reply = Arc<Mutex<Option<oneshot::Sender<Vec<u8>>>>>;
+
+impl InMemoryBus {
+    pub fn new() -> InMemoryBus {
+        InMemoryBus{state: Arc::new(Mutex::new(State::default()))}
+    }
+
+    fn publish_now(&self, subject: &str, payload: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        for group in 0..state.groups.len() {
+            if subject_matches(&state.groups[group].pattern, subject) {
+                let delivery = self.delivery(group, subject, payload, 1, None);
+                dispatch(&mut state.groups[group], delivery);
+            }
+        }
+    }
+
+    fn delivery(&self, group: usize, subject: &str, payload: &[u8], deliveries: i64, reply: Option<Reply>) -> Delivery {
+        let handle = MemoryHandle{
+            bus: self.clone(),
+            group: group,
+            subject: subject.to_owned(),
+            data: payload.to_vec(),
+            deliveries: deliveries,
+            reply: reply,
+        };
+        Delivery::new(subject.to_owned(), payload.to_vec(), Box::new(handle))
+    }
+}
+
+// Round robin over the members still listening
+fn dispatch(group: &mut Group, delivery: Delivery) {
+    let mut delivery = delivery;
+    while !group.members.is_empty() {
+        let member = group.next_member % group.members.len();
+        match group.members[member].1.unbounded_send(delivery) {
+            Ok(_) => {
+                group.next_member = member + 1;
+                return;
+            },
+            Err(e) => {
+                delivery = e.into_inner();
+                group.members.remove(member);
+            },
+        }
+    }
+    group.pending.push(delivery);
+}
+
+#[async_trait]
+impl MessageBus for InMemoryBus {
+    async fn subscribe(&self, subject: &str, queue_group: &str) -> io::Result<Subscription> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut state = self.state.lock().unwrap();
+
+        let group = match state.groups.iter().position(|group| group.pattern == subject && group.name == queue_group) {
+            Some(group) => group,
+            None => {
+                state.groups.push(Group{
+                    pattern: subject.to_owned(),
+                    name: queue_group.to_owned(),
+                    members: Vec::new(),
+                    next_member: 0,
+                    pending: Vec::new(),
+                });
+                state.groups.len() - 1
+            },
+        };
+
+        state.subscriptions += 1;
+        let handle = MemorySubscription{bus: self.clone(), group: group, id: state.subscriptions};
+
+        let group = &mut state.groups[group];
+        group.members.push((handle.id, sender));
+        for delivery in std::mem::replace(&mut group.pending, Vec::new()) {
+            dispatch(group, delivery);
+        }
+
+        Ok(Subscription::new(receiver.boxed(), Arc::new(handle)))
+    }
+
+    async fn publish(&self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        self.publish_now(subject, payload);
+        Ok(())
+    }
+
+    async fn request(&self, subject: &str, payload: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+        let (sender, receiver) = oneshot::channel();
+        let reply: Reply = Arc::new(Mutex::new(Some(sender)));
+        {
+            let mut state = self.state.lock().unwrap();
+            let mut responders = 0;
+            for group in 0..state.groups.len() {
+                if subject_matches(&state.groups[group].pattern, subject) && !state.groups[group].members.is_empty() {
+                    let delivery = self.delivery(group, subject, payload, 1, Some(Arc::clone(&reply)));
+                    dispatch(&mut state.groups[group], delivery);
+                    responders += 1;
+                }
+            }
+            if responders == 0 {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("no responders on '{}'", subject)));
+            }
+        }
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(io::Error::new(io::ErrorKind::BrokenPipe, format!("request on '{}' was dropped", subject))),
+            Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, format!("no response on '{}'", subject))),
+        }
+    }
+
+    async fn close(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for group in state.groups.iter_mut() {
+            group.members.clear();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChunkStore for InMemoryBus {
+    async fn store_chunk(&self, chunk: &[u8]) -> io::Result<String> {
+        let mut state = self.state.lock().unwrap();
+        state.chunks.push(chunk.to_vec());
+        Ok((state.chunks.len() - 1).to_string())
+    }
+
+    async fn load_chunk(&self, key: &str) -> io::Result<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        key.parse::<usize>().ok()
+            .and_then(|index| state.chunks.get(index).cloned())
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, format!("no chunk '{}'", key)))
+    }
+}
+
+struct MemorySubscription {
+    bus: InMemoryBus,
+    group: usize,
+    id: usize,
+}
+
+#[async_trait]
+impl SubscriptionHandle for MemorySubscription {
+    // Leaves the group, its other members get the following deliveries
+    async fn drain(&self) -> io::Result<()> {
+        let mut state = self.bus.state.lock().unwrap();
+        state.groups[self.group].members.retain(|(id, _)| *id != self.id);
+        Ok(())
+    }
+}
+
+struct MemoryHandle {
+    bus: InMemoryBus,
+    group: usize,
+    subject: String,
+    data: Vec<u8>,
+    deliveries: i64,
+    reply: Option<Reply>,
+}
+
+#[async_trait]
+impl DeliveryHandle for MemoryHandle {
+    async fn settle(&self, outcome: &Outcome) -> io::Result<()> {
+        // Requests aren't stored, like core NATS messages
+        if self.reply.is_some() {
+            return Ok(());
+        }
+
+        match outcome {
+            Outcome::Done => {},
+            Outcome::Retry(_) if self.deliveries < MAX_DELIVER => {
+                let delivery = self.bus.delivery(self.group, &self.subject, &self.data, self.deliveries + 1, None);
+                dispatch(&mut self.bus.state.lock().unwrap().groups[self.group], delivery);
+            },
+            Outcome::Retry(reason) | Outcome::Fail(reason) => {
+                let dead_letter = DeadLetter::new(&self.subject, reason, self.deliveries, &self.data);
+                self.bus.publish_now(&format!("dlq.{}", self.subject), &serde_json::to_vec(&dead_letter)?);
+            },
+        };
+        Ok(())
+    }
+
+    // Deliveries aren't redelivered until they're settled
+    async fn progress(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn respond(&self, payload: &[u8]) -> io::Result<()> {
+        let sender = self.reply.as_ref().and_then(|reply| reply.lock().unwrap().take());
+        match sender {
+            // The requester may have timed out already
+            Some(sender) => Ok(sender.send(payload.to_vec()).unwrap_or(())),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "message wasn't a request or was already responded to")),
+        }
+    }
+}
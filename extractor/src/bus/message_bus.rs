@@ -0,0 +1,130 @@
+use {
+    std::io,
+    std::pin::Pin,
+    std::sync::Arc,
+    std::future::Future,
+    std::task::{Context, Poll},
+    std::time::Duration,
+    async_trait::async_trait,
+    futures::future,
+    futures::stream::{Stream, StreamExt, BoxStream},
+    tracing::warn,
+};
+
+#[async_trait]
+pub trait MessageBus: Send + Sync {
+    // Members of the same queue group share the deliveries, each one has to be settled
+    async fn subscribe(&self, subject: &str, queue_group: &str) -> io::Result<Subscription>;
+    // Returns once the bus accepted the message
+    async fn publish(&self, subject: &str, payload: &[u8]) -> io::Result<()>;
+    async fn request(&self, subject: &str, payload: &[u8], timeout: Duration) -> io::Result<Vec<u8>>;
+    async fn close(&self) -> io::Result<()>;
+}
+
+// How a subscription is ended depends on the bus it's on
+#[async_trait]
+pub trait SubscriptionHandle: Send + Sync {
+    // Stops new deliveries, the ones already received still come out of the subscription before it ends
+    async fn drain(&self) -> io::Result<()>;
+}
+
+pub struct Subscription {
+    deliveries: BoxStream<'static, Delivery>,
+    handle: Arc<dyn SubscriptionHandle>,
+}
+
+impl Subscription {
+    pub fn new(deliveries: BoxStream<'static, Delivery>, handle: Arc<dyn SubscriptionHandle>) -> Subscription {
+        Subscription{deliveries: deliveries, handle: handle}
+    }
+
+    // The same subscription with its deliveries passed through the given adapter, e.g. to decode them
+    pub fn map_deliveries<F>(self, adapt: F) -> Subscription
+        where F: FnOnce(BoxStream<'static, Delivery>) -> BoxStream<'static, Delivery> {
+        Subscription{deliveries: adapt(self.deliveries), handle: self.handle}
+    }
+
+    // Drains the subscription once stop completes, so nothing it received is lost.
+    // If it can't be drained the deliveries end right away
+    pub fn drain_when<F: Future<Output = ()> + Send + 'static>(self, stop: F) -> BoxStream<'static, Delivery> {
+        let handle = self.handle;
+        let stopped = async move {
+            stop.await;
+            match handle.drain().await {
+                Ok(_) => future::pending().await,
+                Err(e) => warn!(error = %e, "Error draining a subscription, the messages it received will be redelivered or lost"),
+            }
+        };
+        self.deliveries.take_until(stopped).boxed()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Delivery;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Delivery>> {
+        self.deliveries.poll_next_unpin(cx)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    Done,
+    // Worth another delivery, e.g. the page or the bus were unreachable
+    Retry(String),
+    // Will fail the same way on every delivery
+    Fail(String),
+}
+
+// How a delivery is acknowledged or answered depends on the bus it came from
+#[async_trait]
+pub trait DeliveryHandle: Send + Sync {
+    async fn settle(&self, outcome: &Outcome) -> io::Result<()>;
+    // Keeps an unsettled delivery from being redelivered while it's still worked on
+    async fn progress(&self) -> io::Result<()>;
+    async fn respond(&self, payload: &[u8]) -> io::Result<()>;
+}
+
+pub struct Delivery {
+    pub subject: String,
+    pub data: Vec<u8>,
+    handle: Box<dyn DeliveryHandle>,
+}
+
+impl Delivery {
+    pub fn new(subject: String, data: Vec<u8>, handle: Box<dyn DeliveryHandle>) -> Delivery {
+        Delivery{subject: subject, data: data, handle: handle}
+    }
+
+    // The same delivery with its payload decoded
+    pub fn with_data(self, data: Vec<u8>) -> Delivery {
+        Delivery{data: data, ..self}
+    }
+
+    pub async fn settle(&self, outcome: &Outcome) -> io::Result<()> {
+        self.handle.settle(outcome).await
+    }
+
+    pub async fn progress(&self) -> io::Result<()> {
+        self.handle.progress().await
+    }
+
+    pub async fn respond(&self, payload: &[u8]) -> io::Result<()> {
+        self.handle.respond(payload).await
+    }
+}
+
+// NATS subject matching, `*` matches a single token and a trailing `>` the rest
+pub fn subject_matches(pattern: &str, subject: &str) -> bool {
+    let mut subject_tokens = subject.split('.');
+    for pattern_token in pattern.split('.') {
+        match (pattern_token, subject_tokens.next()) {
+            (">", Some(_)) => return true,
+            (_, None) => return false,
+            ("*", Some(_)) => continue,
+            (pattern_token, Some(subject_token)) if pattern_token == subject_token => continue,
+            _ => return false,
+        }
+    }
+    subject_tokens.next().is_none()
+}
@@ -0,0 +1,14 @@
+// Everything the extractor needs from a broker, so workers can run against NATS or in process.
+mod message_bus;
+mod nats_bus;
+mod memory_bus;
+mod codec;
+mod encoded_bus;
+
+#[cfg(test)]
+mod tests;
+
+pub use message_bus::{MessageBus, Delivery, Outcome};
+pub use nats_bus::NatsBus;
+pub use memory_bus::InMemoryBus;
+pub use encoded_bus::{EncodedBus, EncodingConfiguration, DEFAULT_MAX_PAYLOAD, DEFAULT_ENCODED_SUBJECTS, DEFAULT_MAX_DECODED_SIZE};
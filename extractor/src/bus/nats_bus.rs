@@ -0,0 +1,185 @@
+use {
+    std::io,
+    std::sync::Arc,
+    std::time::Duration,
+    async_trait::async_trait,
+    futures::stream::{self, Stream, StreamExt},
+    tracing::error,
+    crate::metrics,
+    crate::nats::asynk::{self as nats_client, Connection, Message},
+    crate::jetstream::{self, PullConsumer},
+    super::message_bus::{MessageBus, Outcome, Delivery, DeliveryHandle, Subscription, SubscriptionHandle, subject_matches},
+    super::encoded_bus::ChunkStore,
+};
+
+const STREAMS: [(&str, &str); 3] = [jetstream::DATASOURCE_STREAM, jetstream::NORMALIZER_STREAM, jetstream::DEAD_LETTER_STREAM];
+// Work left unconsumed for a week isn't worth doing anymore, dead letters are kept until they're looked at
+const STREAM_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const CHUNK_SUBJECT: &str = "chunks.payload";
+// A message is read up to STREAM_MAX_AGE after it was published, its chunks have to last as long.
+// Dead letters referring to chunks can only be replayed within that time
+const CHUNK_MAX_AGE: Duration = STREAM_MAX_AGE;
+
+// Subjects captured by a JetStream stream are consumed through durable pull consumers,
+// anything else is plain core NATS.
+pub struct NatsBus {
+    nc: Connection,
+    // How many messages a subscription keeps in flight
+    prefetch: usize,
+}
+
+impl NatsBus {
+    pub async fn connect(url: &str, prefetch: usize) -> io::Result<NatsBus> {
+        let nc = nats_client::connect(url).await?;
+        jetstream::ensure_expiring_stream(&nc, jetstream::DATASOURCE_STREAM, STREAM_MAX_AGE).await?;
+        jetstream::ensure_expiring_stream(&nc, jetstream::NORMALIZER_STREAM, STREAM_MAX_AGE).await?;
+        jetstream::ensure_stream(&nc, jetstream::DEAD_LETTER_STREAM).await?;
+        jetstream::ensure_expiring_stream(&nc, jetstream::CHUNK_STREAM, CHUNK_MAX_AGE).await?;
+        Ok(NatsBus{nc: nc, prefetch: prefetch})
+    }
+
+    fn stream_of(subject: &str) -> Option<&'static str> {
+        STREAMS.iter()
+            .find(|(_, pattern)| subject_matches(pattern, subject))
+            .map(|(name, _)| *name)
+    }
+}
+
+#[async_trait]
+impl MessageBus for NatsBus {
+    async fn subscribe(&self, subject: &str, queue_group: &str) -> io::Result<Subscription> {
+        let stream = match NatsBus::stream_of(subject) {
+            Some(stream) => stream,
+            None => {
+                let nc = self.nc.clone();
+                let subscription = Arc::new(self.nc.queue_subscribe(subject, queue_group).await?);
+                let deliveries = messages(Arc::clone(&subscription)).map(move |message| {
+                    let handle = CoreHandle{nc: nc.clone(), message: message.clone()};
+                    Delivery::new(message.subject, message.data, Box::new(handle))
+                });
+                return Ok(Subscription::new(deliveries.boxed(), Arc::new(NatsSubscription{subscription: subscription})));
+            },
+        };
+
+        let consumer = Arc::new(PullConsumer::create(&self.nc, stream, subject, queue_group).await?);
+        let exhausted = consumer.subscribe_exhausted(&self.nc, queue_group).await?;
+        let nc = self.nc.clone();
+        tokio::spawn(async move {
+            while let Some(advisory) = exhausted.next().await {
+                if let Err(e) = jetstream::dead_letter_exhausted(&nc, &advisory.data).await {
+                    error!(error = %e, "Error dead lettering a message that ran out of deliveries");
+                    metrics::ERRORS.with_label_values(&["dead_letter"]).inc();
+                }
+            }
+        });
+
+        let subscription = Arc::new(consumer.subscribe(&self.nc).await?);
+        // Every settled message pulls the next one
+        consumer.pull(&self.nc, self.prefetch).await?;
+
+        let nc = self.nc.clone();
+        let deliveries = messages(Arc::clone(&subscription)).map(move |message| {
+            let handle = JetStreamHandle{nc: nc.clone(), consumer: Arc::clone(&consumer), message: message.clone()};
+            Delivery::new(message.subject, message.data, Box::new(handle))
+        });
+        Ok(Subscription::new(deliveries.boxed(), Arc::new(NatsSubscription{subscription: subscription})))
+    }
+
+    async fn publish(&self, subject: &str, payload: &[u8]) -> io::Result<()> {
+        match NatsBus::stream_of(subject) {
+            Some(_) => jetstream::publish(&self.nc, subject, payload).await,
+            None => self.nc.publish(subject, payload).await,
+        }
+    }
+
+    async fn request(&self, subject: &str, payload: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+        match tokio::time::timeout(timeout, self.nc.request(subject, payload)).await {
+            Ok(response) => Ok(response?.data),
+            Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, format!("no response on '{}'", subject))),
+        }
+    }
+
+    async fn close(&self) -> io::Result<()> {
+        self.nc.flush().await?;
+        self.nc.close().await
+    }
+}
+
+#[async_trait]
+impl ChunkStore for NatsBus {
+    // Chunks are keyed by their sequence in the chunk stream
+    async fn store_chunk(&self, chunk: &[u8]) -> io::Result<String> {
+        Ok(jetstream::store(&self.nc, CHUNK_SUBJECT, chunk).await?.to_string())
+    }
+
+    async fn load_chunk(&self, key: &str) -> io::Result<Vec<u8>> {
+        let sequence = key.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid chunk key '{}'", key)))?;
+        jetstream::get(&self.nc, jetstream::CHUNK_STREAM.0, sequence).await
+    }
+}
+
+// The subscription's messages, while it can still be drained through the other reference
+fn messages(subscription: Arc<nats_client::Subscription>) -> impl Stream<Item = Message> {
+    stream::unfold(subscription, |subscription| async move {
+        subscription.next().await.map(|message| (message, subscription))
+    })
+}
+
+struct NatsSubscription {
+    subscription: Arc<nats_client::Subscription>,
+}
+
+#[async_trait]
+impl SubscriptionHandle for NatsSubscription {
+    // Unsubscribes, the messages already sent to us still arrive. Unsettled stream messages left in flight
+    // are redelivered to the group's other members once their ack wait passes
+    async fn drain(&self) -> io::Result<()> {
+        self.subscription.drain().await
+    }
+}
+
+struct JetStreamHandle {
+    nc: Connection,
+    consumer: Arc<PullConsumer>,
+    message: Message,
+}
+
+#[async_trait]
+impl DeliveryHandle for JetStreamHandle {
+    async fn settle(&self, outcome: &Outcome) -> io::Result<()> {
+        jetstream::settle(&self.nc, &self.message, outcome).await?;
+        self.consumer.pull(&self.nc, 1).await
+    }
+
+    async fn progress(&self) -> io::Result<()> {
+        jetstream::in_progress(&self.nc, &self.message).await
+    }
+
+    async fn respond(&self, _payload: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "stream messages can't be responded to"))
+    }
+}
+
+// Core NATS has no acknowledgements, a lost message is lost
+struct CoreHandle {
+    nc: Connection,
+    message: Message,
+}
+
+#[async_trait]
+impl DeliveryHandle for CoreHandle {
+    async fn settle(&self, _outcome: &Outcome) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn progress(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    async fn respond(&self, payload: &[u8]) -> io::Result<()> {
+        match &self.message.reply {
+            Some(reply) => self.nc.publish(reply, payload).await,
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "message wasn't a request")),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use {
+    std::time::Duration,
+    futures::stream::StreamExt,
+    crate::jetstream::{DeadLetter, MAX_DELIVER},
+    super::{MessageBus, InMemoryBus, EncodedBus, EncodingConfiguration, Outcome},
+    super::message_bus::subject_matches,
+    super::codec::{self, Frame, ContentEncoding, FRAME_PREFIX},
+};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+#[test]
+fn test_subject_matches() {
+    let cases = vec![
+        ("datasource.*", "datasource.bachtrack_listing", true),
+        ("datasource.*", "datasource", false),
+        ("datasource.*", "datasource.bachtrack_listing.sync", false),
+        ("normalizer.>", "normalizer.event.music", true),
+        ("normalizer.>", "normalizer", false),
+        ("normalizer.venue", "normalizer.venue", true),
+        ("normalizer.venue", "normalizer.city", false),
+        ("*.venue", "normalizer.venue", true),
+    ];
+
+    for (pattern, subject, expected) in cases {
+        assert_eq!(subject_matches(pattern, subject), expected, "matching '{}' against '{}'", subject, pattern);
+    }
+}
+
+#[tokio::test]
+async fn test_new_groups_start_at_new_messages() {
+    let bus = InMemoryBus::new();
+    bus.publish("normalizer.venue", b"Wigmore Hall").await.unwrap();
+
+    // The group keeps its messages while nobody listens
+    drop(bus.subscribe("normalizer.>", "normalizer").await.unwrap());
+    bus.publish("normalizer.city", b"London").await.unwrap();
+
+    let mut subscription = bus.subscribe("normalizer.>", "normalizer").await.unwrap();
+    assert_eq!(subscription.next().await.unwrap().data, b"London".to_vec());
+    assert!(tokio::time::timeout(TIMEOUT, subscription.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_queue_group_members_share_messages() {
+    let bus = InMemoryBus::new();
+    let mut first_member = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut second_member = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut other_group = bus.subscribe("datasource.*", "archive").await.unwrap();
+
+    bus.publish("datasource.bachtrack_listing", b"first").await.unwrap();
+    bus.publish("datasource.bachtrack_listing", b"second").await.unwrap();
+
+    assert_eq!(first_member.next().await.unwrap().data, b"first".to_vec());
+    assert_eq!(second_member.next().await.unwrap().data, b"second".to_vec());
+    assert_eq!(other_group.next().await.unwrap().data, b"first".to_vec());
+    assert_eq!(other_group.next().await.unwrap().data, b"second".to_vec());
+}
+
+#[tokio::test]
+async fn test_drained_members_keep_what_they_received() {
+    let bus = InMemoryBus::new();
+    let first_member = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut second_member = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    bus.publish("datasource.bachtrack_listing", b"first").await.unwrap();
+
+    let (stop, stopped) = futures::channel::oneshot::channel::<()>();
+    let mut first_member = first_member.drain_when(async { let _ = stopped.await; });
+    stop.send(()).unwrap();
+    // The drained member ends after the message it already had, the other one gets the rest
+    assert_eq!(first_member.next().await.unwrap().data, b"first".to_vec());
+    bus.publish("datasource.bachtrack_listing", b"second").await.unwrap();
+    bus.publish("datasource.bachtrack_listing", b"third").await.unwrap();
+
+    assert!(first_member.next().await.is_none());
+    assert_eq!(second_member.next().await.unwrap().data, b"second".to_vec());
+    assert_eq!(second_member.next().await.unwrap().data, b"third".to_vec());
+}
+
+#[tokio::test]
+async fn test_retries_end_in_dead_letter_queue() {
+    let bus = InMemoryBus::new();
+    let mut subscription = bus.subscribe("datasource.bachtrack_listing", "extractor").await.unwrap();
+    let mut dead_letters = bus.subscribe("dlq.>", "monitoring").await.unwrap();
+
+    bus.publish("datasource.bachtrack_listing", b"/concert-event/1").await.unwrap();
+    for _ in 0..MAX_DELIVER {
+        let delivery = subscription.next().await.unwrap();
+        delivery.settle(&Outcome::Retry("timed out".to_owned())).await.unwrap();
+    }
+
+    let dead_letter: DeadLetter = serde_json::from_slice(&dead_letters.next().await.unwrap().data).unwrap();
+    assert_eq!(dead_letter, DeadLetter::new("datasource.bachtrack_listing", "timed out", MAX_DELIVER, b"/concert-event/1"));
+}
+
+#[tokio::test]
+async fn test_request() {
+    let bus = InMemoryBus::new();
+    assert!(bus.request("datasource.bachtrack_listing.sync", b"ping", TIMEOUT).await.is_err());
+
+    let mut subscription = bus.subscribe("datasource.bachtrack_listing.sync", "extractor").await.unwrap();
+    let responder = async {
+        let delivery = subscription.next().await.unwrap();
+        delivery.respond(b"pong").await.unwrap();
+    };
+    let (response, _) = futures::join!(bus.request("datasource.bachtrack_listing.sync", b"ping", TIMEOUT), responder);
+
+    assert_eq!(response.unwrap(), b"pong".to_vec());
+}
+
+fn encoded_bus(inner: &InMemoryBus, max_payload: usize) -> EncodedBus<InMemoryBus> {
+    EncodedBus::new(inner.clone(), EncodingConfiguration{subjects: vec!["datasource.*".to_owned()], max_payload: max_payload, max_decoded_size: 1024 * 1024})
+}
+
+// Bytes zstd can't make smaller
+fn incompressible(size: usize) -> Vec<u8> {
+    let mut state: u32 = 2463534242;
+    (0..size).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    }).collect()
+}
+
+#[test]
+fn test_frames() {
+    let frame = Frame{encoding: ContentEncoding::Zstd, content_length: 42, chunks: vec!["7".to_owned(), "8".to_owned()], body: Vec::new()};
+    assert_eq!(Frame::parse(&frame.to_bytes()).unwrap(), Some(frame));
+
+    assert_eq!(Frame::parse(b"/concert-event/1").unwrap(), None);
+    assert!(Frame::parse(b"NATS/1.0\r\nContent-Encoding: zstd\r\n").is_err());
+    assert!(Frame::parse(b"NATS/1.0\r\nContent-Encoding: gzip\r\nContent-Length: 1\r\n\r\nx").is_err());
+    assert!(Frame::parse(b"NATS/1.0\r\nContent-Encoding: zstd\r\n\r\nx").is_err());
+}
+
+#[tokio::test]
+async fn test_encoded_payloads_round_trip() {
+    let inner = InMemoryBus::new();
+    let bus = encoded_bus(&inner, 64 * 1024);
+    let mut subscription = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut wire = inner.subscribe("datasource.*", "wire").await.unwrap();
+
+    let small = b"/concert-event/1".to_vec();
+    let compressible = "<tr><td>Mozart, Wolfgang Amadeus</td></tr>".repeat(10_000).into_bytes();
+    let large = incompressible(200 * 1024);
+    for payload in &[&small, &compressible, &large] {
+        bus.publish("datasource.bachtrack_listing", payload).await.unwrap();
+    }
+
+    assert_eq!(wire.next().await.unwrap().data, small);
+    let compressed = wire.next().await.unwrap().data;
+    assert!(compressed.starts_with(FRAME_PREFIX) && compressed.len() < compressible.len() / 10);
+    let chunked = Frame::parse(&wire.next().await.unwrap().data).unwrap().unwrap();
+    assert_eq!(chunked.encoding, ContentEncoding::Identity);
+    assert_eq!(chunked.chunks.len(), 4);
+
+    assert_eq!(subscription.next().await.unwrap().data, small);
+    assert_eq!(subscription.next().await.unwrap().data, compressible);
+    assert_eq!(subscription.next().await.unwrap().data, large);
+}
+
+#[tokio::test]
+async fn test_subjects_without_encoding_are_published_as_is() {
+    let inner = InMemoryBus::new();
+    let bus = encoded_bus(&inner, 64 * 1024);
+    let mut wire = inner.subscribe("normalizer.>", "normalizer").await.unwrap();
+
+    let festival = "{\"name\": \"Salzburger Festspiele\"}".repeat(1_000).into_bytes();
+    bus.publish("normalizer.festival", &festival).await.unwrap();
+
+    assert_eq!(wire.next().await.unwrap().data, festival);
+}
+
+#[tokio::test]
+async fn test_undecodable_messages_are_dead_lettered() {
+    let inner = InMemoryBus::new();
+    let bus = encoded_bus(&inner, 64 * 1024);
+    let mut subscription = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut dead_letters = inner.subscribe("dlq.>", "monitoring").await.unwrap();
+
+    let broken = Frame{encoding: ContentEncoding::Zstd, content_length: 10, chunks: Vec::new(), body: b"not zstd".to_vec()};
+    inner.publish("datasource.bachtrack_listing", &broken.to_bytes()).await.unwrap();
+    bus.publish("datasource.bachtrack_listing", b"/concert-event/1").await.unwrap();
+
+    assert_eq!(subscription.next().await.unwrap().data, b"/concert-event/1".to_vec());
+    let dead_letter: DeadLetter = serde_json::from_slice(&dead_letters.next().await.unwrap().data).unwrap();
+    assert_eq!(dead_letter.subject, "datasource.bachtrack_listing");
+    assert_eq!(dead_letter.deliveries, 1);
+    // Kept as it was published, not as text
+    assert_eq!(dead_letter.data().unwrap(), broken.to_bytes());
+}
+
+#[tokio::test]
+async fn test_oversized_messages_are_dead_lettered() {
+    let inner = InMemoryBus::new();
+    let bus = encoded_bus(&inner, 64 * 1024);
+    let mut subscription = bus.subscribe("datasource.*", "extractor").await.unwrap();
+    let mut dead_letters = inner.subscribe("dlq.>", "monitoring").await.unwrap();
+
+    // Announces far more than it carries, nothing is allocated for it
+    let oversized = Frame{encoding: ContentEncoding::Zstd, content_length: usize::MAX, chunks: Vec::new(), body: codec::compress(b"x").unwrap()};
+    inner.publish("datasource.bachtrack_listing", &oversized.to_bytes()).await.unwrap();
+    bus.publish("datasource.bachtrack_listing", b"/concert-event/1").await.unwrap();
+
+    assert_eq!(subscription.next().await.unwrap().data, b"/concert-event/1".to_vec());
+    let dead_letter: DeadLetter = serde_json::from_slice(&dead_letters.next().await.unwrap().data).unwrap();
+    assert!(dead_letter.reason.contains("more than the 1048576 allowed"));
+}
+
@@ -1,14 +1,22 @@
 use {
-    std::str,
+    std::time::Duration,
+    std::collections::HashSet,
     scraper::Html,
     scraper::Selector,
     async_trait::async_trait,
-    crate::model::{Extracted, Datasource, ExtractResult, Configuration},
+    crate::model::{Extracted, Datasource, ExtractResult, ExtractionReport, Configuration},
+    crate::model::quality,
+    crate::model::links,
+    crate::model::charset,
     crate::model::http_client::{HttpClient},
+    tracing::debug,
+    super::search::SearchConfiguration,
 };
 
 pub const DS_NAME: &str = "datasource.bachtrack_discovery";
 
+const LISTING_SELECTOR: &str = "a.listing-more-info";
+
 #[derive(Copy, Clone)]
 pub struct DS<H: HttpClient>{
     pub http_client: H,
@@ -23,33 +31,90 @@ impl<H: HttpClient> DS<H>{
 #[async_trait]
 impl<H: HttpClient + Send + Sync> Datasource for DS<H>{
     async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
-        println!("extracting with configuration {}", str::from_utf8(&configuration)?);
-        let webpage: String = self.http_client.get(str::from_utf8(&configuration)?).await?;
-        Ok(parse_bachtrack_html(&webpage)?)
+        let configuration = charset::decode_text(&configuration);
+        debug!(configuration = %configuration, "extracting");
+        let urls = match serde_json::from_str::<SearchConfiguration>(&configuration) {
+            Ok(search) => search.search_urls()?,
+            // A plain url is searched as is
+            Err(_) => vec![configuration],
+        };
+
+        let mut report = ExtractionReport::new(Vec::new());
+        for (index, url) in urls.iter().enumerate() {
+            let webpage: String = self.http_client.get(url).await?;
+            let ExtractionReport{items, unmatched_selectors, warnings, parsed_fields, ..} = parse_bachtrack_html(&webpage, url)?;
+            report.warnings.extend(warnings);
+            report.parsed_fields += parsed_fields;
+
+            // Listings spanning several windows show up in each of them
+            for item in items {
+                if !report.items.contains(&item) {
+                    report.items.push(item);
+                }
+            }
+            // A window without results is normal, a selector is only missing when no window matched it
+            if index == 0 {
+                report.unmatched_selectors = unmatched_selectors;
+            } else {
+                report.unmatched_selectors.retain(|selector| unmatched_selectors.contains(selector));
+            }
+        }
+        Ok(report)
+    }
+
+    // Without the url it was fetched from, relative links resolve against the site
+    fn parse(&self, webpage: &str) -> ExtractResult{
+        parse_bachtrack_html(webpage, super::super::listing::BASE_URL)
     }
     
     fn get_name(&self) -> String{
         DS_NAME.to_owned()
     }
+
+    fn paused(&self) -> Option<Duration>{
+        self.http_client.paused()
+    }
 } 
 
-pub fn parse_bachtrack_html(body: &str) -> ExtractResult {
-    let mut listings: Vec<Extracted> = Vec::new();
+pub fn parse_bachtrack_html(body: &str, page_url: &str) -> ExtractResult {
+    let mut report = ExtractionReport::new(Vec::new());
+    let mut listing_ids = HashSet::new();
 
     let document = Html::parse_document(&body);
-    let selector = Selector::parse("a.listing-more-info").unwrap();
+    let base_url = links::page_base_url(&document, page_url);
+    let selector = Selector::parse(LISTING_SELECTOR).unwrap();
 
     for element in document.select(&selector) {
-        let listing_url = match element.value().attr("href"){
+        let href = match element.value().attr("href"){
             Some(value) => value,
             None => {
-                println!("Couldn't not find listing url");
+                report.field_failed("listing_url", LISTING_SELECTOR, &element.html(), "the link has no href");
                 continue;
             }
         };
-        listings.push(Extracted::Configuration(Configuration{ds_name: super::super::listing::DS_NAME.to_owned(), value: listing_url.to_string()}));
+        let listing_url = match links::resolve(&base_url, href) {
+            Ok(url) => url,
+            Err(e) => {
+                report.field_failed("listing_url", LISTING_SELECTOR, href, e);
+                continue;
+            }
+        };
+        report.field_parsed();
+
+        // The same listing is often linked more than once on a page
+        let listing_id = super::super::listing_id(&listing_url);
+        if !listing_ids.insert(listing_id.to_owned()) {
+            continue;
+        }
+        report.items.push(Extracted::Configuration(Configuration{
+            ds_name: super::super::listing::DS_NAME.to_owned(), 
+            value: listing_url, 
+            id: Some(listing_id),
+            festival_id: None,
+        }));
     }
 
-    Ok(listings)
+    report.unmatched_selectors = quality::unmatched_selectors(&document, &[LISTING_SELECTOR]);
+    Ok(report)
 }
- 
\ No newline at end of file
+
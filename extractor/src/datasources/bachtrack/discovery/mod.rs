@@ -1,7 +1,9 @@
 mod datasource;
+mod search;
 
 #[cfg(test)]
 pub mod tests;
 
 pub use datasource::DS;
-pub use datasource::DS_NAME;
\ No newline at end of file
+pub use datasource::DS_NAME;
+pub use datasource::parse_bachtrack_html;
\ No newline at end of file
@@ -0,0 +1,114 @@
+use {
+    std::error::Error,
+    chrono::{NaiveDate, Duration},
+    serde::{Serialize, Deserialize},
+    crate::model::errors::{InvalidDateRangeError, TooManySearchWindowsError},
+};
+
+const SEARCH_URL: &str = "https://bachtrack.com/search-";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+// Bachtrack caps the results of a single search, long ranges are searched a week at a time
+pub const SEARCH_WINDOW_DAYS: i64 = 7;
+// Each window is a fetch of its own, a configuration searches at most half a year
+pub const MAX_SEARCH_WINDOWS: usize = 26;
+
+// Either end left open searches without that bound
+type DateWindow = (Option<NaiveDate>, Option<NaiveDate>);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Genre {
+    Concerts,
+    Opera,
+    Dance,
+    Theatre,
+    Talks,
+    KidsEvents,
+}
+
+impl Genre {
+    fn path(&self) -> &'static str {
+        match self {
+            Genre::Concerts => "concerts",
+            Genre::Opera => "opera",
+            Genre::Dance => "dance",
+            Genre::Theatre => "theatre",
+            Genre::Talks => "talks",
+            Genre::KidsEvents => "kids-events",
+        }
+    }
+}
+
+// A structured bachtrack search, every field left out matches everything
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfiguration {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub genre: Option<Genre>,
+    pub performer: Option<String>,
+    pub composer: Option<String>,
+}
+
+impl SearchConfiguration {
+    pub fn search_urls(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self.date_windows()?
+            .into_iter()
+            .map(|(from, to)| self.search_url(from, to))
+            .collect())
+    }
+
+    fn search_url(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> String {
+        let mut url = format!("{}{}", SEARCH_URL, self.genre.map(|genre| genre.path()).unwrap_or("events"));
+
+        let filters = vec![
+            ("city", self.city.as_ref().map(|city| slug(city))),
+            ("country", self.country.as_ref().map(|country| slug(country))),
+            ("performer", self.performer.as_ref().map(|performer| slug(performer))),
+            ("composer", self.composer.as_ref().map(|composer| slug(composer))),
+            ("date-from", from.map(|date| date.format(DATE_FORMAT).to_string())),
+            ("date-to", to.map(|date| date.format(DATE_FORMAT).to_string())),
+        ];
+        for (name, value) in filters {
+            if let Some(value) = value {
+                url.push_str(&format!("/{}={}", name, value));
+            }
+        }
+        url
+    }
+
+    fn date_windows(&self) -> Result<Vec<DateWindow>, Box<dyn Error>> {
+        let (from, to) = match (self.from, self.to) {
+            (Some(from), Some(to)) => (from, to),
+            (from, to) => return Ok(vec![(from, to)]),
+        };
+        if from > to {
+            return Err(Box::new(InvalidDateRangeError));
+        }
+        let days = (to - from).num_days() + 1;
+        if (days + SEARCH_WINDOW_DAYS - 1) / SEARCH_WINDOW_DAYS > MAX_SEARCH_WINDOWS as i64 {
+            return Err(Box::new(TooManySearchWindowsError));
+        }
+
+        let mut windows = Vec::new();
+        let mut window_start = from;
+        while window_start <= to {
+            let window_end = std::cmp::min(window_start + Duration::days(SEARCH_WINDOW_DAYS - 1), to);
+            windows.push((Some(window_start), Some(window_end)));
+            window_start = window_end + Duration::days(1);
+        }
+        Ok(windows)
+    }
+}
+
+// The form bachtrack uses for names in urls, "Wiener Staatsoper" becomes "wiener-staatsoper"
+fn slug(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("-")
+}
@@ -3,9 +3,15 @@ use {
     std::path::Path,
     std::error::Error,
     std::fs,
+    std::sync::Mutex,
+    async_trait::async_trait,
+    chrono::{NaiveDate, Duration},
     tokio_test,
     super::super::listing::DS_NAME,
-    crate::model::http_client::TestHttpClient,
+    super::search::{SearchConfiguration, Genre, SEARCH_WINDOW_DAYS, MAX_SEARCH_WINDOWS},
+    crate::model::errors::{InvalidDateRangeError, TooManySearchWindowsError},
+    crate::model::http_client::{HttpClient, TestHttpClient},
+    crate::model::charset::Webpage,
 };
 
 const DISCOVERY_URL: &str = "https://bachtrack.com/find-concerts/";
@@ -23,12 +29,130 @@ fn test_extracor() -> Result<(), Box<dyn Error>>{
     let datasource = super::DS::new(TestHttpClient::new(&webpage));
 
     let configuration = DISCOVERY_URL.as_bytes().to_vec();
-    let items = tokio_test::block_on(datasource.extract(&configuration))?;
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    let items = report.items.to_vec();
 
+    assert_eq!(report.unmatched_selectors, Vec::<String>::new());
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.completeness(), 1.0);
     assert_eq!(items.len(), 50);
     assert_eq!(items[0], Extracted::Configuration(Configuration{
         ds_name: DS_NAME.to_owned(),
         value: "https://bachtrack.com/concert-event/residenz-serenade-munich-residenz-solisten-die-residenz-hofkapelle-5-september-2019/318719".to_owned(),
+        id: Some("bachtrack:318719".to_owned()),
+        festival_id: None,
     }));
     Ok(())
 }
+
+// Answers every request with the same page and remembers what was asked for
+struct RecordingHttpClient{
+    content: String,
+    urls: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl HttpClient for RecordingHttpClient{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        self.urls.lock().unwrap().push(url.to_owned());
+        Ok(Webpage::new(self.content.as_bytes().to_vec(), None))
+    }
+}
+
+#[test]
+fn test_search_urls() -> Result<(), Box<dyn Error>>{
+    let search = SearchConfiguration{
+        city: Some("Vienna".to_owned()),
+        genre: Some(Genre::Opera),
+        from: Some(NaiveDate::from_ymd(2021, 3, 1)),
+        to: Some(NaiveDate::from_ymd(2021, 3, 16)),
+        ..SearchConfiguration::default()
+    };
+
+    assert_eq!(search.search_urls()?, vec![
+        "https://bachtrack.com/search-opera/city=vienna/date-from=2021-03-01/date-to=2021-03-07",
+        "https://bachtrack.com/search-opera/city=vienna/date-from=2021-03-08/date-to=2021-03-14",
+        "https://bachtrack.com/search-opera/city=vienna/date-from=2021-03-15/date-to=2021-03-16",
+    ]);
+
+    let search = SearchConfiguration{
+        country: Some("United Kingdom".to_owned()),
+        performer: Some("London Symphony Orchestra".to_owned()),
+        composer: Some("Dvořák, Antonín".to_owned()),
+        ..SearchConfiguration::default()
+    };
+    assert_eq!(search.search_urls()?, vec![
+        "https://bachtrack.com/search-events/country=united-kingdom/performer=london-symphony-orchestra/composer=dvořák-antonín",
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_rejected_date_ranges() {
+    let reversed = SearchConfiguration{
+        from: Some(NaiveDate::from_ymd(2021, 3, 16)),
+        to: Some(NaiveDate::from_ymd(2021, 3, 1)),
+        ..SearchConfiguration::default()
+    };
+    assert!(reversed.search_urls().unwrap_err().is::<InvalidDateRangeError>());
+
+    let half_year = SearchConfiguration{
+        from: Some(NaiveDate::from_ymd(2021, 1, 1)),
+        to: Some(NaiveDate::from_ymd(2021, 1, 1) + Duration::days(MAX_SEARCH_WINDOWS as i64 * SEARCH_WINDOW_DAYS - 1)),
+        ..SearchConfiguration::default()
+    };
+    assert_eq!(half_year.search_urls().unwrap().len(), MAX_SEARCH_WINDOWS);
+
+    let year = SearchConfiguration{
+        from: Some(NaiveDate::from_ymd(2021, 1, 1)),
+        to: Some(NaiveDate::from_ymd(2021, 12, 31)),
+        ..SearchConfiguration::default()
+    };
+    assert!(year.search_urls().unwrap_err().is::<TooManySearchWindowsError>());
+}
+
+#[test]
+fn test_parse_search_configuration() -> Result<(), Box<dyn Error>>{
+    let search: SearchConfiguration = serde_json::from_str(r#"{"city": "Vienna", "genre": "kids-events", "from": "2021-03-01"}"#)?;
+    assert_eq!(search, SearchConfiguration{
+        city: Some("Vienna".to_owned()),
+        genre: Some(Genre::KidsEvents),
+        from: Some(NaiveDate::from_ymd(2021, 3, 1)),
+        ..SearchConfiguration::default()
+    });
+
+    assert!(serde_json::from_str::<SearchConfiguration>(r#"{"venue": "Musikverein"}"#).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_extract_searches_every_window() -> Result<(), Box<dyn Error>>{
+    let http_client = RecordingHttpClient{content: read_test_webpage()?, urls: Mutex::new(Vec::new())};
+    let datasource = super::DS::new(http_client);
+
+    let configuration = r#"{"city": "Munich", "from": "2021-03-01", "to": "2021-03-10"}"#.as_bytes().to_vec();
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+
+    assert_eq!(datasource.http_client.urls.lock().unwrap().len(), 2);
+    // Both windows returned the same listings
+    assert_eq!(report.items.len(), 50);
+    assert_eq!(report.unmatched_selectors, Vec::<String>::new());
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.completeness(), 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_relative_links_resolve_against_the_fetched_page() -> Result<(), Box<dyn Error>>{
+    let webpage = r#"<html><body><a class="listing-more-info" href="../concert-event/requiem/318719">more</a></body></html>"#;
+    let datasource = super::DS::new(TestHttpClient::new(webpage));
+
+    let configuration = "https://bachtrack.com/de_DE/find-concerts/".as_bytes().to_vec();
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+
+    match &report.items[0] {
+        Extracted::Configuration(configuration) => assert_eq!(configuration.value, "https://bachtrack.com/de_DE/concert-event/requiem/318719"),
+        item => panic!("expected a configuration, got {:?}", item),
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use {
+    std::error::Error,
+    std::time::Duration,
+    std::str,
+    scraper::{Html, Selector, ElementRef},
+    chrono::NaiveDate,
+    async_trait::async_trait,
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted, Configuration, Festival, City, Country},
+    crate::model::http_client::{HttpClient},
+    crate::model::quality,
+    crate::model::links,
+    super::super::{select_text, select_attr},
+    tracing::debug,
+};
+
+pub const DS_NAME: &str = "datasource.bachtrack_festival";
+
+const FESTIVAL_SELECTOR: &str = "[itemtype='http://schema.org/Festival']";
+const NAME_SELECTOR: &str = "[itemprop='name']";
+const URL_SELECTOR: &str = "[itemprop='url']";
+const START_DATE_SELECTOR: &str = "meta[itemprop='startDate']";
+const END_DATE_SELECTOR: &str = "meta[itemprop='endDate']";
+const CITY_SELECTOR: &str = "[itemprop='addressLocality']";
+const COUNTRY_SELECTOR: &str = "[itemprop='addressCountry']";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Copy, Clone)]
+pub struct DS<H: HttpClient>{
+    pub http_client: H,
+}
+
+impl<H: HttpClient> DS<H>{
+    pub fn new(http_client: H) -> DS<H>{
+        DS{http_client: http_client}
+    } 
+}
+
+#[async_trait]
+impl<H: HttpClient + Send + Sync> Datasource for DS<H>{
+    async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
+        debug!(configuration = ?str::from_utf8(&configuration), "extracting");
+
+        let url = page_url(configuration)?;
+        // Archived with the configuration, so reprocessing resolves links against the same page
+        let webpage: String = self.http_client.get_for(&url, configuration).await?;
+        parse_bachtrack_html(&webpage, &url)
+    }
+
+    // Without the url it was fetched from, relative links resolve against the site
+    fn parse(&self, webpage: &str) -> ExtractResult{
+        parse_bachtrack_html(webpage, super::super::listing::BASE_URL)
+    }
+
+    fn parse_for(&self, webpage: &str, configuration: &[u8]) -> ExtractResult{
+        parse_bachtrack_html(webpage, &page_url(configuration)?)
+    }
+    
+    fn get_name(&self) -> String{
+        DS_NAME.to_owned()
+    }
+
+    fn paused(&self) -> Option<Duration>{
+        self.http_client.paused()
+    }
+} 
+
+fn page_url(configuration: &[u8]) -> Result<String, Box<dyn Error>> {
+    let ds_config: Configuration = serde_json::from_slice(configuration)?;
+    links::resolve(super::super::listing::BASE_URL, &ds_config.value)
+}
+
+// The festival itself, and its concerts for the listing datasource
+fn parse_bachtrack_html(body: &str, page_url: &str) -> ExtractResult {
+    // Festival pages list their concerts like the search results do
+    let mut report = super::super::discovery::parse_bachtrack_html(body, page_url)?;
+
+    let document = Html::parse_document(&body);
+    let festival = match document.select(&Selector::parse(FESTIVAL_SELECTOR).unwrap()).next() {
+        Some(festival_element) => get_festival(&festival_element, page_url, &mut report),
+        None => None,
+    };
+
+    if let Some(festival) = festival {
+        for item in report.items.iter_mut() {
+            if let Extracted::Configuration(configuration) = item {
+                configuration.festival_id = Some(festival.id.to_owned());
+            }
+        }
+        report.items.insert(0, Extracted::Festival(festival));
+    }
+
+    report.unmatched_selectors.extend(quality::unmatched_selectors(&document, &[FESTIVAL_SELECTOR, START_DATE_SELECTOR, END_DATE_SELECTOR, CITY_SELECTOR]));
+    Ok(report)
+}
+
+// A festival needs its url for an id, a name and its dates, the city is optional
+fn get_festival(festival_element: &ElementRef, page_url: &str, report: &mut ExtractionReport) -> Option<Festival>{
+    let url = select_attr(festival_element, URL_SELECTOR, "href")
+        .and_then(|href| links::resolve(page_url, &href).ok());
+    let url = match url {
+        Some(url) => {
+            report.field_parsed();
+            url
+        },
+        None => {
+            report.field_failed("url", URL_SELECTOR, "", "the festival has no url");
+            return None;
+        },
+    };
+
+    let name = match select_text(festival_element, NAME_SELECTOR) {
+        Some(name) if !name.is_empty() => {
+            report.field_parsed();
+            name
+        },
+        other => {
+            report.field_failed("name", NAME_SELECTOR, &other.unwrap_or_default(), "the festival has no name");
+            return None;
+        },
+    };
+
+    let start_date = get_date(festival_element, START_DATE_SELECTOR, "start_date", report)?;
+    let end_date = get_date(festival_element, END_DATE_SELECTOR, "end_date", report)?;
+    if end_date < start_date {
+        report.field_failed("end_date", END_DATE_SELECTOR, &end_date.format(DATE_FORMAT).to_string(), "the festival ends before it starts");
+        return None;
+    }
+
+    Some(Festival{
+        id: festival_id(&url),
+        name: name,
+        start_date: start_date,
+        end_date: end_date,
+        city: get_city(festival_element),
+        url: url,
+    })
+}
+
+// Festival urls end with an id of their own, prefixed to keep it apart from listing ids
+fn festival_id(festival_url: &str) -> String {
+    match links::numeric_id(festival_url) {
+        Some(id) => format!("bachtrack:festival:{}", id),
+        None => festival_url.to_owned(),
+    }
+}
+
+fn get_date(festival_element: &ElementRef, selector: &str, field: &str, report: &mut ExtractionReport) -> Option<NaiveDate>{
+    let date = select_attr(festival_element, selector, "content").unwrap_or_default();
+    match NaiveDate::parse_from_str(&date, DATE_FORMAT) {
+        Ok(date) => {
+            report.field_parsed();
+            Some(date)
+        },
+        Err(e) => {
+            report.field_failed(field, selector, &date, e);
+            None
+        },
+    }
+}
+
+fn get_city(festival_element: &ElementRef) -> Option<City>{
+    let city = select_text(festival_element, CITY_SELECTOR).filter(|city| !city.is_empty())?;
+    let country = select_text(festival_element, COUNTRY_SELECTOR).unwrap_or_default();
+    Some(City{name: city, country: Country{name: country}})
+}
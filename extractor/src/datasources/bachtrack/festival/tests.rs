@@ -0,0 +1,84 @@
+use {
+    crate::model::{Datasource, Extracted, Configuration, Festival, City, Country},
+    std::path::Path,
+    std::error::Error,
+    std::fs,
+    chrono::NaiveDate,
+    tokio_test,
+    super::super::listing::DS_NAME,
+    crate::model::http_client::TestHttpClient,
+};
+
+fn read_test_webpage() -> Result<String, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    let webpage_path = Path::new(&base_path).join("resources/tests/bachtrack_festival");
+    Ok(fs::read_to_string(webpage_path)?)
+}
+
+#[test]
+fn test_extracor() -> Result<(), Box<dyn Error>>{
+    let webpage = read_test_webpage()?;
+    
+    let datasource = super::DS::new(TestHttpClient::new(&webpage));
+
+    let configuration = r#"{"ds_name": "datasource.bachtrack_festival", "value": "/festival/salzburger-festspiele-2021/1042"}"#.as_bytes().to_vec();
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+
+    assert_eq!(report.unmatched_selectors, Vec::<String>::new());
+    assert_eq!(report.warnings, vec![]);
+    // The festival and its three concerts, one of them linked twice
+    assert_eq!(report.items.len(), 4);
+    assert_eq!(report.items[0], Extracted::Festival(Festival{
+        id: "bachtrack:festival:1042".to_owned(),
+        name: "Salzburg Festival 2021".to_owned(),
+        start_date: NaiveDate::from_ymd(2021, 7, 17),
+        end_date: NaiveDate::from_ymd(2021, 8, 31),
+        city: Some(City{name: "Salzburg".to_owned(), country: Country{name: "Austria".to_owned()}}),
+        url: "https://bachtrack.com/festival/salzburger-festspiele-2021/1042".to_owned(),
+    }));
+    assert_eq!(report.items[2], Extracted::Configuration(Configuration{
+        ds_name: DS_NAME.to_owned(),
+        value: "https://bachtrack.com/concert-event/wiener-philharmoniker-muti-grosses-festspielhaus-14-august-2021/341002".to_owned(),
+        id: Some("bachtrack:341002".to_owned()),
+        festival_id: Some("bachtrack:festival:1042".to_owned()),
+    }));
+    Ok(())
+}
+
+#[test]
+fn test_festival_round_trip() -> Result<(), Box<dyn Error>>{
+    let webpage = read_test_webpage()?;
+    let report = super::DS::new(TestHttpClient::new(&webpage)).parse(&webpage)?;
+
+    // Festivals only share the name with the other variants, they mustn't come back as one of those
+    let serialized = serde_json::to_string(&report.items[0])?;
+    assert_eq!(serde_json::from_str::<Extracted>(&serialized)?, report.items[0]);
+    Ok(())
+}
+
+#[test]
+fn test_reprocessing_resolves_against_the_fetched_page() -> Result<(), Box<dyn Error>>{
+    let webpage = r#"<html><body><a class="listing-more-info" href="../../concert-event/requiem/318719">more</a></body></html>"#;
+    let datasource = super::DS::new(TestHttpClient::new(webpage));
+
+    let configuration = r#"{"ds_name": "datasource.bachtrack_festival", "value": "/de_DE/festival/osterfestspiele/1043"}"#.as_bytes().to_vec();
+    let extracted = tokio_test::block_on(datasource.extract(&configuration))?;
+    let reprocessed = datasource.parse_for(webpage, &configuration)?;
+
+    match &extracted.items[0] {
+        Extracted::Configuration(configuration) => assert_eq!(configuration.value, "https://bachtrack.com/de_DE/concert-event/requiem/318719"),
+        item => panic!("expected a configuration, got {:?}", item),
+    }
+    assert_eq!(reprocessed.items, extracted.items);
+    Ok(())
+}
+
+#[test]
+fn test_festival_ending_before_it_starts() -> Result<(), Box<dyn Error>>{
+    let webpage = read_test_webpage()?.replace("content='2021-08-31'", "content='2021-07-01'");
+    let report = super::DS::new(TestHttpClient::new(&webpage)).parse(&webpage)?;
+
+    assert!(report.items.iter().all(|item| item.get_type_name() != "Festival"));
+    assert_eq!(report.warnings[0].field, "end_date");
+    Ok(())
+}
@@ -5,16 +5,29 @@ use {
     chrono::{NaiveDateTime, Duration},
     scraper::Selector,
     async_trait::async_trait,
-    crate::model::{Datasource, ExtractResult, EventTime, Extracted, Configuration, MusicEvent, Person, Piece},
+    crate::model::{Datasource, ExtractResult, ExtractionReport, EventTime, EndTimePrecision, Extracted, Configuration, MusicEvent, Person, Piece},
     crate::model::http_client::{HttpClient},
     crate::model::errors,
+    crate::model::date_parser::{self, DateTimeParser, ParsedTime, ALL_LANGUAGES},
+    crate::model::duration,
+    crate::model::quality,
+    crate::model::links,
     regex::Regex,
+    tracing::debug,
 };
 
 pub const BASE_URL: &str = "https://bachtrack.com";
 pub const DS_NAME: &str = "datasource.bachtrack_listing";
 const DEFAULT_EVENT_LENGTH: i64 = 2;
 
+const TIMES_SELECTOR: &str = "table#table_li_times";
+const PROGRAMME_SELECTOR: &str = "table#table_listing-programme";
+const DESCRIPTION_SELECTOR: &str = "div.listing-description";
+// Columns of a programme row
+const ARTIST_SELECTOR: &str = "td:nth-child(1)";
+const PIECE_SELECTOR: &str = "td:nth-child(2)";
+const WORKS_BY_PREFIX: &str = "Works by ";
+
 
 #[derive(Copy, Clone)]
 pub struct DS<H: HttpClient>{
@@ -30,84 +43,160 @@ impl<H: HttpClient> DS<H>{
 #[async_trait]
 impl<H: HttpClient + Send + Sync> Datasource for DS<H>{
     async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
-        println!("extracting with configuration {:#?}", &str::from_utf8(&configuration));
+        debug!(configuration = ?str::from_utf8(&configuration), "extracting");
 
         let ds_config: Configuration = serde_json::from_slice(&configuration)?;
 
-        let webpage: String = self.http_client.get(&format!("{}{}", BASE_URL, &ds_config.value)).await?;
-        Ok(parse_bachtrack_html(&webpage)?)
+        // Discovery sends absolute urls, hand written configurations often just the path
+        let url = links::resolve(BASE_URL, &ds_config.value)?;
+        // Archived with the configuration, so reprocessing the page keeps the festival
+        let webpage: String = self.http_client.get_for(&url, configuration).await?;
+        self.parse_for(&webpage, configuration)
+    }
+
+    fn parse(&self, webpage: &str) -> ExtractResult{
+        parse_bachtrack_html(webpage)
+    }
+
+    // Events found through a festival's page belong to the festival
+    fn parse_for(&self, webpage: &str, configuration: &[u8]) -> ExtractResult{
+        let ds_config: Configuration = serde_json::from_slice(configuration)?;
+        let mut report = self.parse(webpage)?;
+
+        for item in report.items.iter_mut() {
+            if let Extracted::MusicEvent(event) = item {
+                event.festival_id = ds_config.festival_id.to_owned();
+            }
+        }
+        Ok(report)
     }
     
     fn get_name(&self) -> String{
         DS_NAME.to_owned()
     }
+
+    fn paused(&self) -> Option<std::time::Duration>{
+        self.http_client.paused()
+    }
 } 
 
 fn parse_bachtrack_html(body: &str) -> ExtractResult {
-    let mut events = Vec::new();
+    let mut report = ExtractionReport::new(Vec::new());
     
     let document = Html::parse_document(&body);
-    let (pieces, artists) = get_pieces_and_artists(&document);
+    let (pieces, artists) = get_pieces_and_artists(&document, &mut report);
     let description = get_description(&document);
 
-    for start_time in get_event_times(&document)?{
-        let end_time = match start_time.checked_add_signed(Duration::hours(DEFAULT_EVENT_LENGTH)){
-            Some(time) => time,
-            None => return Err(Box::new(errors::DateTimeCalculationError{}))
+    for parsed_time in get_event_times(&document, &mut report){
+        let time = match get_event_time(parsed_time, &description, &pieces) {
+            Ok(time) => time,
+            Err(e) => {
+                report.field_failed("time", TIMES_SELECTOR, &format!("{:?}", parsed_time), e);
+                continue;
+            }
         };
-        
-        events.push(
+        report.items.push(
             Extracted::MusicEvent(MusicEvent{
-                time: EventTime{ 
-                    start_time: start_time, 
-                    end_time: end_time, 
-                    local_timezone: true,
-                },
+                time: time,
                 description: description.clone(),
                 pieces: pieces.to_vec(),
                 artists: artists.to_vec(),
+                festival_id: None,
             })
         );
     }
-    Ok(events)
-      
+
+    report.unmatched_selectors = quality::unmatched_selectors(&document, &[TIMES_SELECTOR, PROGRAMME_SELECTOR, DESCRIPTION_SELECTOR]);
+    Ok(report)
 }
  
-fn get_event_times(document: &Html) -> Result<Vec<chrono::NaiveDateTime>, Box<dyn Error>>{
+fn get_event_times(document: &Html, report: &mut ExtractionReport) -> Vec<ParsedTime>{
     let mut times = Vec::new();
-    for element in document.select(&Selector::parse("table#table_li_times").unwrap()){
+    for element in document.select(&Selector::parse(TIMES_SELECTOR).unwrap()){
 
         for time_element in element.select(&Selector::parse("tr").unwrap()){
             match parse_time(&time_element){
-                Ok(time) => times.push(time),
-                Err(e) => {
-                    println!("Couldn't parse time from html element. err: {}", e);
-                    continue;
-                }
+                Ok(time) => {
+                    report.field_parsed();
+                    times.push(time);
+                },
+                Err(e) => report.field_failed("time", TIMES_SELECTOR, &time_element.text().collect::<Vec<_>>().join(" "), e),
             }
         }
     }
 
-    Ok(times)
+    times
 }
 
-const DATE_TIME_FORMAT: &str = "%A %d %B %Y %H:%M";
+const DATE_TIME_PARSER: DateTimeParser = DateTimeParser::new(
+    ALL_LANGUAGES,
+    &["%A %d %B %Y %H:%M", "%d %B %Y %H:%M"],
+    &["%A %d %B %Y", "%d %B %Y"],
+);
 
-fn parse_time(time_element: &scraper::ElementRef) -> Result<NaiveDateTime, Box<dyn Error>>{    
+fn parse_time(time_element: &scraper::ElementRef) -> Result<ParsedTime, Box<dyn Error>>{    
     let date_strings = time_element.text().collect::<Vec<_>>();
-    Ok(NaiveDateTime::parse_from_str(&date_strings.join(" "), DATE_TIME_FORMAT)?)
+    DATE_TIME_PARSER.parse(&date_strings.join(" "))
 }
 
-fn get_pieces_and_artists(document: &Html) -> (Vec<Piece>, Vec<Person>){
+// Prefers an end given by the page, then an estimate from the programme, then the default length.
+fn get_event_time(parsed_time: ParsedTime, description: &str, pieces: &[Piece]) -> Result<EventTime, Box<dyn Error>>{
+    let start_time = match parsed_time {
+        ParsedTime::Range(start_time, end_time) => return Ok(EventTime{
+            start_time: start_time,
+            end_time: end_time,
+            end_time_precision: EndTimePrecision::Exact,
+            local_timezone: true,
+        }),
+        ParsedTime::DateTime(start_time) => start_time,
+        // Without a time of day the event is assumed to span the whole day
+        ParsedTime::Date(date) => return event_time_with_length(date.and_hms(0, 0, 0), Duration::days(1), EndTimePrecision::Default),
+    };
+
+    if let Some(end_time) = duration::find_end_time(description){
+        return Ok(EventTime{
+            start_time: start_time,
+            end_time: date_parser::end_time_after(start_time, end_time)?,
+            end_time_precision: EndTimePrecision::Exact,
+            local_timezone: true,
+        });
+    }
+
+    if let Some(length) = duration::find_duration(description){
+        return event_time_with_length(start_time, length, EndTimePrecision::Exact);
+    }
+
+    match duration::estimate_programme_length(pieces){
+        Some(length) => event_time_with_length(start_time, length, EndTimePrecision::Estimated),
+        None => event_time_with_length(start_time, Duration::hours(DEFAULT_EVENT_LENGTH), EndTimePrecision::Default),
+    }
+}
+
+fn event_time_with_length(start_time: NaiveDateTime, length: Duration, precision: EndTimePrecision) -> Result<EventTime, Box<dyn Error>>{
+    let end_time = match start_time.checked_add_signed(length){
+        Some(time) => time,
+        None => return Err(Box::new(errors::DateTimeCalculationError{}))
+    };
+
+    Ok(EventTime{
+        start_time: start_time,
+        end_time: end_time,
+        end_time_precision: precision,
+        local_timezone: true,
+    })
+}
+
+fn get_pieces_and_artists(document: &Html, report: &mut ExtractionReport) -> (Vec<Piece>, Vec<Person>){
     let mut pieces = Vec::new();
     let mut artists: Vec<Person> = Vec::new();
 
-    for element in document.select(&Selector::parse("table#table_listing-programme").unwrap()){
+    for element in document.select(&Selector::parse(PROGRAMME_SELECTOR).unwrap()){
         for programme_element in element.select(&Selector::parse("tr").unwrap()){
             let artist_result = get_artist_name(&programme_element);
             
             match get_piece_name(&programme_element){
                 Ok(piece_name) => {
+                    report.field_parsed();
                     let mut piece_artists = Vec::new();
                     
                     match artist_result {
@@ -120,16 +209,19 @@ fn get_pieces_and_artists(document: &Html) -> (Vec<Piece>, Vec<Person>){
                        artists: piece_artists,
                     });
                 },
-                Err(e) => println!("Coudld not parse piece from html element. err: {}", e)
+                // "Works by <composer>" rows name no piece
+                Err(_) if is_works_by_row(&programme_element) => (),
+                Err(e) => report.field_failed("piece", &format!("{} {}", PROGRAMME_SELECTOR, PIECE_SELECTOR), &programme_element.text().collect::<String>(), e),
             }
             
             match artist_result{
                 Ok(artist_name) => {
+                    report.field_parsed();
                     if !artists.iter().any(|artist| artist.name==artist_name) {
                         artists.push(Person{name: artist_name});
                     }
                 },
-                Err(e) => println!("Coudld not parse artist from html element. err: {}", e)
+                Err(e) => report.field_failed("artist", &format!("{} {}", PROGRAMME_SELECTOR, ARTIST_SELECTOR), &programme_element.text().collect::<String>(), e),
             }
 
         }
@@ -138,10 +230,14 @@ fn get_pieces_and_artists(document: &Html) -> (Vec<Piece>, Vec<Person>){
     (pieces, artists)
 }
 
+fn is_works_by_row(programme_element: &scraper::ElementRef) -> bool{
+    programme_element.text().collect::<String>().trim_start().starts_with(WORKS_BY_PREFIX)
+}
+
 fn get_artist_name(programme_element: &scraper::ElementRef) -> Result<String, Box<dyn Error>>{
-    let re_arists_name = Regex::new(r"(?:Works by ){0,1}(.*) (?:.*)").unwrap();
+    let re_arists_name = Regex::new(&format!(r"(?:{}){{0,1}}(.*) (?:.*)", WORKS_BY_PREFIX)).unwrap();
 
-    for element in programme_element.select(&Selector::parse("td:nth-child(1)").unwrap()){
+    for element in programme_element.select(&Selector::parse(ARTIST_SELECTOR).unwrap()){
         for capture in re_arists_name.captures_iter(&element.text().collect::<String>()) {
             if capture.len() != 2{
                 return Err(Box::new(errors::RegexDidNotMatchError{}));
@@ -154,7 +250,7 @@ fn get_artist_name(programme_element: &scraper::ElementRef) -> Result<String, Bo
 }
 
 fn get_piece_name(programme_element: &scraper::ElementRef) -> Result<String, Box<dyn Error>>{
-    for element in programme_element.select(&Selector::parse("td:nth-child(2)").unwrap()){
+    for element in programme_element.select(&Selector::parse(PIECE_SELECTOR).unwrap()){
         let piece_name = element.text().collect::<String>();
         if piece_name != ""{
             return Ok(piece_name);
@@ -164,7 +260,7 @@ fn get_piece_name(programme_element: &scraper::ElementRef) -> Result<String, Box
 }
 
 fn get_description(document: &Html) -> String{
-    for element in document.select(&Selector::parse("div.listing-description").unwrap()){ 
+    for element in document.select(&Selector::parse(DESCRIPTION_SELECTOR).unwrap()){ 
         return element.text().collect::<String>();
     }
 
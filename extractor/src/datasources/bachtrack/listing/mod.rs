@@ -4,4 +4,5 @@ mod datasource;
 mod tests;
 
 pub use datasource::DS;
-pub use datasource::DS_NAME;
\ No newline at end of file
+pub use datasource::DS_NAME;
+pub use datasource::BASE_URL;
\ No newline at end of file
@@ -1,6 +1,6 @@
 
 use {
-    crate::model::{Datasource, Extracted, MusicEvent, EventTime, Person, Piece},
+    crate::model::{Datasource, Extracted, ExtractionWarning, MusicEvent, EventTime, EndTimePrecision, Person, Piece},
     std::path::Path,
     std::error::Error,
     std::fs,
@@ -24,8 +24,12 @@ fn test_extracor() -> Result<(), Box<dyn Error>>{
     let datasource = super::DS::new(TestHttpClient::new(&webpage));
 
     let configuration = r#"{"ds_name": "datasource.bachtrack_listing", "value": "/url-something"}"#.as_bytes().to_vec();
-    let items = tokio_test::block_on(datasource.extract(&configuration))?;
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    let items = report.items.to_vec();
 
+    assert_eq!(report.unmatched_selectors, Vec::<String>::new());
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.completeness(), 1.0);
     assert_eq!(items.len(), 25);
     assert_eq!(items[0], Extracted::MusicEvent(
         MusicEvent{
@@ -43,8 +47,10 @@ fn test_extracor() -> Result<(), Box<dyn Error>>{
             time: EventTime{
                 start_time: Utc.ymd(2020, 10, 8).and_hms_milli(18, 30, 0, 0).naive_utc(),
                 end_time: Utc.ymd(2020, 10, 8).and_hms_milli(20, 30, 0, 0).naive_utc(),
+                end_time_precision: EndTimePrecision::Default,
                 local_timezone: true,
-            }
+            },
+            festival_id: None,
         })
     );
     Ok(())
@@ -57,8 +63,12 @@ fn test_extracor2() -> Result<(), Box<dyn Error>>{
     let datasource = super::DS::new(TestHttpClient::new(&webpage));
 
     let configuration = r#"{"ds_name": "datasource.bachtrack_listing", "value": "/url-something"}"#.as_bytes().to_vec();
-    let items = tokio_test::block_on(datasource.extract(&configuration))?;
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    let items = report.items.to_vec();
 
+    assert_eq!(report.unmatched_selectors, vec!["div.listing-description".to_owned()]);
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.completeness(), 1.0);
     assert_eq!(items.len(), 1);
     assert_eq!(items[0], Extracted::MusicEvent(
         MusicEvent{
@@ -75,11 +85,41 @@ fn test_extracor2() -> Result<(), Box<dyn Error>>{
             description: "".to_owned(),
             time: EventTime{
                 start_time: Utc.ymd(2020, 10, 23).and_hms_milli(19, 30, 0, 0).naive_utc(),
-                end_time: Utc.ymd(2020, 10, 23).and_hms_milli(21, 30, 0, 0).naive_utc(),
+                end_time: Utc.ymd(2020, 10, 23).and_hms_milli(21, 10, 0, 0).naive_utc(),
+                end_time_precision: EndTimePrecision::Estimated,
                 local_timezone: true,
-            }
+            },
+            festival_id: None,
         })
     );
     Ok(())
 }
 
+
+#[test]
+fn test_partial_results() -> Result<(), Box<dyn Error>>{
+    let webpage = r#"<html><body>
+        <table id='table_li_times'><tbody>
+            <tr><td>Friday 23 October 2020</td><td>19:30</td></tr>
+            <tr><td>Date to be announced</td><td></td></tr>
+        </tbody></table>
+        <table id='table_listing-programme'><tbody>
+            <tr><td>Mozart, Wolfgang Amadeus (1756-1791)</td><td>Requiem in D minor, K626</td></tr>
+        </tbody></table>
+        <div class='listing-description'>A concert</div>
+    </body></html>"#;
+
+    let datasource = super::DS::new(TestHttpClient::new(webpage));
+    let report = datasource.parse(webpage)?;
+
+    assert_eq!(report.items.len(), 1);
+    assert_eq!(report.warnings, vec![ExtractionWarning{
+        field: "time".to_owned(),
+        selector: "table#table_li_times".to_owned(),
+        raw_text: "Date to be announced".to_owned(),
+        reason: "The text did not match any of the known date formats".to_owned(),
+    }]);
+    // The first date, the piece and its composer parsed
+    assert_eq!(report.completeness(), 0.75);
+    Ok(())
+}
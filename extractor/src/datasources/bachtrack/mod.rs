@@ -1,2 +1,54 @@
+use {
+    scraper::{Selector, ElementRef},
+    url::Url,
+    crate::model::Configuration,
+    crate::model::links,
+};
+
 pub mod discovery;
-pub mod listing;
\ No newline at end of file
+pub mod listing;
+pub mod review;
+pub mod festival;
+
+// Listing urls end with bachtrack's numeric id, the title before it can change
+pub fn listing_id(listing_url: &str) -> String {
+    match links::numeric_id(listing_url) {
+        Some(id) => format!("bachtrack:{}", id),
+        None => listing_url.to_owned(),
+    }
+}
+
+// The configuration of the datasource that extracts a bachtrack page, None for pages no datasource handles
+pub fn configuration_for(url: &str) -> Option<Configuration> {
+    let url = links::canonicalize(url).ok()?;
+    let parsed = Url::parse(&url).ok()?;
+    if parsed.host_str()?.trim_start_matches("www.") != "bachtrack.com" {
+        return None;
+    }
+
+    let path = parsed.path();
+    let (ds_name, id) = if path.starts_with("/concert-event/") {
+        (listing::DS_NAME, Some(listing_id(&url)))
+    } else if path.starts_with("/festival/") {
+        (festival::DS_NAME, None)
+    } else if path.starts_with("/review-") {
+        (review::DS_NAME, None)
+    } else {
+        return None;
+    };
+    Some(Configuration{ds_name: ds_name.to_owned(), value: url, id: id, festival_id: None})
+}
+
+// Trimmed text of the first element matching the selector
+pub fn select_text(element: &ElementRef, selector: &str) -> Option<String> {
+    element.select(&Selector::parse(selector).unwrap())
+        .next()
+        .map(|found| found.text().collect::<String>().trim().to_owned())
+}
+
+pub fn select_attr(element: &ElementRef, selector: &str, attr: &str) -> Option<String> {
+    element.select(&Selector::parse(selector).unwrap())
+        .next()
+        .and_then(|found| found.value().attr(attr))
+        .map(|value| value.to_owned())
+}
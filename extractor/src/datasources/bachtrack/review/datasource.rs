@@ -0,0 +1,145 @@
+use {
+    std::time::Duration,
+    std::str,
+    scraper::{Html, Selector, ElementRef},
+    chrono::NaiveDate,
+    async_trait::async_trait,
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted, Configuration, Person, Review},
+    crate::model::http_client::{HttpClient},
+    crate::model::quality,
+    crate::model::links,
+    super::super::{select_text, select_attr},
+    tracing::debug,
+};
+
+pub const DS_NAME: &str = "datasource.bachtrack_review";
+
+// Reviews are marked up with schema.org microdata, a page can hold more than one
+const REVIEW_SELECTOR: &str = "[itemtype='http://schema.org/Review']";
+const REVIEWER_SELECTOR: &str = "[itemprop='author'] [itemprop='name']";
+const RATING_SELECTOR: &str = "[itemprop='reviewRating'] meta[itemprop='ratingValue']";
+const PUBLISHED_SELECTOR: &str = "meta[itemprop='datePublished']";
+const BODY_SELECTOR: &str = "[itemprop='reviewBody'] p";
+const EVENT_SELECTOR: &str = "[itemprop='itemReviewed'] a[itemprop='url']";
+
+const MAX_RATING: u8 = 5;
+// Longer first paragraphs are cut at a word boundary
+const EXCERPT_LENGTH: usize = 300;
+
+#[derive(Copy, Clone)]
+pub struct DS<H: HttpClient>{
+    pub http_client: H,
+}
+
+impl<H: HttpClient> DS<H>{
+    pub fn new(http_client: H) -> DS<H>{
+        DS{http_client: http_client}
+    } 
+}
+
+#[async_trait]
+impl<H: HttpClient + Send + Sync> Datasource for DS<H>{
+    async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
+        debug!(configuration = ?str::from_utf8(&configuration), "extracting");
+
+        let ds_config: Configuration = serde_json::from_slice(&configuration)?;
+        let url = links::resolve(super::super::listing::BASE_URL, &ds_config.value)?;
+        let webpage: String = self.http_client.get(&url).await?;
+        self.parse(&webpage)
+    }
+
+    fn parse(&self, webpage: &str) -> ExtractResult{
+        parse_bachtrack_html(webpage)
+    }
+    
+    fn get_name(&self) -> String{
+        DS_NAME.to_owned()
+    }
+
+    fn paused(&self) -> Option<Duration>{
+        self.http_client.paused()
+    }
+} 
+
+fn parse_bachtrack_html(body: &str) -> ExtractResult {
+    let mut report = ExtractionReport::new(Vec::new());
+
+    let document = Html::parse_document(&body);
+    for review_element in document.select(&Selector::parse(REVIEW_SELECTOR).unwrap()) {
+        if let Some(review) = get_review(&review_element, &mut report) {
+            report.items.push(Extracted::Review(review));
+        }
+    }
+
+    report.unmatched_selectors = quality::unmatched_selectors(&document, &[REVIEW_SELECTOR, REVIEWER_SELECTOR, RATING_SELECTOR, PUBLISHED_SELECTOR, BODY_SELECTOR, EVENT_SELECTOR]);
+    Ok(report)
+}
+
+// A review without a reviewer or a publication date is skipped, everything else is optional
+fn get_review(review_element: &ElementRef, report: &mut ExtractionReport) -> Option<Review>{
+    let reviewer = match select_text(review_element, REVIEWER_SELECTOR) {
+        Some(name) if !name.is_empty() => {
+            report.field_parsed();
+            name
+        },
+        other => {
+            report.field_failed("reviewer", REVIEWER_SELECTOR, &other.unwrap_or_default(), "the review has no author");
+            return None;
+        },
+    };
+
+    let published = select_attr(review_element, PUBLISHED_SELECTOR, "content").unwrap_or_default();
+    let published = match NaiveDate::parse_from_str(&published, "%Y-%m-%d") {
+        Ok(date) => {
+            report.field_parsed();
+            date
+        },
+        Err(e) => {
+            report.field_failed("published", PUBLISHED_SELECTOR, &published, e);
+            return None;
+        },
+    };
+
+    let event_url = select_attr(review_element, EVENT_SELECTOR, "href")
+        .and_then(|href| links::resolve(super::super::listing::BASE_URL, &href).ok());
+
+    Some(Review{
+        reviewer: Person{name: reviewer},
+        rating: get_rating(review_element, report),
+        published: published,
+        excerpt: get_excerpt(review_element),
+        listing_id: event_url.as_ref().map(|url| super::super::listing_id(url)),
+        event_url: event_url,
+    })
+}
+
+fn get_rating(review_element: &ElementRef, report: &mut ExtractionReport) -> Option<u8>{
+    let rating = select_attr(review_element, RATING_SELECTOR, "content")?;
+    match rating.trim().parse::<u8>() {
+        Ok(stars) if stars <= MAX_RATING => {
+            report.field_parsed();
+            Some(stars)
+        },
+        Ok(_) => {
+            report.field_failed("rating", RATING_SELECTOR, &rating, format!("ratings go up to {} stars", MAX_RATING));
+            None
+        },
+        Err(e) => {
+            report.field_failed("rating", RATING_SELECTOR, &rating, e);
+            None
+        },
+    }
+}
+
+fn get_excerpt(review_element: &ElementRef) -> String{
+    let first_paragraph = select_text(review_element, BODY_SELECTOR).unwrap_or_default();
+    if first_paragraph.chars().count() <= EXCERPT_LENGTH {
+        return first_paragraph;
+    }
+
+    let cut: String = first_paragraph.chars().take(EXCERPT_LENGTH).collect();
+    match cut.rfind(' ') {
+        Some(last_space) => format!("{}…", cut[..last_space].trim_end()),
+        None => format!("{}…", cut),
+    }
+}
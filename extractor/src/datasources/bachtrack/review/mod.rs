@@ -0,0 +1,7 @@
+mod datasource;
+
+#[cfg(test)]
+mod tests;
+
+pub use datasource::DS;
+pub use datasource::DS_NAME;
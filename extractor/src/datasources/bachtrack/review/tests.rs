@@ -0,0 +1,69 @@
+use {
+    crate::model::{Datasource, Extracted, Person, Review},
+    std::path::Path,
+    std::error::Error,
+    std::fs,
+    chrono::NaiveDate,
+    tokio_test,
+    crate::model::http_client::TestHttpClient,
+};
+
+fn read_test_webpage() -> Result<String, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    let webpage_path = Path::new(&base_path).join("resources/tests/bachtrack_review");
+    Ok(fs::read_to_string(webpage_path)?)
+}
+
+#[test]
+fn test_extracor() -> Result<(), Box<dyn Error>>{
+    let webpage = read_test_webpage()?;
+    
+    let datasource = super::DS::new(TestHttpClient::new(&webpage));
+
+    let configuration = r#"{"ds_name": "datasource.bachtrack_review", "value": "/review-coronation-anthems-all-city-chorus-southwark-cathedral-october-2020"}"#.as_bytes().to_vec();
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+
+    assert_eq!(report.unmatched_selectors, Vec::<String>::new());
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.items, vec![Extracted::Review(Review{
+        reviewer: Person{name: "Mark Pullinger".to_owned()},
+        rating: Some(4),
+        published: NaiveDate::from_ymd(2020, 10, 24),
+        excerpt: "There was something fitting about hearing Handel's Zadok the Priest in a cathedral that has seen nearly a thousand years of worship, even with the choir spread across the nave and the audience in masks.".to_owned(),
+        listing_id: Some("bachtrack:333746".to_owned()),
+        event_url: Some("https://bachtrack.com/concert-event/coronation-anthems-all-city-chorus-southwark-cathedral-23-october-2020/333746".to_owned()),
+    })]);
+    Ok(())
+}
+
+#[test]
+fn test_incomplete_reviews() -> Result<(), Box<dyn Error>>{
+    let webpage = r#"<html><body>
+        <div itemscope itemtype='http://schema.org/Review'>
+            <span itemprop='author'><span itemprop='name'>Mark Pullinger</span></span>
+            <meta itemprop='datePublished' content='2020-10-24'>
+            <div itemprop='reviewRating'><meta itemprop='ratingValue' content='7'></div>
+        </div>
+        <div itemscope itemtype='http://schema.org/Review'>
+            <meta itemprop='datePublished' content='2020-10-25'>
+        </div>
+    </body></html>"#;
+
+    let report = super::DS::new(TestHttpClient::new(webpage)).parse(webpage)?;
+
+    // The unrated review is kept, the one without an author isn't
+    assert_eq!(report.items.len(), 1);
+    assert_eq!(
+        report.warnings.iter().map(|warning| warning.field.as_str()).collect::<Vec<_>>(), 
+        vec!["rating", "reviewer"],
+    );
+    match &report.items[0] {
+        Extracted::Review(review) => {
+            assert_eq!(review.rating, None);
+            assert_eq!(review.excerpt, "");
+            assert_eq!(review.listing_id, None);
+        },
+        other => panic!("expected a review, got {:?}", other),
+    };
+    Ok(())
+}
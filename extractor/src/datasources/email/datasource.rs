@@ -0,0 +1,108 @@
+use {
+    std::path::Path,
+    scraper::{Html, Selector},
+    async_trait::async_trait,
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted, Configuration},
+    crate::model::{links, paths},
+    crate::datasources::bachtrack,
+    tracing::{debug, warn},
+    super::message::Part,
+    super::mailbox,
+    super::json_ld,
+};
+
+pub const DS_NAME: &str = "datasource.email_newsletter";
+// Mailboxes named in configurations are looked up in here, relative to the working directory
+pub const DEFAULT_MAILBOX_ROOT: &str = "mail";
+
+const LINK_SELECTOR: &str = "a[href]";
+
+// Venue newsletters. A configuration names a Maildir or mbox under the mailbox root to read, 
+// any other payload is a raw message (EML). Only Maildir messages are marked as seen,
+// an mbox is read whole on every extraction and its items are published again.
+#[derive(Copy, Clone)]
+pub struct DS<'a>{
+    mailbox_root: &'a Path,
+}
+
+impl<'a> DS<'a>{
+    pub fn new(mailbox_root: &'a Path) -> DS<'a>{
+        DS{mailbox_root: mailbox_root}
+    }
+}
+
+#[async_trait]
+impl<'a> Datasource for DS<'a>{
+    async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
+        // Messages start with their headers, so a broken configuration fails instead of passing as a message
+        if !configuration.iter().find(|byte| !byte.is_ascii_whitespace()).map_or(false, |byte| *byte == b'{') {
+            return parse_message(&Part::parse(&configuration));
+        }
+        let ds_config: Configuration = serde_json::from_slice(&configuration)?;
+
+        debug!(mailbox = %ds_config.value, "extracting");
+        let mailbox_path = paths::resolve_within(self.mailbox_root, &ds_config.value)?;
+        let mut report = ExtractionReport::new(Vec::new());
+        for message in mailbox::read(&mailbox_path)? {
+            let ExtractionReport{items, warnings, parsed_fields, ..} = parse_message(&Part::parse(&message.raw))?;
+            report.warnings.extend(warnings);
+            report.parsed_fields += parsed_fields;
+            // Newsletters repeat the same links and events from one issue to the next
+            for item in items {
+                if !report.items.contains(&item) {
+                    report.items.push(item);
+                }
+            }
+
+            // Marked as seen once the items were published, a failed extraction reads it again
+            if let Some(message_path) = message.maildir_path {
+                report.consumed.push(message_path.to_string_lossy().to_string());
+            }
+        }
+        Ok(report)
+    }
+
+    fn commit(&self, consumed: &[String]) {
+        for message_path in consumed {
+            // A message that couldn't be marked would only be extracted again
+            if let Err(e) = mailbox::mark_seen(Path::new(message_path)) {
+                warn!(error = %e, path = %message_path, "Error marking the message as seen");
+            }
+        }
+    }
+
+    fn parse(&self, message: &str) -> ExtractResult{
+        parse_message(&Part::parse(message.as_bytes()))
+    }
+
+    fn get_name(&self) -> String{
+        DS_NAME.to_owned()
+    }
+}
+
+// Events announced in the message, and the pages it links to that a datasource can extract
+fn parse_message(message: &Part) -> ExtractResult {
+    debug!(subject = ?message.header("Subject"), "parsing message");
+    let mut report = ExtractionReport::new(Vec::new());
+
+    for body in message.html_bodies() {
+        let document = Html::parse_document(&body);
+        for event in json_ld::music_events(&document, &mut report) {
+            report.items.push(Extracted::MusicEvent(event));
+        }
+
+        for link in document.select(&Selector::parse(LINK_SELECTOR).unwrap()) {
+            let configuration = link.value().attr("href")
+                .and_then(|href| links::canonicalize(href).ok())
+                .and_then(|url| bachtrack::configuration_for(&url));
+            if let Some(configuration) = configuration {
+                report.field_parsed();
+                let item = Extracted::Configuration(configuration);
+                if !report.items.contains(&item) {
+                    report.items.push(item);
+                }
+            }
+        }
+    }
+    Ok(report)
+}
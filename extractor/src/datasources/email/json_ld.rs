@@ -0,0 +1,157 @@
+use {
+    std::error::Error,
+    scraper::{Html, Selector},
+    serde_json::Value,
+    chrono::{DateTime, NaiveDate, NaiveDateTime, Duration},
+    crate::model::{ExtractionReport, MusicEvent, EventTime, EndTimePrecision, Person, Piece},
+    crate::model::errors::{UnrecognizedDateTimeError, MissingDataInHtmlError, DateTimeCalculationError},
+    crate::model::duration,
+};
+
+pub const JSON_LD_SELECTOR: &str = "script[type='application/ld+json']";
+const DEFAULT_EVENT_LENGTH: i64 = 2;
+
+// schema.org events in the page's JSON-LD blocks, wherever they're nested (@graph, reservations, sub events)
+pub fn music_events(document: &Html, report: &mut ExtractionReport) -> Vec<MusicEvent> {
+    let mut events = Vec::new();
+    for script in document.select(&Selector::parse(JSON_LD_SELECTOR).unwrap()) {
+        let text = script.text().collect::<String>();
+        let value: Value = match serde_json::from_str(&text) {
+            Ok(value) => value,
+            Err(e) => {
+                report.field_failed("json_ld", JSON_LD_SELECTOR, &text, e);
+                continue;
+            }
+        };
+
+        let mut event_objects = Vec::new();
+        find_events(&value, &mut event_objects);
+        for event in event_objects {
+            match music_event(event) {
+                Ok(music_event) => {
+                    report.field_parsed();
+                    events.push(music_event);
+                },
+                Err(e) => report.field_failed("time", JSON_LD_SELECTOR, &event.to_string(), e),
+            }
+        }
+    }
+    events
+}
+
+fn find_events<'a>(value: &'a Value, events: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(values) => values.iter().for_each(|value| find_events(value, events)),
+        Value::Object(object) => {
+            if is_event(value) {
+                events.push(value);
+            }
+            object.values().for_each(|value| find_events(value, events));
+        },
+        _ => {},
+    }
+}
+
+// Event and its subtypes (MusicEvent, TheaterEvent, ...)
+fn is_event(value: &Value) -> bool {
+    match &value["@type"] {
+        Value::String(event_type) => event_type.ends_with("Event"),
+        Value::Array(event_types) => event_types.iter().any(|event_type| event_type.as_str().map(|t| t.ends_with("Event")).unwrap_or(false)),
+        _ => false,
+    }
+}
+
+fn music_event(event: &Value) -> Result<MusicEvent, Box<dyn Error>> {
+    let pieces: Vec<Piece> = as_list(&event["workPerformed"])
+        .iter()
+        .filter_map(|work| Some(Piece{
+            name: name(work)?,
+            artists: as_list(&work["composer"]).iter().filter_map(|composer| person(composer)).collect(),
+        }))
+        .collect();
+
+    // Performers first, then the composers of the programme
+    let mut artists: Vec<Person> = as_list(&event["performer"]).iter().filter_map(|performer| person(performer)).collect();
+    for composer in pieces.iter().flat_map(|piece| piece.artists.iter()) {
+        if !artists.contains(composer) {
+            artists.push(composer.to_owned());
+        }
+    }
+
+    let description = event["description"].as_str().or(event["name"].as_str()).unwrap_or("").trim().to_owned();
+    let time = event_time(event, &pieces)?;
+    Ok(MusicEvent{artists: artists, pieces: pieces, description: description, time: time, festival_id: None})
+}
+
+fn event_time(event: &Value, pieces: &[Piece]) -> Result<EventTime, Box<dyn Error>> {
+    let start = event["startDate"].as_str().ok_or(MissingDataInHtmlError)?;
+    let (start_time, local_timezone) = match parse_date_time(start)? {
+        Some(start) => start,
+        // Without a time of day the event is assumed to span the whole day
+        None => {
+            let date = NaiveDate::parse_from_str(start, "%Y-%m-%d")?;
+            return with_length(date.and_hms(0, 0, 0), Duration::days(1), EndTimePrecision::Default, true);
+        }
+    };
+
+    if let Some(end) = event["endDate"].as_str() {
+        if let Some((end_time, _)) = parse_date_time(end)? {
+            return Ok(EventTime{
+                start_time: start_time,
+                end_time: end_time,
+                end_time_precision: EndTimePrecision::Exact,
+                local_timezone: local_timezone,
+            });
+        }
+    }
+    match duration::estimate_programme_length(pieces) {
+        Some(length) => with_length(start_time, length, EndTimePrecision::Estimated, local_timezone),
+        None => with_length(start_time, Duration::hours(DEFAULT_EVENT_LENGTH), EndTimePrecision::Default, local_timezone),
+    }
+}
+
+// Times with an offset are converted to utc, times without one are the venue's local time.
+// Ok(None) for dates without a time of day.
+fn parse_date_time(text: &str) -> Result<Option<(NaiveDateTime, bool)>, Box<dyn Error>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(Some((time.naive_utc(), false)));
+    }
+    for format in &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(Some((time, true)));
+        }
+    }
+    match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(_) => Ok(None),
+        Err(_) => Err(Box::new(UnrecognizedDateTimeError)),
+    }
+}
+
+fn with_length(start_time: NaiveDateTime, length: Duration, precision: EndTimePrecision, local_timezone: bool) -> Result<EventTime, Box<dyn Error>> {
+    Ok(EventTime{
+        start_time: start_time,
+        end_time: start_time.checked_add_signed(length).ok_or(DateTimeCalculationError)?,
+        end_time_precision: precision,
+        local_timezone: local_timezone,
+    })
+}
+
+// JSON-LD properties hold either one value or a list of them
+fn as_list(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(values) => values.iter().collect(),
+        Value::Null => Vec::new(),
+        value => vec![value],
+    }
+}
+
+fn name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.trim().to_owned()),
+        value => value["name"].as_str().map(|name| name.trim().to_owned()),
+    }
+}
+
+fn person(value: &Value) -> Option<Person> {
+    name(value).map(|name| Person{name: name})
+}
@@ -0,0 +1,72 @@
+use {
+    std::io,
+    std::fs,
+    std::path::{Path, PathBuf},
+};
+
+// A message read from a mailbox. Maildir messages know their file, so they can be marked as read.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MailboxMessage {
+    pub raw: Vec<u8>,
+    pub maildir_path: Option<PathBuf>,
+}
+
+// A Maildir directory yields its unread messages, any other file is read as an mbox.
+// Nothing records how far an mbox was read, all of its messages are returned every time
+pub fn read(path: &Path) -> io::Result<Vec<MailboxMessage>> {
+    if path.join("new").is_dir() {
+        read_maildir(path)
+    } else {
+        Ok(split_mbox(&fs::read(path)?)
+            .into_iter()
+            .map(|raw| MailboxMessage{raw: raw, maildir_path: None})
+            .collect())
+    }
+}
+
+fn read_maildir(path: &Path) -> io::Result<Vec<MailboxMessage>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(path.join("new"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    // Maildir file names start with the delivery time
+    paths.sort();
+
+    let mut messages = Vec::new();
+    for message_path in paths {
+        messages.push(MailboxMessage{raw: fs::read(&message_path)?, maildir_path: Some(message_path)});
+    }
+    Ok(messages)
+}
+
+// Moves a Maildir message from new/ to cur/ flagged as seen, so the next read skips it
+pub fn mark_seen(message_path: &Path) -> io::Result<()> {
+    let maildir = message_path.parent().and_then(Path::parent).ok_or(io::ErrorKind::NotFound)?;
+    let file_name = message_path.file_name().ok_or(io::ErrorKind::NotFound)?.to_string_lossy();
+
+    fs::create_dir_all(maildir.join("cur"))?;
+    fs::rename(message_path, maildir.join("cur").join(format!("{}:2,S", file_name)))
+}
+
+// Messages in an mbox start with a "From " line, body lines starting with it were escaped as ">From "
+fn split_mbox(mbox: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut previous_blank = true;
+
+    for line in mbox.split(|byte| *byte == b'\n') {
+        if previous_blank && line.starts_with(b"From ") {
+            messages.extend(current.replace(Vec::new()));
+            previous_blank = false;
+            continue;
+        }
+        previous_blank = line.is_empty() || line == b"\r";
+
+        if let Some(message) = current.as_mut() {
+            let line = if line.starts_with(b">From ") { &line[1..] } else { line };
+            message.extend_from_slice(line);
+            message.push(b'\n');
+        }
+    }
+    messages.extend(current);
+    messages
+}
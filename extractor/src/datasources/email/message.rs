@@ -0,0 +1,175 @@
+use {
+    crate::model::charset::{self, Webpage},
+};
+
+// A MIME entity: the message itself or one of its parts
+#[derive(Debug, PartialEq, Clone)]
+pub struct Part {
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Part {
+    pub fn parse(raw: &[u8]) -> Part {
+        let (head, body) = split_head(raw);
+        Part{headers: parse_headers(head), body: body.to_vec()}
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // The lowercase media type, text/plain when the part doesn't say
+    pub fn media_type(&self) -> String {
+        self.header("Content-Type")
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|media_type| media_type.trim().to_lowercase())
+            .filter(|media_type| !media_type.is_empty())
+            .unwrap_or("text/plain".to_owned())
+    }
+
+    // The body without its Content-Transfer-Encoding
+    pub fn decoded_body(&self) -> Vec<u8> {
+        let transfer_encoding = self.header("Content-Transfer-Encoding").unwrap_or("").trim().to_lowercase();
+        match transfer_encoding.as_str() {
+            "base64" => {
+                let encoded: Vec<u8> = self.body.iter().copied().filter(|byte| !byte.is_ascii_whitespace()).collect();
+                base64::decode(&encoded).unwrap_or_default()
+            },
+            "quoted-printable" => decode_quoted_printable(&self.body),
+            _ => self.body.to_owned(),
+        }
+    }
+
+    pub fn parts(&self) -> Vec<Part> {
+        match parameter(self.header("Content-Type").unwrap_or(""), "boundary") {
+            Some(boundary) if self.media_type().starts_with("multipart/") => split_multipart(&self.body, &boundary)
+                .iter()
+                .map(|raw| Part::parse(raw))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Every html body of the message, nested multiparts included, decoded with the charset each declares
+    pub fn html_bodies(&self) -> Vec<String> {
+        if self.media_type().starts_with("multipart/") {
+            return self.parts().iter().flat_map(|part| part.html_bodies()).collect();
+        }
+        if self.media_type() != "text/html" {
+            return Vec::new();
+        }
+        vec![Webpage::new(self.decoded_body(), self.header("Content-Type")).text()]
+    }
+}
+
+// Headers end at the first empty line, messages use either line ending
+fn split_head(raw: &[u8]) -> (&[u8], &[u8]) {
+    let crlf = raw.windows(4).position(|window| window == b"\r\n\r\n").map(|index| (index, 4));
+    let lf = raw.windows(2).position(|window| window == b"\n\n").map(|index| (index, 2));
+    let split = match (crlf, lf) {
+        (Some(crlf), Some(lf)) => Some(if crlf.0 < lf.0 { crlf } else { lf }),
+        (crlf, lf) => crlf.or(lf),
+    };
+    match split {
+        Some((index, length)) => (&raw[..index], &raw[index + length..]),
+        None => (raw, &[]),
+    }
+}
+
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in charset::decode_text(head).lines() {
+        // Folded headers continue on lines starting with whitespace
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            headers.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+    headers
+}
+
+fn parameter(header: &str, name: &str) -> Option<String> {
+    header.split(';')
+        .skip(1)
+        .filter_map(|parameter| {
+            let mut parts = parameter.splitn(2, '=');
+            let key = parts.next()?.trim();
+            let value = parts.next()?.trim().trim_matches('"');
+            if key.eq_ignore_ascii_case(name) { Some(value.to_owned()) } else { None }
+        })
+        .next()
+}
+
+// The raw parts between the boundary delimiters, the preamble and epilogue are dropped
+fn split_multipart(body: &[u8], boundary: &str) -> Vec<Vec<u8>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let closing_delimiter = format!("--{}--", boundary).into_bytes();
+
+    let mut parts = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    for line in body.split(|byte| *byte == b'\n') {
+        let trimmed = trim_end(line);
+        if trimmed == closing_delimiter.as_slice() {
+            parts.extend(current.take());
+            break;
+        }
+        if trimmed == delimiter.as_slice() {
+            parts.extend(current.replace(Vec::new()));
+            continue;
+        }
+        if let Some(part) = current.as_mut() {
+            part.extend_from_slice(trim_end(line));
+            part.push(b'\n');
+        }
+    }
+    parts.extend(current);
+    parts
+}
+
+fn trim_end(line: &[u8]) -> &[u8] {
+    match line.last() {
+        Some(b'\r') => &line[..line.len() - 1],
+        _ => line,
+    }
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut index = 0;
+    while index < body.len() {
+        if body[index] != b'=' {
+            decoded.push(body[index]);
+            index += 1;
+            continue;
+        }
+        // A soft line break, the line goes on
+        let rest = &body[index + 1..];
+        if rest.starts_with(b"\r\n") {
+            index += 3;
+        } else if rest.starts_with(b"\n") {
+            index += 2;
+        } else {
+            match rest.get(..2).and_then(|hex| std::str::from_utf8(hex).ok()).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 3;
+                },
+                None => {
+                    decoded.push(b'=');
+                    index += 1;
+                },
+            }
+        }
+    }
+    decoded
+}
@@ -0,0 +1,9 @@
+mod datasource;
+mod message;
+mod mailbox;
+mod json_ld;
+
+#[cfg(test)]
+mod tests;
+
+pub use datasource::{DS, DEFAULT_MAILBOX_ROOT};
@@ -0,0 +1,214 @@
+use {
+    std::path::{Path, PathBuf},
+    std::error::Error,
+    std::fs,
+    chrono::NaiveDate,
+    uuid::Uuid,
+    tokio_test,
+    crate::model::{Datasource, Extracted, Configuration, MusicEvent, EventTime, EndTimePrecision, Person, Piece},
+    crate::model::duration,
+    crate::datasources::bachtrack::{listing, festival, review},
+    super::message::Part,
+    super::mailbox,
+};
+
+fn read_test_message() -> Result<Vec<u8>, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    let message_path = Path::new(&base_path).join("resources/tests/email_newsletter");
+    Ok(fs::read(message_path)?)
+}
+
+fn temporary_maildir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("maildir-{}", Uuid::new_v4()));
+    fs::create_dir_all(dir.join("new")).unwrap();
+    dir
+}
+
+fn message(subject: &str) -> String {
+    format!("From: venue@example.org\nSubject: {}\nContent-Type: text/html\n\n<a href=\"https://bachtrack.com/concert-event/{}/1\">{}</a>\n", subject, subject, subject)
+}
+
+#[test]
+fn test_extracor() -> Result<(), Box<dyn Error>>{
+    let report = tokio_test::block_on(super::DS::new(Path::new(super::DEFAULT_MAILBOX_ROOT)).extract(&read_test_message()?))?;
+
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(report.items.len(), 5);
+    assert_eq!(report.items[0], Extracted::MusicEvent(MusicEvent{
+        artists: vec![
+            Person{name: "Wiener Philharmoniker".to_owned()},
+            Person{name: "Riccardo Muti".to_owned()},
+            Person{name: "Bruckner, Anton".to_owned()},
+        ],
+        pieces: vec![Piece{name: "Symphony no. 9 in D minor".to_owned(), artists: vec![Person{name: "Bruckner, Anton".to_owned()}]}],
+        description: "Großes Festspielhaus, Wiener Philharmoniker unter Riccardo Muti".to_owned(),
+        time: EventTime{
+            start_time: NaiveDate::from_ymd(2021, 8, 14).and_hms(9, 0, 0),
+            end_time: NaiveDate::from_ymd(2021, 8, 14).and_hms(11, 0, 0),
+            end_time_precision: EndTimePrecision::Exact,
+            local_timezone: false,
+        },
+        festival_id: None,
+    }));
+
+    let pieces = vec![Piece{name: "Così fan tutte".to_owned(), artists: vec![Person{name: "Mozart, Wolfgang Amadeus".to_owned()}]}];
+    let start_time = NaiveDate::from_ymd(2021, 8, 20).and_hms(19, 0, 0);
+    assert_eq!(report.items[1], Extracted::MusicEvent(MusicEvent{
+        artists: vec![Person{name: "Mozart, Wolfgang Amadeus".to_owned()}],
+        description: "Così fan tutte".to_owned(),
+        time: EventTime{
+            start_time: start_time,
+            end_time: start_time + duration::estimate_programme_length(&pieces).unwrap(),
+            end_time_precision: EndTimePrecision::Estimated,
+            local_timezone: true,
+        },
+        pieces: pieces,
+        festival_id: None,
+    }));
+
+    // The plain text alternative isn't read, the html links are routed to their datasources
+    assert_eq!(report.items[2..].to_vec(), vec![
+        Extracted::Configuration(Configuration{
+            ds_name: listing::DS_NAME.to_owned(),
+            value: "https://bachtrack.com/concert-event/wiener-philharmoniker-muti-grosses-festspielhaus-14-august-2021/341002".to_owned(),
+            id: Some("bachtrack:341002".to_owned()),
+            festival_id: None,
+        }),
+        Extracted::Configuration(Configuration{
+            ds_name: festival::DS_NAME.to_owned(),
+            value: "https://www.bachtrack.com/festival/salzburger-festspiele-2021/1042".to_owned(),
+            id: None,
+            festival_id: None,
+        }),
+        Extracted::Configuration(Configuration{
+            ds_name: review::DS_NAME.to_owned(),
+            value: "https://bachtrack.com/review-coronation-anthems-all-city-chorus-southwark-cathedral-october-2020".to_owned(),
+            id: None,
+            festival_id: None,
+        }),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_json_ld() {
+    let message = "Content-Type: text/html\n\n<script type=\"application/ld+json\">{\"@type\": \"MusicEvent\", \"startDate\": \"next friday\"}</script>";
+    let report = super::DS::new(Path::new(super::DEFAULT_MAILBOX_ROOT)).parse(message).unwrap();
+
+    assert_eq!(report.items, vec![]);
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].reason, "The text did not match any of the known date formats");
+}
+
+#[test]
+fn test_transfer_encodings() {
+    let message = Part::parse(concat!(
+        "Content-Type: multipart/mixed; boundary=outer\r\n\r\n",
+        "--outer\r\n",
+        "Content-Type: multipart/alternative; boundary=\"inner\"\r\n\r\n",
+        "--inner\r\n",
+        "Content-Type: text/html; charset=utf-8\r\n",
+        "Content-Transfer-Encoding: base64\r\n\r\n",
+        "PHA+TcO8bmNoZW48L3A+\r\n",
+        "--inner--\r\n",
+        "--outer\r\n",
+        "Content-Type: text/html; charset=windows-1252\r\n",
+        "Content-Transfer-Encoding: quoted-printable\r\n\r\n",
+        "<p class=3D\"long\">Z=FCrich, Tonhalle =\r\n",
+        "=96 Gro=DFer Saal</p>\r\n",
+        "--outer--\r\n",
+    ).as_bytes());
+
+    assert_eq!(message.html_bodies(), vec![
+        "<p>München</p>".to_owned(),
+        "<p class=\"long\">Zürich, Tonhalle – Großer Saal</p>\n".to_owned(),
+    ]);
+}
+
+#[test]
+fn test_read_mbox() -> Result<(), Box<dyn Error>>{
+    let dir = temporary_maildir();
+    let mbox_path = dir.join("venues.mbox");
+    let escaped = message("second").replace("</a>\n", "</a>\n\n>From the archive\n");
+    fs::write(&mbox_path, format!("From venue@example.org Mon Aug  2 09:00:00 2021\n{}\nFrom venue@example.org Tue Aug  3 09:00:00 2021\n{}", message("first"), escaped))?;
+
+    let messages = mailbox::read(&mbox_path)?;
+    assert_eq!(messages.len(), 2);
+    assert_eq!(Part::parse(&messages[0].raw).header("subject"), Some("first"));
+    assert!(String::from_utf8(messages[1].raw.to_vec())?.contains("\nFrom the archive\n"));
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+fn mailbox_configuration(mailbox: &Path) -> Result<Vec<u8>, Box<dyn Error>>{
+    Ok(serde_json::to_vec(&Configuration{ds_name: super::datasource::DS_NAME.to_owned(), value: mailbox.to_string_lossy().to_string(), id: None, festival_id: None})?)
+}
+
+#[test]
+fn test_maildir_messages_are_read_once() -> Result<(), Box<dyn Error>>{
+    let dir = temporary_maildir();
+    fs::write(dir.join("new").join("1627887600.1.venue"), message("first"))?;
+    fs::write(dir.join("new").join("1627974000.2.venue"), message("second"))?;
+    let datasource = super::DS::new(&dir);
+    // Relative to the mailbox root
+    let configuration = mailbox_configuration(Path::new("."))?;
+
+    // Until the items were published the messages stay unread
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    assert_eq!(report.items.len(), 2);
+    assert_eq!(report.consumed.len(), 2);
+    assert!(!dir.join("cur").join("1627887600.1.venue:2,S").exists());
+
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    assert_eq!(report.items.len(), 2);
+    datasource.commit(&report.consumed);
+    assert!(dir.join("cur").join("1627887600.1.venue:2,S").exists());
+
+    let report = tokio_test::block_on(datasource.extract(&configuration))?;
+    assert_eq!(report.items, vec![]);
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_mailboxes_outside_the_root() -> Result<(), Box<dyn Error>>{
+    let dir = temporary_maildir();
+    let root = dir.join("mail");
+    fs::create_dir_all(&root)?;
+    fs::write(dir.join("other.mbox"), format!("From venue@example.org Mon Aug  2 09:00:00 2021\n{}", message("first")))?;
+    let datasource = super::DS::new(&root);
+
+    for mailbox in &[dir.join("other.mbox"), Path::new("..").join("other.mbox")] {
+        let error = tokio_test::block_on(datasource.extract(&mailbox_configuration(mailbox)?)).unwrap_err();
+        assert_eq!(error.to_string(), "The path is outside the directory the datasource may read from");
+    }
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_malformed_configuration_fails() {
+    let datasource = super::DS::new(Path::new("."));
+    let configuration = br#" {"ds_name": "datasource.email_newsletter", "mailbox": "venues"}"#.to_vec();
+    assert!(tokio_test::block_on(datasource.extract(&configuration)).is_err());
+}
+
+#[test]
+fn test_configuration_for() {
+    let cases = vec![
+        ("https://bachtrack.com/concert-event/requiem/318719?utm_source=x", Some(listing::DS_NAME)),
+        ("https://bachtrack.com/festival/salzburger-festspiele-2021/1042", Some(festival::DS_NAME)),
+        ("https://bachtrack.com/review-requiem-london", Some(review::DS_NAME)),
+        ("https://bachtrack.com/find-concerts/", None),
+        ("https://example.org/concert-event/1", None),
+        ("mailto:info@example.org", None),
+    ];
+
+    for (url, expected) in cases {
+        let ds_name = crate::datasources::bachtrack::configuration_for(url).map(|configuration| configuration.ds_name);
+        assert_eq!(ds_name.as_deref(), expected, "routing {}", url);
+    }
+}
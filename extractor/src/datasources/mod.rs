@@ -1 +1,2 @@
-pub mod bachtrack;
\ No newline at end of file
+pub mod bachtrack;pub mod email;
+pub mod spreadsheet;
@@ -0,0 +1,319 @@
+use {
+    std::fs,
+    std::collections::HashSet,
+    std::path::Path,
+    std::error::Error,
+    chrono::{NaiveDate, NaiveDateTime, NaiveTime, Duration},
+    async_trait::async_trait,
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted, MusicEvent, EventTime, EndTimePrecision, Person, Piece, Venue, City, Country},
+    crate::model::errors::{MissingColumnError, IncompleteRowError, EmptyImportError, ImportOnDiskError, UnrecognizedDateTimeError, DateTimeCalculationError},
+    crate::model::{charset, date_parser, duration, paths},
+    tracing::debug,
+    super::profile::{ImportConfiguration, ProfileSource, Profile, Format},
+    super::table,
+};
+
+pub const DS_NAME: &str = "datasource.spreadsheet_import";
+// Files and profiles named in configurations are looked up in here, relative to the working directory
+pub const DEFAULT_IMPORT_DIR: &str = "imports";
+const DEFAULT_EVENT_LENGTH: i64 = 2;
+
+// Season programmes partners send as csv or xlsx, read with the column mapping profile of the configuration
+#[derive(Copy, Clone)]
+pub struct DS<'a>{
+    import_dir: &'a Path,
+}
+
+impl<'a> DS<'a>{
+    pub fn new(import_dir: &'a Path) -> DS<'a>{
+        DS{import_dir: import_dir}
+    }
+}
+
+#[async_trait]
+impl<'a> Datasource for DS<'a>{
+    async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
+        let configuration: ImportConfiguration = serde_json::from_str(&charset::decode_text(&configuration))?;
+        debug!(path = ?configuration.path, format = ?configuration.format(), "importing");
+
+        let profile = configuration.profile.load(self.import_dir)?;
+        let bytes = match &configuration.path {
+            Some(path) => fs::read(paths::resolve_within(self.import_dir, path)?)?,
+            None => content(&configuration)?,
+        };
+        import(&bytes, configuration.format(), &profile)
+    }
+
+    // Only for configurations carrying their file and profile, files on disk are read by extract
+    fn parse(&self, configuration: &str) -> ExtractResult{
+        let configuration: ImportConfiguration = serde_json::from_str(configuration)?;
+        let profile = match (&configuration.path, &configuration.profile) {
+            (None, ProfileSource::Inline(profile)) => profile,
+            _ => return Err(Box::new(ImportOnDiskError)),
+        };
+        import(&content(&configuration)?, configuration.format(), profile)
+    }
+
+    fn get_name(&self) -> String{
+        DS_NAME.to_owned()
+    }
+}
+
+// The file carried in the configuration
+fn content(configuration: &ImportConfiguration) -> Result<Vec<u8>, Box<dyn Error>> {
+    match &configuration.content {
+        Some(content) if configuration.format() == Format::Xlsx => Ok(base64::decode(content)?),
+        Some(content) => Ok(content.as_bytes().to_vec()),
+        None => Err(Box::new(EmptyImportError)),
+    }
+}
+
+fn import(bytes: &[u8], format: Format, profile: &Profile) -> ExtractResult {
+    let rows = match format {
+        Format::Csv => table::read_csv(bytes, profile.delimiter),
+        Format::Xlsx => table::read_xlsx(bytes)?,
+    };
+    import_rows(&rows, profile)
+}
+
+// A row of the file, with the header's column positions
+struct Row<'a> {
+    number: usize,
+    cells: &'a [String],
+    header: &'a [String],
+}
+
+impl<'a> Row<'a> {
+    // The trimmed cell of the mapped column, None when it's empty or the column isn't mapped
+    fn cell(&self, column: &Option<String>) -> Option<(String, &'a str)> {
+        let column = column.as_ref()?;
+        let index = self.header.iter().position(|name| name.trim().eq_ignore_ascii_case(column.trim()))?;
+        let cell = self.cells.get(index)?.trim();
+        if cell.is_empty() {
+            return None;
+        }
+        Some((self.location(column), cell))
+    }
+
+    fn location(&self, column: &str) -> String {
+        format!("row {}, column {}", self.number, column)
+    }
+
+    fn is_blank(&self) -> bool {
+        self.cells.iter().all(|cell| cell.trim().is_empty())
+    }
+}
+
+fn import_rows(rows: &[Vec<String>], profile: &Profile) -> ExtractResult {
+    let mut report = ExtractionReport::new(Vec::new());
+    let header = match rows.first() {
+        Some(header) => header,
+        None => return Ok(report),
+    };
+    check_header(header, profile, &mut report)?;
+
+    // Serialized, a season's thousands of items aren't each compared with all the others
+    let mut imported = HashSet::new();
+
+    for (index, cells) in rows.iter().enumerate().skip(1) {
+        // Numbered like the spreadsheet shows them, the header is row 1
+        let row = Row{number: index + 1, cells: cells, header: header};
+        if row.is_blank() {
+            continue;
+        }
+        for item in import_row(&row, profile, &mut report) {
+            // Performers, pieces and venues come back on every row of a season
+            if imported.insert(serde_json::to_string(&item)?) {
+                report.items.push(item);
+            }
+        }
+    }
+    Ok(report)
+}
+
+// Mapped columns missing from the file, without a date column nothing can be imported
+fn check_header(header: &[String], profile: &Profile, report: &mut ExtractionReport) -> Result<(), Box<dyn Error>> {
+    let columns = &profile.columns;
+    let mapped = vec![
+        ("date", Some(&columns.date)), ("start_time", columns.start_time.as_ref()), ("end_time", columns.end_time.as_ref()),
+        ("description", columns.description.as_ref()), ("performers", columns.performers.as_ref()),
+        ("pieces", columns.pieces.as_ref()), ("composers", columns.composers.as_ref()), ("venue", columns.venue.as_ref()),
+        ("address", columns.address.as_ref()), ("city", columns.city.as_ref()), ("country", columns.country.as_ref()),
+    ];
+
+    for (field, column) in mapped {
+        let column = match column {
+            Some(column) => column,
+            None => continue,
+        };
+        if header.iter().any(|name| name.trim().eq_ignore_ascii_case(column.trim())) {
+            continue;
+        }
+        if field == "date" {
+            return Err(Box::new(MissingColumnError));
+        }
+        report.field_failed(field, "row 1", column, MissingColumnError);
+    }
+    Ok(())
+}
+
+// The row's event and what it refers to. Rows without a valid date or start time are skipped,
+// the other fields are dropped on their own.
+fn import_row(row: &Row, profile: &Profile, report: &mut ExtractionReport) -> Vec<Extracted> {
+    let columns = &profile.columns;
+
+    let date = match row.cell(&Some(columns.date.to_owned())) {
+        Some((location, text)) => match parse_date(text, profile) {
+            Ok(date) => date,
+            Err(e) => {
+                report.field_failed("date", &location, text, e);
+                return Vec::new();
+            }
+        },
+        None => {
+            report.field_failed("date", &row.location(&columns.date), "", IncompleteRowError);
+            return Vec::new();
+        }
+    };
+    report.field_parsed();
+
+    let start_time = match row.cell(&columns.start_time) {
+        Some((location, text)) => match parse_time(text, profile) {
+            Ok(time) => {
+                report.field_parsed();
+                Some(date.and_time(time))
+            },
+            Err(e) => {
+                report.field_failed("start_time", &location, text, e);
+                return Vec::new();
+            }
+        },
+        None => None,
+    };
+    let end_time = row.cell(&columns.end_time).and_then(|(location, text)| match parse_time(text, profile) {
+        Ok(time) => {
+            report.field_parsed();
+            Some(time)
+        },
+        Err(e) => {
+            report.field_failed("end_time", &location, text, e);
+            None
+        }
+    });
+
+    let separator = profile.list_separator.as_str();
+    let performers = list(row.cell(&columns.performers), separator);
+    let composers = list(row.cell(&columns.composers), separator);
+    let pieces: Vec<Piece> = list(row.cell(&columns.pieces), separator)
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| Piece{
+            name: name,
+            artists: composers.get(index).or(if composers.len() == 1 { composers.first() } else { None })
+                .map(|composer| vec![Person{name: composer.to_owned()}])
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let mut artists: Vec<Person> = performers.iter().map(|name| Person{name: name.to_owned()}).collect();
+    for composer in pieces.iter().flat_map(|piece| piece.artists.iter()) {
+        if !artists.contains(composer) {
+            artists.push(composer.to_owned());
+        }
+    }
+
+    let time = match event_time(date, start_time, end_time, &pieces) {
+        Ok(time) => time,
+        Err(e) => {
+            report.field_failed("time", &row.location(&columns.date), &date.to_string(), e);
+            return Vec::new();
+        }
+    };
+
+    let mut items = vec![Extracted::MusicEvent(MusicEvent{
+        artists: artists.to_vec(),
+        pieces: pieces.to_vec(),
+        description: row.cell(&columns.description).map(|(_, text)| text.to_owned()).unwrap_or_default(),
+        time: time,
+        festival_id: None,
+    })];
+    items.extend(venue(row, profile, report).map(Extracted::Venue));
+    items.extend(artists.into_iter().map(Extracted::Person));
+    items.extend(pieces.into_iter().map(Extracted::Piece));
+    items
+}
+
+// The profile's format, or the ISO one xlsx cells are read as
+fn parse_date(text: &str, profile: &Profile) -> Result<NaiveDate, UnrecognizedDateTimeError> {
+    NaiveDate::parse_from_str(text, &profile.date_format)
+        .or(NaiveDate::parse_from_str(text, "%Y-%m-%d"))
+        .map_err(|_| UnrecognizedDateTimeError)
+}
+
+fn parse_time(text: &str, profile: &Profile) -> Result<NaiveTime, UnrecognizedDateTimeError> {
+    NaiveTime::parse_from_str(text, &profile.time_format)
+        .or(NaiveTime::parse_from_str(text, "%H:%M"))
+        .map_err(|_| UnrecognizedDateTimeError)
+}
+
+fn list(cell: Option<(String, &str)>, separator: &str) -> Vec<String> {
+    match cell {
+        Some((_, text)) => text.split(separator)
+            .map(|value| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Like scraped listings: the given end, else an estimate from the programme, else the default length
+fn event_time(date: NaiveDate, start_time: Option<NaiveDateTime>, end_time: Option<NaiveTime>, pieces: &[Piece]) -> Result<EventTime, Box<dyn Error>> {
+    let start_time = match start_time {
+        Some(start_time) => start_time,
+        // Without a time of day the event is assumed to span the whole day
+        None => return with_length(date.and_hms(0, 0, 0), Duration::days(1), EndTimePrecision::Default),
+    };
+
+    if let Some(end_time) = end_time {
+        return Ok(EventTime{
+            start_time: start_time,
+            end_time: date_parser::end_time_after(start_time, end_time)?,
+            end_time_precision: EndTimePrecision::Exact,
+            local_timezone: true,
+        });
+    }
+    match duration::estimate_programme_length(pieces) {
+        Some(length) => with_length(start_time, length, EndTimePrecision::Estimated),
+        None => with_length(start_time, Duration::hours(DEFAULT_EVENT_LENGTH), EndTimePrecision::Default),
+    }
+}
+
+fn with_length(start_time: NaiveDateTime, length: Duration, precision: EndTimePrecision) -> Result<EventTime, Box<dyn Error>> {
+    Ok(EventTime{
+        start_time: start_time,
+        end_time: start_time.checked_add_signed(length).ok_or(DateTimeCalculationError)?,
+        end_time_precision: precision,
+        local_timezone: true,
+    })
+}
+
+// A venue is only useful to the normalizer with its city and country
+fn venue(row: &Row, profile: &Profile, report: &mut ExtractionReport) -> Option<Venue> {
+    let columns = &profile.columns;
+    let (location, name) = row.cell(&columns.venue)?;
+
+    match (row.cell(&columns.city), row.cell(&columns.country)) {
+        (Some((_, city)), Some((_, country))) => {
+            report.field_parsed();
+            Some(Venue{
+                name: name.to_owned(),
+                address: row.cell(&columns.address).map(|(_, address)| address.to_owned()).unwrap_or_default(),
+                city: City{name: city.to_owned(), country: Country{name: country.to_owned()}},
+            })
+        },
+        _ => {
+            report.field_failed("venue", &location, name, IncompleteRowError);
+            None
+        }
+    }
+}
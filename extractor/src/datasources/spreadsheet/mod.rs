@@ -0,0 +1,8 @@
+mod datasource;
+mod profile;
+mod table;
+
+#[cfg(test)]
+mod tests;
+
+pub use datasource::{DS, DEFAULT_IMPORT_DIR};
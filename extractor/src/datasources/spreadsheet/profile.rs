@@ -0,0 +1,116 @@
+use {
+    std::error::Error,
+    std::fs,
+    std::path::Path,
+    serde::Deserialize,
+    crate::model::paths,
+};
+
+#[derive(Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Csv,
+    Xlsx,
+}
+
+// What to import and how to read it. The file is either on disk (path, within the import directory)
+// or in the payload (content, base64 encoded for xlsx).
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ImportConfiguration {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    // Taken from the path's extension when not given, csv otherwise
+    #[serde(default)]
+    pub format: Option<Format>,
+    pub profile: ProfileSource,
+}
+
+impl ImportConfiguration {
+    pub fn format(&self) -> Format {
+        match (self.format, &self.path) {
+            (Some(format), _) => format,
+            (None, Some(path)) if path.to_lowercase().ends_with(".xlsx") => Format::Xlsx,
+            _ => Format::Csv,
+        }
+    }
+}
+
+// Partners keep sending the same layout, so their profile is usually kept in a file of the import directory
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ProfileSource {
+    Inline(Profile),
+    Path(String),
+}
+
+impl ProfileSource {
+    pub fn load(&self, import_dir: &Path) -> Result<Profile, Box<dyn Error>> {
+        match self {
+            ProfileSource::Inline(profile) => Ok(profile.to_owned()),
+            ProfileSource::Path(path) => Ok(serde_json::from_str(&fs::read_to_string(paths::resolve_within(import_dir, path)?)?)?),
+        }
+    }
+}
+
+// How a partner's columns map to our fields
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub columns: ColumnMapping,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    // Separates the values of a cell holding several performers, pieces or composers
+    #[serde(default = "default_list_separator")]
+    pub list_separator: String,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+}
+
+// Header names of the columns holding each field, only the date is required
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnMapping {
+    pub date: String,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub performers: Option<String>,
+    #[serde(default)]
+    pub pieces: Option<String>,
+    // One composer per piece, or one for the whole programme
+    #[serde(default)]
+    pub composers: Option<String>,
+    #[serde(default)]
+    pub venue: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_list_separator() -> String {
+    ";".to_owned()
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_owned()
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_owned()
+}
@@ -0,0 +1,85 @@
+use {
+    std::error::Error,
+    crate::model::charset,
+};
+
+// Rows of cells as RFC 4180 describes them: quoted cells may hold delimiters, line breaks and doubled quotes
+pub fn read_csv(bytes: &[u8], delimiter: char) -> Vec<Vec<String>> {
+    // Spreadsheet programs often export in the system's legacy encoding
+    let text = charset::decode_text(bytes);
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    cell.push('"');
+                    chars.next();
+                },
+                '"' => quoted = false,
+                c => cell.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if cell.is_empty() => quoted = true,
+            c if c == delimiter => row.push(std::mem::replace(&mut cell, String::new())),
+            '\r' => {},
+            '\n' => {
+                row.push(std::mem::replace(&mut cell, String::new()));
+                rows.push(std::mem::replace(&mut row, Vec::new()));
+            },
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    rows
+}
+
+// The first sheet, dates and times written the way the default profile formats read them
+#[cfg(feature = "xlsx")]
+pub fn read_xlsx(bytes: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    use {
+        std::io::Cursor,
+        calamine::{Reader, Xlsx, DataType},
+        crate::model::errors::MissingColumnError,
+    };
+
+    let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(bytes.to_vec()))?;
+    let range = workbook.worksheet_range_at(0).ok_or(MissingColumnError)??;
+    Ok(range.rows()
+        .map(|row| row.iter().map(|cell| match cell {
+            DataType::DateTime(serial) => excel_date_time(*serial),
+            DataType::Empty => String::new(),
+            cell => cell.to_string(),
+        }).collect())
+        .collect())
+}
+
+#[cfg(not(feature = "xlsx"))]
+pub fn read_xlsx(_bytes: &[u8]) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    Err(Box::new(crate::model::errors::XlsxUnsupportedError))
+}
+
+// Excel counts days since 1899-12-30, the fraction is the time of day
+#[cfg(feature = "xlsx")]
+fn excel_date_time(serial: f64) -> String {
+    use chrono::{NaiveDate, Duration};
+
+    let date_time = NaiveDate::from_ymd(1899, 12, 30).and_hms(0, 0, 0) + Duration::seconds((serial * 86400.0).round() as i64);
+    if serial < 1.0 {
+        date_time.format("%H:%M").to_string()
+    } else if serial.fract() == 0.0 {
+        date_time.format("%Y-%m-%d").to_string()
+    } else {
+        date_time.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
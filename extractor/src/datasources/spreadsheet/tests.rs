@@ -0,0 +1,173 @@
+use {
+    std::path::{Path, PathBuf},
+    std::error::Error,
+    std::fs,
+    chrono::NaiveDate,
+    uuid::Uuid,
+    tokio_test,
+    crate::model::{Datasource, Extracted, ExtractionWarning, MusicEvent, EventTime, EndTimePrecision, Person, Piece, Venue, City, Country},
+    crate::model::duration,
+    super::table,
+};
+
+fn fixture_dir() -> Result<PathBuf, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    Ok(Path::new(&base_path).join("resources/tests"))
+}
+
+const PROFILE: &str = r#"{
+    "delimiter": ";",
+    "list_separator": "|",
+    "date_format": "%d.%m.%Y",
+    "columns": {
+        "date": "Datum", "start_time": "Beginn", "end_time": "Ende", "description": "Titel",
+        "performers": "Interpreten", "pieces": "Werke", "composers": "Komponisten",
+        "venue": "Spielstätte", "address": "Adresse", "city": "Stadt", "country": "Land"
+    }
+}"#;
+
+fn person(name: &str) -> Person {
+    Person{name: name.to_owned()}
+}
+
+fn warning(field: &str, selector: &str, raw_text: &str, reason: &str) -> ExtractionWarning {
+    ExtractionWarning{field: field.to_owned(), selector: selector.to_owned(), raw_text: raw_text.to_owned(), reason: reason.to_owned()}
+}
+
+#[test]
+fn test_extracor() -> Result<(), Box<dyn Error>>{
+    let configuration = format!(r#"{{"path": "spreadsheet_import", "format": "csv", "profile": {}}}"#, PROFILE);
+    let report = tokio_test::block_on(super::DS::new(&fixture_dir()?).extract(&configuration.as_bytes().to_vec()))?;
+
+    assert_eq!(report.warnings, vec![
+        warning("date", "row 5, column Datum", "31.09.2021", "The text did not match any of the known date formats"),
+        warning("venue", "row 6, column Spielstätte", "Mozarteum", "The row is missing a value the field needs"),
+        warning("start_time", "row 7, column Beginn", "abends", "The text did not match any of the known date formats"),
+    ]);
+    assert_eq!(report.items.len(), 16);
+
+    let salzburg = City{name: "Salzburg".to_owned(), country: Country{name: "Österreich".to_owned()}};
+    let symphony = Piece{name: "Symphony no. 9 in D minor".to_owned(), artists: vec![person("Bruckner, Anton")]};
+    assert_eq!(report.items[..6].to_vec(), vec![
+        Extracted::MusicEvent(MusicEvent{
+            artists: vec![person("Wiener Philharmoniker"), person("Riccardo Muti"), person("Bruckner, Anton")],
+            pieces: vec![symphony.to_owned()],
+            description: "Wiener Philharmoniker; Muti".to_owned(),
+            time: EventTime{
+                start_time: NaiveDate::from_ymd(2021, 8, 14).and_hms(11, 0, 0),
+                end_time: NaiveDate::from_ymd(2021, 8, 14).and_hms(13, 0, 0),
+                end_time_precision: EndTimePrecision::Exact,
+                local_timezone: true,
+            },
+            festival_id: None,
+        }),
+        Extracted::Venue(Venue{name: "Großes Festspielhaus".to_owned(), address: "Hofstallgasse 1".to_owned(), city: salzburg}),
+        Extracted::Person(person("Wiener Philharmoniker")),
+        Extracted::Person(person("Riccardo Muti")),
+        Extracted::Person(person("Bruckner, Anton")),
+        Extracted::Piece(symphony),
+    ]);
+
+    let pieces = vec![Piece{name: "Così fan tutte".to_owned(), artists: vec![person("Mozart, Wolfgang Amadeus")]}];
+    let start_time = NaiveDate::from_ymd(2021, 8, 20).and_hms(19, 0, 0);
+    match &report.items[6] {
+        Extracted::MusicEvent(event) => assert_eq!(event.time, EventTime{
+            start_time: start_time,
+            end_time: start_time + duration::estimate_programme_length(&pieces).unwrap(),
+            end_time_precision: EndTimePrecision::Estimated,
+            local_timezone: true,
+        }),
+        item => panic!("expected an event, got {:?}", item),
+    }
+
+    // Without a start time the recital spans the day, and still comes without its incomplete venue
+    match &report.items[11] {
+        Extracted::MusicEvent(event) => {
+            assert_eq!(event.description, "Liederabend \"Winterreise\"");
+            assert_eq!((event.time.start_time, event.time.end_time_precision), (NaiveDate::from_ymd(2021, 9, 2).and_hms(0, 0, 0), EndTimePrecision::Default));
+        },
+        item => panic!("expected an event, got {:?}", item),
+    }
+    assert_eq!(report.items[12], Extracted::Person(person("Matthias Goerne")));
+    Ok(())
+}
+
+#[test]
+fn test_inline_content_and_profile_path() -> Result<(), Box<dyn Error>>{
+    let import_dir = std::env::temp_dir().join(format!("imports-{}", Uuid::new_v4()));
+    fs::create_dir_all(&import_dir)?;
+    fs::write(import_dir.join("profile.json"), r#"{"columns": {"date": "date", "start_time": "time", "performers": "artist", "venue": "hall"}}"#)?;
+
+    let configuration = serde_json::json!({
+        "content": "date,time,artist,unused\n2021-10-01,19:30,Igor Levit,x\n",
+        "profile": "profile.json",
+    });
+    let report = tokio_test::block_on(super::DS::new(&import_dir).extract(&configuration.to_string().into_bytes()))?;
+
+    assert_eq!(report.warnings, vec![warning("venue", "row 1", "hall", "The file has no column for a field the profile requires")]);
+    assert_eq!(report.items.len(), 2);
+    assert_eq!(report.items[1], Extracted::Person(person("Igor Levit")));
+
+    fs::remove_dir_all(import_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_paths_outside_the_import_dir() -> Result<(), Box<dyn Error>>{
+    let import_dir = std::env::temp_dir().join(format!("imports-{}", Uuid::new_v4()));
+    fs::create_dir_all(&import_dir)?;
+    let datasource = super::DS::new(&import_dir);
+    let fixture = fixture_dir()?.join("spreadsheet_import").to_string_lossy().to_string();
+
+    let configurations = vec![
+        serde_json::json!({"path": fixture, "profile": {"columns": {"date": "Datum"}}}),
+        serde_json::json!({"content": "date\n2021-10-01\n", "profile": fixture}),
+    ];
+    for configuration in configurations {
+        let error = tokio_test::block_on(datasource.extract(&configuration.to_string().into_bytes())).unwrap_err();
+        assert_eq!(error.to_string(), "The path is outside the directory the datasource may read from");
+    }
+
+    fs::remove_dir_all(import_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_missing_date_column() {
+    let configuration = r#"{"content": "when,artist\n2021-10-01,Igor Levit\n", "profile": {"columns": {"date": "date"}}}"#;
+
+    let error = super::DS::new(Path::new(super::DEFAULT_IMPORT_DIR)).parse(configuration).unwrap_err();
+    assert_eq!(error.to_string(), "The file has no column for a field the profile requires");
+}
+
+// Reprocessing never touches the disk
+#[test]
+fn test_parse_needs_inline_imports() {
+    let datasource = super::DS::new(Path::new(super::DEFAULT_IMPORT_DIR));
+    let configurations = vec![
+        r#"{"path": "season.csv", "profile": {"columns": {"date": "date"}}}"#,
+        r#"{"content": "date\n2021-10-01\n", "profile": "profile.json"}"#,
+    ];
+    for configuration in configurations {
+        assert_eq!(datasource.parse(configuration).unwrap_err().to_string(), "The import's file or profile is on disk, only an extraction reads it");
+    }
+}
+
+#[test]
+#[cfg(not(feature = "xlsx"))]
+fn test_xlsx_needs_feature() {
+    let configuration = r#"{"content": "", "format": "xlsx", "profile": {"columns": {"date": "date"}}}"#;
+
+    assert!(super::DS::new(Path::new(super::DEFAULT_IMPORT_DIR)).parse(configuration).is_err());
+}
+
+#[test]
+fn test_read_csv() {
+    let rows = table::read_csv(b"name,notes\r\n\"Levit, Igor\",\"plays \"\"Bach\"\"\nand Beethoven\"\r\nSchiff,\n", ',');
+
+    assert_eq!(rows, vec![
+        vec!["name".to_owned(), "notes".to_owned()],
+        vec!["Levit, Igor".to_owned(), "plays \"Bach\"\nand Beethoven".to_owned()],
+        vec!["Schiff".to_owned(), "".to_owned()],
+    ]);
+}
@@ -0,0 +1,118 @@
+use {
+    std::io,
+    std::time::Duration,
+    serde::{Serialize, Deserialize},
+    tracing::warn,
+    crate::nats::asynk::{Connection, Message},
+    crate::bus::Outcome,
+    super::api,
+    super::consumer::MAX_DELIVER,
+};
+
+// Retries back off linearly, the n-th redelivery waits n times this
+const NAK_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DeadLetter {
+    pub subject: String,
+    pub reason: String,
+    pub deliveries: i64,
+    // Base64, so compressed frames and binary attachments can be replayed as they were published
+    pub payload: String,
+}
+
+impl DeadLetter {
+    pub fn new(subject: &str, reason: &str, deliveries: i64, data: &[u8]) -> DeadLetter {
+        DeadLetter{
+            subject: subject.to_owned(),
+            reason: reason.to_owned(),
+            deliveries: deliveries,
+            payload: base64::encode(data),
+        }
+    }
+
+    // The payload as it was published
+    pub fn data(&self) -> io::Result<Vec<u8>> {
+        base64::decode(&self.payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// What the server publishes when a message ran out of deliveries without being settled
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct MaxDeliveriesAdvisory {
+    pub stream: String,
+    pub consumer: String,
+    pub stream_seq: u64,
+    pub deliveries: i64,
+}
+
+// Delivery metadata encoded in the reply subject of a JetStream message
+#[derive(Debug, PartialEq, Clone)]
+pub struct AckInfo {
+    pub stream: String,
+    pub consumer: String,
+    pub deliveries: i64,
+    pub stream_sequence: u64,
+}
+
+impl AckInfo {
+    pub fn parse(reply: &str) -> Option<AckInfo> {
+        let tokens: Vec<&str> = reply.split('.').collect();
+        if tokens.len() < 9 || tokens[0] != "$JS" || tokens[1] != "ACK" {
+            return None;
+        }
+
+        // Newer servers add a domain and an account hash after the prefix
+        let offset = if tokens.len() >= 11 { 4 } else { 2 };
+        Some(AckInfo{
+            stream: tokens[offset].to_owned(),
+            consumer: tokens[offset + 1].to_owned(),
+            deliveries: tokens[offset + 2].parse().ok()?,
+            stream_sequence: tokens[offset + 3].parse().ok()?,
+        })
+    }
+}
+
+// Acknowledges a processed message, schedules a redelivery or moves it to the dead letter queue.
+pub async fn settle(nc: &Connection, message: &Message, outcome: &Outcome) -> io::Result<()> {
+    let reply = match &message.reply {
+        Some(reply) => reply,
+        None => return Ok(()),
+    };
+    let deliveries = AckInfo::parse(reply).map(|info| info.deliveries).unwrap_or(1);
+
+    match outcome {
+        Outcome::Done => nc.publish(reply, b"+ACK").await,
+        Outcome::Retry(reason) if deliveries < MAX_DELIVER => {
+            warn!(%reason, deliveries, "retrying message");
+            let delay = NAK_DELAY * deliveries as u32;
+            nc.publish(reply, format!("-NAK {{\"delay\": {}}}", delay.as_nanos())).await
+        },
+        Outcome::Retry(reason) | Outcome::Fail(reason) => {
+            dead_letter(nc, &message.subject, &message.data, reason, deliveries).await?;
+            nc.publish(reply, b"+TERM").await
+        },
+    }
+}
+
+// Resets the ack deadline of a message still being worked on, so it isn't redelivered meanwhile
+pub async fn in_progress(nc: &Connection, message: &Message) -> io::Result<()> {
+    match &message.reply {
+        Some(reply) => nc.publish(reply, b"+WPI").await,
+        None => Ok(()),
+    }
+}
+
+// The server drops messages whose deliveries all timed out, the advisory is the last chance to keep them
+pub async fn dead_letter_exhausted(nc: &Connection, advisory: &[u8]) -> io::Result<()> {
+    let advisory: MaxDeliveriesAdvisory = serde_json::from_slice(advisory)?;
+    let (subject, data) = api::get_message(nc, &advisory.stream, advisory.stream_seq).await?;
+    dead_letter(nc, &subject, &data, "not settled before its deliveries ran out", advisory.deliveries).await
+}
+
+async fn dead_letter(nc: &Connection, subject: &str, data: &[u8], reason: &str, deliveries: i64) -> io::Result<()> {
+    warn!(%reason, deliveries, "moving message to the dead letter queue");
+
+    let dead_letter = DeadLetter::new(subject, reason, deliveries, data);
+    api::publish(nc, &format!("dlq.{}", subject), &serde_json::to_vec(&dead_letter)?).await
+}
@@ -0,0 +1,112 @@
+use {
+    std::io,
+    std::time::Duration,
+    serde::{Serialize, Deserialize},
+    crate::nats::asynk::Connection,
+};
+
+// Requests to a missing responder never complete with our client, so every api call is bounded
+const API_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug)]
+struct ApiError {
+    code: u16,
+    description: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiResponse {
+    error: Option<ApiError>,
+}
+
+#[derive(Serialize, Debug)]
+struct StreamConfig<'a> {
+    name: &'a str,
+    subjects: Vec<&'a str>,
+    storage: &'a str,
+    // In nanoseconds, messages are kept until deleted without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_age: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PubAck {
+    seq: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct StoredMessage {
+    subject: String,
+    // Base64
+    data: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GetMessageResponse {
+    message: StoredMessage,
+}
+
+pub async fn request(nc: &Connection, subject: &str, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let response = match tokio::time::timeout(API_TIMEOUT, nc.request(subject, payload)).await {
+        Ok(response) => response?,
+        Err(_) => return Err(io::Error::new(io::ErrorKind::TimedOut, format!("no response on '{}', is JetStream enabled?", subject))),
+    };
+
+    let api_response: ApiResponse = serde_json::from_slice(&response.data)?;
+    if let Some(error) = api_response.error {
+        return Err(io::Error::new(io::ErrorKind::Other, format!("{} ({})", error.description, error.code)));
+    }
+
+    Ok(response.data)
+}
+
+// Creates the stream unless it already exists.
+pub async fn ensure_stream(nc: &Connection, stream: (&str, &str)) -> io::Result<()> {
+    create_stream(nc, stream, None).await
+}
+
+// Like ensure_stream, for streams whose messages are only needed for a while.
+// Streams created before they had a max age are updated to it
+pub async fn ensure_expiring_stream(nc: &Connection, stream: (&str, &str), max_age: Duration) -> io::Result<()> {
+    create_stream(nc, stream, Some(max_age)).await
+}
+
+async fn create_stream(nc: &Connection, (name, subject): (&str, &str), max_age: Option<Duration>) -> io::Result<()> {
+    let exists = request(nc, &format!("$JS.API.STREAM.INFO.{}", name), b"").await.is_ok();
+    let action = match (exists, max_age) {
+        (false, _) => "CREATE",
+        (true, Some(_)) => "UPDATE",
+        (true, None) => return Ok(()),
+    };
+
+    let config = StreamConfig{name: name, subjects: vec![subject], storage: "file", max_age: max_age.map(|max_age| max_age.as_nanos() as u64)};
+    request(nc, &format!("$JS.API.STREAM.{}.{}", action, name), &serde_json::to_vec(&config)?).await?;
+    Ok(())
+}
+
+// Publishes to a stream subject and waits for the stream to acknowledge it was stored.
+pub async fn publish(nc: &Connection, subject: &str, payload: &[u8]) -> io::Result<()> {
+    request(nc, subject, payload).await?;
+    Ok(())
+}
+
+// Like publish, returns the sequence the stream stored the message under
+pub async fn store(nc: &Connection, subject: &str, payload: &[u8]) -> io::Result<u64> {
+    let ack: PubAck = serde_json::from_slice(&request(nc, subject, payload).await?)?;
+    Ok(ack.seq)
+}
+
+// A stored message's payload by its stream sequence
+pub async fn get(nc: &Connection, stream: &str, sequence: u64) -> io::Result<Vec<u8>> {
+    let (_, data) = get_message(nc, stream, sequence).await?;
+    Ok(data)
+}
+
+// Like get, along with the subject the message was published to
+pub async fn get_message(nc: &Connection, stream: &str, sequence: u64) -> io::Result<(String, Vec<u8>)> {
+    let query = serde_json::json!({"seq": sequence});
+    let response = request(nc, &format!("$JS.API.STREAM.MSG.GET.{}", stream), &serde_json::to_vec(&query)?).await?;
+    let response: GetMessageResponse = serde_json::from_slice(&response)?;
+    let data = base64::decode(&response.message.data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok((response.message.subject, data))
+}
@@ -0,0 +1,110 @@
+use {
+    std::io,
+    std::time::Duration,
+    serde::Serialize,
+    crate::nats::asynk::{Connection, Subscription},
+    super::api,
+};
+
+pub const MAX_DELIVER: i64 = 5;
+const ACK_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Debug)]
+struct ConsumerConfig<'a> {
+    durable_name: &'a str,
+    filter_subject: &'a str,
+    deliver_policy: &'a str,
+    ack_policy: &'a str,
+    ack_wait: u128,
+    max_deliver: i64,
+    replay_policy: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct CreateConsumerRequest<'a> {
+    stream_name: &'a str,
+    config: ConsumerConfig<'a>,
+}
+
+#[derive(Serialize, Debug)]
+struct PullRequest {
+    batch: usize,
+}
+
+// Queue groups end up in consumer names and subjects. Dots are rejected too, the durable name replaces
+// the subject's dots with underscores and "a.b" would share a durable with "a_b"
+pub fn valid_queue_group(queue_group: &str) -> io::Result<()> {
+    if queue_group.is_empty() || !queue_group.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid queue group '{}'", queue_group)));
+    }
+    Ok(())
+}
+
+// Workers in the same queue group share one durable per subject, so each message goes to only one of them.
+// Durable names can't contain dots
+pub fn durable_name(queue_group: &str, filter_subject: &str) -> String {
+    format!("{}_{}", queue_group, filter_subject).replace('.', "_")
+}
+
+// A durable pull consumer, messages are delivered to a private inbox as they're pulled.
+pub struct PullConsumer {
+    stream: String,
+    durable: String,
+    inbox: String,
+}
+
+impl PullConsumer {
+    pub async fn create(nc: &Connection, stream: &str, filter_subject: &str, queue_group: &str) -> io::Result<PullConsumer> {
+        let durable = durable_name(queue_group, filter_subject);
+        let consumer = PullConsumer{
+            stream: stream.to_owned(),
+            durable: durable,
+            inbox: nc.new_inbox(),
+        };
+
+        // An existing durable carries on where it stopped, a new one starts with the messages published from now on
+        // instead of replaying everything the stream still holds
+        if api::request(nc, &format!("$JS.API.CONSUMER.INFO.{}.{}", stream, consumer.durable), b"").await.is_ok() {
+            return Ok(consumer);
+        }
+
+        let request = CreateConsumerRequest{
+            stream_name: stream,
+            config: ConsumerConfig{
+                durable_name: &consumer.durable,
+                filter_subject: filter_subject,
+                deliver_policy: "new",
+                ack_policy: "explicit",
+                ack_wait: ACK_WAIT.as_nanos(),
+                max_deliver: MAX_DELIVER,
+                replay_policy: "instant",
+            },
+        };
+        api::request(
+            nc, 
+            &format!("$JS.API.CONSUMER.DURABLE.CREATE.{}.{}", stream, consumer.durable), 
+            &serde_json::to_vec(&request)?,
+        ).await?;
+
+        Ok(consumer)
+    }
+
+    pub async fn subscribe(&self, nc: &Connection) -> io::Result<Subscription> {
+        nc.subscribe(&self.inbox).await
+    }
+
+    // Advisories for messages the server gave up on after MAX_DELIVER unsettled deliveries, e.g. when every worker timed out on them.
+    // Only one member of the queue group gets each advisory
+    pub async fn subscribe_exhausted(&self, nc: &Connection, queue_group: &str) -> io::Result<Subscription> {
+        nc.queue_subscribe(&format!("$JS.EVENT.ADVISORY.CONSUMER.MAX_DELIVERIES.{}.{}", self.stream, self.durable), queue_group).await
+    }
+
+    // Asks for more messages, they arrive on the subscription whenever the stream has them.
+    pub async fn pull(&self, nc: &Connection, batch: usize) -> io::Result<()> {
+        nc.publish_request(
+            &format!("$JS.API.CONSUMER.MSG.NEXT.{}.{}", self.stream, self.durable),
+            &self.inbox,
+            &serde_json::to_vec(&PullRequest{batch: batch})?,
+        ).await
+    }
+}
@@ -0,0 +1,17 @@
+// JetStream over its JSON API ($JS.API.*), the nats client we use only speaks core NATS.
+mod api;
+mod consumer;
+mod ack;
+
+#[cfg(test)]
+mod tests;
+
+pub use api::{ensure_stream, ensure_expiring_stream, publish, store, get};
+pub use consumer::{PullConsumer, MAX_DELIVER, valid_queue_group};
+pub use ack::{settle, in_progress, dead_letter_exhausted, DeadLetter};
+
+pub const DATASOURCE_STREAM: (&str, &str) = ("DATASOURCE", "datasource.*");
+pub const NORMALIZER_STREAM: (&str, &str) = ("NORMALIZER", "normalizer.>");
+pub const DEAD_LETTER_STREAM: (&str, &str) = ("DLQ", "dlq.>");
+// Parts of payloads too large for a single message, see bus::EncodedBus
+pub const CHUNK_STREAM: (&str, &str) = ("CHUNKS", "chunks.>");
@@ -0,0 +1,137 @@
+use {
+    uuid::Uuid,
+    crate::nats::asynk as nats_client,
+    super::{PullConsumer, ensure_stream, publish, settle},
+    crate::bus::Outcome,
+    super::ack::AckInfo,
+    super::consumer::{durable_name, valid_queue_group},
+    super::ack::{DeadLetter, MaxDeliveriesAdvisory},
+};
+
+#[test]
+fn test_parse_ack_info() {
+    let cases = vec![
+        (
+            "$JS.ACK.DATASOURCE.extractor_datasource_bachtrack_listing.3.1042.17.1604511234000000000.0", 
+            Some(AckInfo{stream: "DATASOURCE".to_owned(), consumer: "extractor_datasource_bachtrack_listing".to_owned(), deliveries: 3, stream_sequence: 1042}),
+        ),
+        (
+            "$JS.ACK.hub.ACCHASH.DATASOURCE.extractor_datasource_bachtrack_listing.1.7.7.1604511234000000000.0.rnd", 
+            Some(AckInfo{stream: "DATASOURCE".to_owned(), consumer: "extractor_datasource_bachtrack_listing".to_owned(), deliveries: 1, stream_sequence: 7}),
+        ),
+        ("_INBOX.abcdef", None),
+        ("$JS.ACK.DATASOURCE.consumer.x.1.1.1.0", None),
+    ];
+
+    for (reply, expected) in cases {
+        assert_eq!(AckInfo::parse(reply), expected, "parsing '{}'", reply);
+    }
+}
+
+#[test]
+fn test_durable_name() {
+    assert_eq!(durable_name("extractor", "datasource.bachtrack_listing"), "extractor_datasource_bachtrack_listing");
+    assert_eq!(durable_name("extractor-eu", "datasource.bachtrack_listing"), "extractor-eu_datasource_bachtrack_listing");
+}
+
+#[test]
+fn test_valid_queue_group() {
+    assert!(valid_queue_group("extractor").is_ok());
+    assert!(valid_queue_group("extractor-eu_2").is_ok());
+    for invalid in &["", "extractor.eu", "extractor.*", "extractor.>", "extractor eu", "extractor\t"] {
+        assert!(valid_queue_group(invalid).is_err(), "accepted '{}'", invalid);
+    }
+}
+
+#[test]
+fn test_parse_max_deliveries_advisory() {
+    let advisory = r#"{
+        "type": "io.nats.jetstream.advisory.v1.max_deliver",
+        "id": "bMtR7ELSzrQ2EfkbJyU7c9",
+        "timestamp": "2020-11-04T17:33:54.123456Z",
+        "stream": "DATASOURCE",
+        "consumer": "extractor_datasource_bachtrack_listing",
+        "stream_seq": 1042,
+        "deliveries": 5
+    }"#;
+
+    let advisory: MaxDeliveriesAdvisory = serde_json::from_str(advisory).unwrap();
+    assert_eq!(advisory, MaxDeliveriesAdvisory{
+        stream: "DATASOURCE".to_owned(),
+        consumer: "extractor_datasource_bachtrack_listing".to_owned(),
+        stream_seq: 1042,
+        deliveries: 5,
+    });
+}
+
+// The following tests need a local `nats-server -js`, run them with `cargo test -- --ignored`
+
+fn unique_subject() -> String {
+    format!("datasource.test_{}", Uuid::new_v4().to_simple())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_pull_and_ack() {
+    let nc = nats_client::connect("127.0.0.1:4222").await.unwrap();
+    let subject = unique_subject();
+    ensure_stream(&nc, super::DATASOURCE_STREAM).await.unwrap();
+
+    // New durables start at new messages
+    let consumer = PullConsumer::create(&nc, super::DATASOURCE_STREAM.0, &subject, "test").await.unwrap();
+    let subscription = consumer.subscribe(&nc).await.unwrap();
+    publish(&nc, &subject, b"https://bachtrack.com/find-concerts/").await.unwrap();
+    consumer.pull(&nc, 1).await.unwrap();
+
+    let message = subscription.next().await.unwrap();
+    assert_eq!(message.subject, subject);
+    assert_eq!(message.data, b"https://bachtrack.com/find-concerts/".to_vec());
+    assert_eq!(AckInfo::parse(message.reply.as_ref().unwrap()).unwrap().deliveries, 1);
+
+    settle(&nc, &message, &Outcome::Done).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_failures_go_to_dead_letter_queue() {
+    let nc = nats_client::connect("127.0.0.1:4222").await.unwrap();
+    let subject = unique_subject();
+    ensure_stream(&nc, super::DATASOURCE_STREAM).await.unwrap();
+    ensure_stream(&nc, super::DEAD_LETTER_STREAM).await.unwrap();
+    let dead_letters = nc.subscribe(&format!("dlq.{}", subject)).await.unwrap();
+
+    let consumer = PullConsumer::create(&nc, super::DATASOURCE_STREAM.0, &subject, "test").await.unwrap();
+    let subscription = consumer.subscribe(&nc).await.unwrap();
+    publish(&nc, &subject, b"not a configuration").await.unwrap();
+    consumer.pull(&nc, 1).await.unwrap();
+
+    let message = subscription.next().await.unwrap();
+    settle(&nc, &message, &Outcome::Fail("invalid configuration".to_owned())).await.unwrap();
+
+    let dead_letter: DeadLetter = serde_json::from_slice(&dead_letters.next().await.unwrap().data).unwrap();
+    assert_eq!(dead_letter, DeadLetter::new(&subject, "invalid configuration", 1, b"not a configuration"));
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_queue_group_shares_messages() {
+    let nc = nats_client::connect("127.0.0.1:4222").await.unwrap();
+    let subject = unique_subject();
+    ensure_stream(&nc, super::DATASOURCE_STREAM).await.unwrap();
+
+    let first_worker = PullConsumer::create(&nc, super::DATASOURCE_STREAM.0, &subject, "test").await.unwrap();
+    let second_worker = PullConsumer::create(&nc, super::DATASOURCE_STREAM.0, &subject, "test").await.unwrap();
+    let first_subscription = first_worker.subscribe(&nc).await.unwrap();
+    let second_subscription = second_worker.subscribe(&nc).await.unwrap();
+    publish(&nc, &subject, b"first").await.unwrap();
+    publish(&nc, &subject, b"second").await.unwrap();
+    first_worker.pull(&nc, 1).await.unwrap();
+    second_worker.pull(&nc, 1).await.unwrap();
+
+    let first_message = first_subscription.next().await.unwrap();
+    let second_message = second_subscription.next().await.unwrap();
+    assert_ne!(first_message.data, second_message.data);
+
+    settle(&nc, &first_message, &Outcome::Done).await.unwrap();
+    settle(&nc, &second_message, &Outcome::Done).await.unwrap();
+}
@@ -1,28 +1,116 @@
 mod datasources;
 mod model;
+mod metrics;
+mod telemetry;
+mod jetstream;
+mod bus;
+mod worker;
+mod archive;
+mod reprocess;
+mod submission;
+mod plugins;
+mod routing;
 extern crate nats;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate prometheus;
 
 use {
-    crate::nats::asynk as nats_client,
     std::sync::Arc,
-    crate::model::Datasource,
-    crate::model::http_client::WebpageHttpClient,
+    std::collections::HashMap,
+    std::path::PathBuf,
+    tracing::{info, warn, error},
+    crate::model::http_client::{WebpageHttpClient, SessionHttpClient, MeasuredHttpClient, ArchivingHttpClient, CircuitBreakingHttpClient},
+    crate::model::circuit_breaker::{CircuitBreakers, BreakerConfiguration},
+    crate::model::session::{self, Session},
+    crate::datasources::bachtrack::{discovery, listing, review, festival},
+    crate::datasources::{email, spreadsheet},
+    crate::archive::{PageArchive, DEFAULT_ARCHIVE_DIR},
+    crate::reprocess::ReprocessArgs,
+    crate::bus::{MessageBus, NatsBus, EncodedBus, EncodingConfiguration},
+    crate::routing::RoutingTable,
+    common::Shutdown,
+    crate::worker::MAX_CONCURRENT_MESSAGES,
 };
 
-const MAX_CONCURRENT_MESSAGES: usize = 100;
+const NATS_URL: &str = "127.0.0.1:4222";
+// Extractors started with the same QUEUE_GROUP share the messages of every datasource
+const DEFAULT_QUEUE_GROUP: &str = "extractor";
 
 extern crate tokio;
 
 #[tokio::main]
 async fn main() {
-    tokio::join!(
-        setup_datasource(datasources::bachtrack::discovery::DS::new(
-            WebpageHttpClient::new()
-        )),
-        setup_datasource(datasources::bachtrack::listing::DS::new(
-            WebpageHttpClient::new()
-        )),
-    );
+    let _telemetry = telemetry::init();
+    metrics::MAX_CONCURRENT_MESSAGES.set(MAX_CONCURRENT_MESSAGES as i64);
+
+    let shutdown = Shutdown::listen();
+
+    let bus = match NatsBus::connect(NATS_URL, MAX_CONCURRENT_MESSAGES).await {
+        Ok(bus) => Arc::new(EncodedBus::new(bus, bus_encoding())),
+        Err(e) => {
+            error!(error = %e, "Error connecting to NATS");
+            return;
+        }
+    };
+    let queue_group = std::env::var("QUEUE_GROUP").unwrap_or(DEFAULT_QUEUE_GROUP.to_owned());
+    if let Err(e) = jetstream::valid_queue_group(&queue_group) {
+        error!(error = %e, "Error reading QUEUE_GROUP");
+        return;
+    }
+    let archive = PageArchive::new(std::env::var("ARCHIVE_DIR").unwrap_or(DEFAULT_ARCHIVE_DIR.to_owned()));
+    let routes = match routing_table() {
+        Ok(routes) => Arc::new(routes),
+        Err(e) => {
+            error!(error = %e, "Error loading the routing table");
+            return;
+        }
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("reprocess") {
+        reprocess_archive(&args[1..], &archive, bus.as_ref(), &routes).await;
+    } else {
+        let sessions = match datasource_sessions() {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                error!(error = %e, "Error setting up the datasource sessions");
+                return;
+            }
+        };
+        let mailbox_root = PathBuf::from(std::env::var("MAILBOX_ROOT").unwrap_or(email::DEFAULT_MAILBOX_ROOT.to_owned()));
+        let import_dir = PathBuf::from(std::env::var("IMPORT_DIR").unwrap_or(spreadsheet::DEFAULT_IMPORT_DIR.to_owned()));
+        let plugin_dir = std::env::var("PLUGIN_DIR").unwrap_or(plugins::DEFAULT_PLUGIN_DIR.to_owned());
+        // Shared, datasources fetching from the same site pause together
+        let breakers = Arc::new(CircuitBreakers::new(BreakerConfiguration::default()));
+        tokio::join!(
+            serve_metrics(Arc::clone(&breakers), shutdown.clone()),
+            serve_submissions(Arc::clone(&bus), Arc::clone(&routes), shutdown.clone()),
+            worker::run(discovery::DS::new(ArchivingHttpClient::new(
+                MeasuredHttpClient::new(CircuitBreakingHttpClient::new(SessionHttpClient::new(&sessions[discovery::DS_NAME]), &breakers, discovery::DS_NAME), discovery::DS_NAME), &archive, discovery::DS_NAME
+            )), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            worker::run(listing::DS::new(ArchivingHttpClient::new(
+                MeasuredHttpClient::new(CircuitBreakingHttpClient::new(SessionHttpClient::new(&sessions[listing::DS_NAME]), &breakers, listing::DS_NAME), listing::DS_NAME), &archive, listing::DS_NAME
+            )), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            worker::run(review::DS::new(ArchivingHttpClient::new(
+                MeasuredHttpClient::new(CircuitBreakingHttpClient::new(SessionHttpClient::new(&sessions[review::DS_NAME]), &breakers, review::DS_NAME), review::DS_NAME), &archive, review::DS_NAME
+            )), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            worker::run(festival::DS::new(ArchivingHttpClient::new(
+                MeasuredHttpClient::new(CircuitBreakingHttpClient::new(SessionHttpClient::new(&sessions[festival::DS_NAME]), &breakers, festival::DS_NAME), festival::DS_NAME), &archive, festival::DS_NAME
+            )), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            worker::run(email::DS::new(&mailbox_root), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            worker::run(spreadsheet::DS::new(&import_dir), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+            plugins::run(&plugin_dir, ArchivingHttpClient::new(
+                MeasuredHttpClient::new(CircuitBreakingHttpClient::new(SessionHttpClient::new(&sessions[plugins::SESSION_NAME]), &breakers, plugins::SESSION_NAME), plugins::SESSION_NAME), &archive, plugins::SESSION_NAME
+            ), Arc::clone(&bus), &routes, &queue_group, shutdown.clone()),
+        );
+    }
+
+    if let Err(e) = bus.close().await {
+        error!(error = %e, "Error closing the connection");
+    }
+    info!("shut down");
 
     // TODO:: switch to spawn task to use multi-thread (Tokio join does not use multi-threading)
     // datasources
@@ -35,52 +123,116 @@ async fn main() {
     //     });
 }
 
-async fn setup_datasource<T: Datasource + Copy>(datasource: T){
-    use futures::stream::StreamExt;
-
-    let datasource_name = datasource.get_name();
-    let nc = nats_client::connect("127.0.0.1:4222").await.unwrap();
-
-    println!("listening to queue {}", datasource_name);
-
-    let subscriber = nc.subscribe(&datasource_name).await.unwrap();
-    let arc_nc = Arc::new(nc);
-    
-    subscriber.for_each_concurrent(MAX_CONCURRENT_MESSAGES, move |message|{
-        println!("{}: Starting extraction", datasource_name);
-        let publisher = Arc::clone(&arc_nc);
-        async move{
-            let datasource_name = datasource.get_name();
-
-            let extracted_items = match datasource.extract(&message.data).await{
-                Ok(k) => k,
-                Err(e) => {
-                    println!("{} Error occured in the extract logic. err: {}", &datasource_name, e);
-                    return;
-                }
-            };
-            
-            for item in extracted_items {
-                println!("{:?}", item);
-                let message = match serde_json::to_string(&item){
-                    Ok(msg) => msg,
-                    Err(e) => {
-                        println!("{}: Error serializing the extracted item from into a message. err: {}, item:{:?}", &datasource_name, e, item);
-                        continue;
-                    }
-                };
-                
-                let destination_queue = item.get_queue_name();
-                match publisher.publish(&destination_queue, &message).await {
-                    Ok(_) => {},
-                    Err(e) => {
-                        println!("{}  Error publishing a message to the '{}' queue. err: {}, message: {}", &datasource_name, destination_queue, e, message);
-                        continue;
-                    }
-                };
-            }
-            println!("{}: Finished extraction", datasource_name);
+async fn serve_metrics(breakers: Arc<CircuitBreakers>, shutdown: Shutdown){
+    let metrics_addr = std::env::var("METRICS_ADDR").unwrap_or(metrics::DEFAULT_METRICS_ADDR.to_owned());
+    let addr = match metrics_addr.parse(){
+        Ok(addr) => addr,
+        Err(e) => {
+            error!(addr = %metrics_addr, error = %e, "Invalid metrics address");
+            return;
+        }
+    };
+
+    if let Err(e) = common::metrics::serve(addr, vec![breakers], shutdown).await {
+        error!(error = %e, "Error serving metrics");
+    }
+}
+
+// Without SUBMISSION_TOKEN nobody could be told apart from anyone else, so nothing is accepted
+async fn serve_submissions<B: MessageBus + 'static>(bus: Arc<B>, routes: Arc<RoutingTable>, shutdown: Shutdown){
+    let token = match std::env::var("SUBMISSION_TOKEN") {
+        Ok(token) if !token.trim().is_empty() => token,
+        _ => {
+            warn!("SUBMISSION_TOKEN isn't set, event submissions are disabled");
+            return;
+        }
+    };
+    let submission_addr = std::env::var("SUBMISSION_ADDR").unwrap_or(submission::DEFAULT_SUBMISSION_ADDR.to_owned());
+    let addr = match submission_addr.parse(){
+        Ok(addr) => addr,
+        Err(e) => {
+            error!(addr = %submission_addr, error = %e, "Invalid submission address");
+            return;
+        }
+    };
+
+    if let Err(e) = submission::serve(addr, token, bus, routes, shutdown).await {
+        error!(error = %e, "Error serving event submissions");
+    }
+}
+
+// Every datasource keeps its own cookies, SESSION_CONFIG points to their configurations by datasource name
+fn datasource_sessions() -> Result<HashMap<&'static str, Session>, Box<dyn std::error::Error>>{
+    let mut configurations = match std::env::var("SESSION_CONFIG") {
+        Ok(path) => session::load_configurations(&path)?,
+        Err(_) => HashMap::new(),
+    };
+
+    let mut sessions = HashMap::new();
+    for ds_name in &[discovery::DS_NAME, listing::DS_NAME, review::DS_NAME, festival::DS_NAME, plugins::SESSION_NAME] {
+        let configuration = configurations.remove(*ds_name).unwrap_or_default();
+        // Plugins are only allowed the hosts their manifests name, redirects can't take them elsewhere
+        let session = if *ds_name == plugins::SESSION_NAME { Session::same_host_only(configuration)? } else { Session::new(configuration)? };
+        sessions.insert(*ds_name, session);
+    }
+    Ok(sessions)
+}
+
+// BUS_ENCODED_SUBJECTS lists the subjects, besides the datasources, whose consumers decode compressed and chunked messages
+fn bus_encoding() -> EncodingConfiguration {
+    let mut subjects = vec![bus::DEFAULT_ENCODED_SUBJECTS.to_owned()];
+    if let Ok(extra) = std::env::var("BUS_ENCODED_SUBJECTS") {
+        subjects.extend(extra.split(',').map(|subject| subject.trim().to_owned()).filter(|subject| !subject.is_empty()));
+    }
+    let max_payload = std::env::var("BUS_MAX_PAYLOAD").ok()
+        .and_then(|max_payload| max_payload.parse().ok())
+        .unwrap_or(bus::DEFAULT_MAX_PAYLOAD);
+    let max_decoded_size = std::env::var("BUS_MAX_DECODED_SIZE").ok()
+        .and_then(|max_decoded_size| max_decoded_size.parse().ok())
+        .unwrap_or(bus::DEFAULT_MAX_DECODED_SIZE);
+    EncodingConfiguration{subjects: subjects, max_payload: max_payload, max_decoded_size: max_decoded_size}
+}
+
+// Without ROUTING_CONFIG every item goes to its default queue
+fn routing_table() -> Result<RoutingTable, Box<dyn std::error::Error>>{
+    match std::env::var("ROUTING_CONFIG") {
+        Ok(path) => RoutingTable::load(&path),
+        Err(_) => Ok(RoutingTable::default()),
+    }
+}
+
+// Republishes what the archived pages yield with the current parsers, the network isn't touched
+async fn reprocess_archive<B: MessageBus>(args: &[String], archive: &PageArchive, bus: &B, routes: &RoutingTable){
+    let args = match ReprocessArgs::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            error!(error = %e, "Invalid reprocess arguments");
+            return;
         }
-    }).await;
+    };
 
+    if args.includes(discovery::DS_NAME) {
+        let datasource = discovery::DS::new(WebpageHttpClient::new());
+        if let Err(e) = reprocess::run(&datasource, archive, bus, routes, args.from, args.to).await {
+            error!(datasource = discovery::DS_NAME, error = %e, "Error reprocessing the archive");
+        }
+    }
+    if args.includes(listing::DS_NAME) {
+        let datasource = listing::DS::new(WebpageHttpClient::new());
+        if let Err(e) = reprocess::run(&datasource, archive, bus, routes, args.from, args.to).await {
+            error!(datasource = listing::DS_NAME, error = %e, "Error reprocessing the archive");
+        }
+    }
+    if args.includes(review::DS_NAME) {
+        let datasource = review::DS::new(WebpageHttpClient::new());
+        if let Err(e) = reprocess::run(&datasource, archive, bus, routes, args.from, args.to).await {
+            error!(datasource = review::DS_NAME, error = %e, "Error reprocessing the archive");
+        }
+    }
+    if args.includes(festival::DS_NAME) {
+        let datasource = festival::DS::new(WebpageHttpClient::new());
+        if let Err(e) = reprocess::run(&datasource, archive, bus, routes, args.from, args.to).await {
+            error!(datasource = festival::DS_NAME, error = %e, "Error reprocessing the archive");
+        }
+    }
 }
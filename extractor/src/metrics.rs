@@ -0,0 +1,168 @@
+use {
+    std::time::Instant,
+    prometheus::{HistogramVec, IntCounterVec, IntGauge, IntGaugeVec},
+    common::metrics::StatusPage,
+    crate::model::circuit_breaker::CircuitBreakers,
+};
+
+pub const DEFAULT_METRICS_ADDR: &str = "0.0.0.0:9898";
+const BREAKERS_PATH: &str = "/circuit-breakers";
+
+lazy_static! {
+    pub static ref MESSAGES_CONSUMED: IntCounterVec = register_int_counter_vec!(
+        "extractor_messages_consumed_total",
+        "Messages consumed from the bus, per subject",
+        &["subject"]
+    ).unwrap();
+
+    pub static ref EXTRACTION_DURATION: HistogramVec = register_histogram_vec!(
+        "extractor_extraction_duration_seconds",
+        "Time spent in a datasource's extract, per datasource",
+        &["datasource"]
+    ).unwrap();
+
+    pub static ref HTTP_FETCH_DURATION: HistogramVec = register_histogram_vec!(
+        "extractor_http_fetch_duration_seconds",
+        "Time spent fetching pages over http, per datasource",
+        &["datasource"]
+    ).unwrap();
+
+    pub static ref PAGE_ENCODINGS: IntCounterVec = register_int_counter_vec!(
+        "extractor_page_encodings_total",
+        "Fetched pages, per datasource, the encoding they were decoded with and where it was detected from",
+        &["datasource", "encoding", "detected_from"]
+    ).unwrap();
+
+    pub static ref ITEMS_PUBLISHED: IntCounterVec = register_int_counter_vec!(
+        "extractor_items_published_total",
+        "Extracted items published to the bus, per item type",
+        &["item_type"]
+    ).unwrap();
+
+    pub static ref ITEMS_DROPPED: IntCounterVec = register_int_counter_vec!(
+        "extractor_items_dropped_total",
+        "Extracted items a route sent nowhere, per item type",
+        &["item_type"]
+    ).unwrap();
+
+    pub static ref BUS_PAYLOAD_BYTES: HistogramVec = register_histogram_vec!(
+        "extractor_bus_payload_bytes",
+        "Sizes of bus messages, per direction (published, consumed) and stage (payload as extracted, wire as sent)",
+        &["direction", "stage"],
+        prometheus::exponential_buckets(256.0, 4.0, 9).unwrap()
+    ).unwrap();
+
+    pub static ref BUS_ENCODED_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "extractor_bus_encoded_messages_total",
+        "Bus messages sent or received as frames, per direction and content encoding",
+        &["direction", "content_encoding"]
+    ).unwrap();
+
+    pub static ref BUS_CHUNKED_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "extractor_bus_chunked_messages_total",
+        "Bus messages too large for one message, sent or received in chunks, per direction",
+        &["direction"]
+    ).unwrap();
+
+    pub static ref SYNC_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "extractor_sync_requests_total",
+        "Synchronous extraction requests answered, per datasource and outcome",
+        &["datasource", "outcome"]
+    ).unwrap();
+
+    pub static ref SUBMISSIONS: IntCounterVec = register_int_counter_vec!(
+        "extractor_submissions_total",
+        "Events submitted by hand, per outcome (accepted, rejected, unauthorized, failed)",
+        &["outcome"]
+    ).unwrap();
+
+    pub static ref BREAKER_STATE: IntGaugeVec = register_int_gauge_vec!(
+        "extractor_circuit_breaker_state",
+        "State of the circuit breaker of each host: 0 closed, 1 half-open, 2 open",
+        &["host"]
+    ).unwrap();
+
+    pub static ref BREAKER_TRANSITIONS: IntCounterVec = register_int_counter_vec!(
+        "extractor_circuit_breaker_transitions_total",
+        "Circuit breaker state changes, per host and the state changed to",
+        &["host", "state"]
+    ).unwrap();
+
+    pub static ref BREAKER_REJECTED_CALLS: IntCounterVec = register_int_counter_vec!(
+        "extractor_circuit_breaker_rejected_calls_total",
+        "Fetches turned away by an open circuit breaker, per host",
+        &["host"]
+    ).unwrap();
+
+    pub static ref PAUSED_DATASOURCES: IntGaugeVec = register_int_gauge_vec!(
+        "extractor_paused_datasources",
+        "1 while a datasource's consumption is paused by a circuit breaker",
+        &["datasource"]
+    ).unwrap();
+
+    pub static ref ERRORS: IntCounterVec = register_int_counter_vec!(
+        "extractor_errors_total",
+        "Errors, per kind",
+        &["kind"]
+    ).unwrap();
+
+    pub static ref IN_FLIGHT_MESSAGES: IntGaugeVec = register_int_gauge_vec!(
+        "extractor_in_flight_messages",
+        "Messages currently being processed, per datasource",
+        &["datasource"]
+    ).unwrap();
+
+    pub static ref MAX_CONCURRENT_MESSAGES: IntGauge = register_int_gauge!(
+        "extractor_max_concurrent_messages",
+        "The maximum number of messages processed concurrently per datasource"
+    ).unwrap();
+}
+
+// Counts a message as in flight until dropped, so early returns are accounted for.
+pub struct InFlight {
+    gauge: IntGauge,
+}
+
+impl InFlight {
+    pub fn start(datasource_name: &str) -> InFlight {
+        let gauge = IN_FLIGHT_MESSAGES.with_label_values(&[datasource_name]);
+        gauge.inc();
+        InFlight{gauge: gauge}
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
+// Marks a datasource as paused until dropped
+pub struct Paused {
+    gauge: IntGauge,
+}
+
+impl Paused {
+    pub fn start(datasource_name: &str) -> Paused {
+        let gauge = PAUSED_DATASOURCES.with_label_values(&[datasource_name]);
+        gauge.set(1);
+        Paused{gauge: gauge}
+    }
+}
+
+impl Drop for Paused {
+    fn drop(&mut self) {
+        self.gauge.set(0);
+    }
+}
+
+// The state of every host's circuit breaker
+impl StatusPage for CircuitBreakers {
+    fn path(&self) -> &str {
+        BREAKERS_PATH
+    }
+
+    fn status(&self) -> serde_json::Value {
+        serde_json::to_value(CircuitBreakers::status(self, Instant::now())).unwrap_or_default()
+    }
+}
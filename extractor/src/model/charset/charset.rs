@@ -0,0 +1,96 @@
+use {
+    std::fmt,
+    encoding_rs::{Encoding, UTF_8, WINDOWS_1252},
+    regex::bytes::Regex,
+};
+
+// Browsers only look for a meta charset in the first 1024 bytes
+const META_PRESCAN_LENGTH: usize = 1024;
+
+lazy_static! {
+    // Covers both <meta charset="..."> and <meta http-equiv="Content-Type" content="...; charset=...">
+    static ref RE_META_CHARSET: Regex = Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap();
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum EncodingSource {
+    Header,
+    MetaTag,
+    Sniffed,
+}
+
+impl fmt::Display for EncodingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingSource::Header => write!(f, "header"),
+            EncodingSource::MetaTag => write!(f, "meta_tag"),
+            EncodingSource::Sniffed => write!(f, "sniffed"),
+        }
+    }
+}
+
+// A page as it was served, with the encoding its text is decoded from
+#[derive(Debug, Clone)]
+pub struct Webpage {
+    pub bytes: Vec<u8>,
+    pub encoding: &'static Encoding,
+    pub encoding_source: EncodingSource,
+}
+
+impl Webpage {
+    // The Content-Type header wins over the page's meta tag, sniffing is the last resort
+    pub fn new(bytes: Vec<u8>, content_type: Option<&str>) -> Webpage {
+        let (encoding, encoding_source) = content_type.and_then(header_encoding)
+            .map(|encoding| (encoding, EncodingSource::Header))
+            .or_else(|| meta_encoding(&bytes).map(|encoding| (encoding, EncodingSource::MetaTag)))
+            .unwrap_or_else(|| (sniff(&bytes), EncodingSource::Sniffed));
+
+        Webpage{bytes: bytes, encoding: encoding, encoding_source: encoding_source}
+    }
+
+    pub fn text(&self) -> String {
+        self.encoding.decode_with_bom_removal(&self.bytes).0.into_owned()
+    }
+
+    pub fn encoding_name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+// Text without any declared encoding, e.g. a bus payload
+pub fn decode_text(bytes: &[u8]) -> String {
+    sniff(bytes).decode_with_bom_removal(bytes).0.into_owned()
+}
+
+fn header_encoding(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';')
+        .skip(1)
+        .filter_map(|parameter| {
+            let mut parts = parameter.splitn(2, '=');
+            match (parts.next()?.trim().to_lowercase().as_str(), parts.next()) {
+                ("charset", Some(label)) => Some(label.trim().trim_matches('"').to_owned()),
+                _ => None,
+            }
+        })
+        .next()
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+}
+
+fn meta_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_PRESCAN_LENGTH)];
+
+    let label = RE_META_CHARSET.captures(head)?.get(1)?.as_bytes();
+    // A page that could be read far enough to find its meta tag isn't utf-16
+    Encoding::for_label(label).map(Encoding::output_encoding)
+}
+
+// A byte order mark, then utf-8 if the bytes are valid utf-8, and the usual legacy encoding otherwise
+fn sniff(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
@@ -0,0 +1,6 @@
+mod charset;
+
+#[cfg(test)]
+mod tests;
+
+pub use charset::{Webpage, decode_text};
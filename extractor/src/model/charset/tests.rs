@@ -0,0 +1,70 @@
+use {
+    encoding_rs::{UTF_8, WINDOWS_1252, ISO_8859_2},
+    super::{Webpage, decode_text},
+    super::charset::EncodingSource,
+};
+
+// "Mozart: Così fan tutte, Großes Festspielhaus" as a legacy western european site serves it
+const LATIN_1_PROGRAMME: &[u8] = b"Mozart: Cos\xec fan tutte, Gro\xdfes Festspielhaus";
+
+fn page_with_meta(meta: &str) -> Vec<u8> {
+    let mut page = format!("<html><head>{}<title>Programme</title></head><body>", meta).into_bytes();
+    page.extend_from_slice(LATIN_1_PROGRAMME);
+    page.extend_from_slice(b"</body></html>");
+    page
+}
+
+#[test]
+fn test_header_encoding() {
+    let webpage = Webpage::new(LATIN_1_PROGRAMME.to_vec(), Some("text/html; charset=ISO-8859-1"));
+
+    assert_eq!((webpage.encoding, webpage.encoding_source), (WINDOWS_1252, EncodingSource::Header));
+    assert_eq!(webpage.text(), "Mozart: Così fan tutte, Großes Festspielhaus");
+    assert_eq!(webpage.encoding_name(), "windows-1252");
+}
+
+#[test]
+fn test_meta_encoding() {
+    let cases = vec![
+        (page_with_meta("<meta charset=\"iso-8859-1\">"), None, WINDOWS_1252),
+        (page_with_meta("<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=windows-1252\">"), Some("text/html"), WINDOWS_1252),
+        (page_with_meta("<meta charset='latin2'>"), Some("text/html; charset=unknown"), ISO_8859_2),
+        // A page declaring utf-16 in ascii is read as utf-8
+        ("<meta charset=\"utf-16\">Così".as_bytes().to_vec(), None, UTF_8),
+    ];
+
+    for (page, content_type, expected) in cases {
+        let webpage = Webpage::new(page, content_type);
+        assert_eq!((webpage.encoding, webpage.encoding_source), (expected, EncodingSource::MetaTag));
+    }
+    assert!(Webpage::new(page_with_meta("<meta charset=\"iso-8859-1\">"), None).text().contains("Così fan tutte, Großes"));
+}
+
+#[test]
+fn test_sniffed_encoding() {
+    let cases = vec![
+        ("Così fan tutte".as_bytes().to_vec(), UTF_8, "Così fan tutte"),
+        (b"\xef\xbb\xbfCos\xc3\xac".to_vec(), UTF_8, "Così"),
+        (LATIN_1_PROGRAMME.to_vec(), WINDOWS_1252, "Mozart: Così fan tutte, Großes Festspielhaus"),
+    ];
+
+    for (page, expected_encoding, expected_text) in cases {
+        let webpage = Webpage::new(page, Some("text/html"));
+        assert_eq!((webpage.encoding, webpage.encoding_source), (expected_encoding, EncodingSource::Sniffed));
+        assert_eq!(webpage.text(), expected_text);
+    }
+}
+
+#[test]
+fn test_meta_tag_after_prescan_is_ignored() {
+    let mut page = vec![b' '; 2000];
+    page.extend_from_slice(b"<meta charset=\"iso-8859-2\">");
+
+    assert_eq!(Webpage::new(page, None).encoding_source, EncodingSource::Sniffed);
+}
+
+#[test]
+fn test_decode_text() {
+    assert_eq!(decode_text(b"https://www.konzerthaus.at/programm?saal=Gro\xdfer%20Saal"), "https://www.konzerthaus.at/programm?saal=Großer%20Saal");
+    assert_eq!(decode_text("{\"city\": \"München\"}".as_bytes()), "{\"city\": \"München\"}");
+}
@@ -0,0 +1,325 @@
+use {
+    std::sync::{Arc, Mutex},
+    std::time::{Duration, Instant},
+    std::collections::{HashMap, HashSet, VecDeque},
+    serde::Serialize,
+    tracing::{info, warn},
+    crate::metrics,
+    crate::model::errors::CircuitOpenError,
+};
+
+// Calls taking longer count as slow by default
+pub const SLOW_CALL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct BreakerConfiguration {
+    // Most recent calls the rates are computed over
+    pub window: usize,
+    // Calls needed in the window before the breaker can open
+    pub min_calls: usize,
+    pub max_error_rate: f64,
+    // Calls taking longer count as slow, a site can be down without failing outright
+    pub slow_call: Duration,
+    pub max_slow_rate: f64,
+    // How long an open breaker turns calls away before letting probes through
+    pub open_for: Duration,
+    // Successful probes needed to close again, other calls are turned away meanwhile
+    pub probes: usize,
+}
+
+impl Default for BreakerConfiguration {
+    fn default() -> BreakerConfiguration {
+        BreakerConfiguration{
+            window: 20,
+            min_calls: 10,
+            max_error_rate: 0.5,
+            slow_call: SLOW_CALL,
+            max_slow_rate: 0.8,
+            open_for: Duration::from_secs(30),
+            probes: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl BreakerState {
+    fn name(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+
+    // For the state gauge
+    fn level(&self) -> i64 {
+        match self {
+            BreakerState::Closed => 0,
+            BreakerState::HalfOpen => 1,
+            BreakerState::Open => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BreakerStatus {
+    pub host: String,
+    pub state: BreakerState,
+    pub calls: usize,
+    pub error_rate: f64,
+    pub slow_rate: f64,
+    // Until probes are let through, while open
+    pub retry_in_ms: Option<u64>,
+    pub datasources: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Call {
+    failed: bool,
+    slow: bool,
+}
+
+struct Breaker {
+    state: BreakerState,
+    calls: VecDeque<Call>,
+    open_until: Instant,
+    probes_in_flight: usize,
+    probe_successes: usize,
+    // Counts the half-open periods, probes only count in the one they were let through in
+    half_open_period: u64,
+}
+
+// The breaker of one host, shared by every datasource fetching from it
+pub struct CircuitBreaker {
+    host: String,
+    configuration: BreakerConfiguration,
+    breaker: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    fn new(host: &str, configuration: BreakerConfiguration) -> CircuitBreaker {
+        metrics::BREAKER_STATE.with_label_values(&[host]).set(BreakerState::Closed.level());
+        CircuitBreaker{
+            host: host.to_owned(),
+            configuration: configuration,
+            breaker: Mutex::new(Breaker{
+                state: BreakerState::Closed,
+                calls: VecDeque::new(),
+                open_until: Instant::now(),
+                probes_in_flight: 0,
+                probe_successes: 0,
+                half_open_period: 0,
+            }),
+        }
+    }
+
+    // Whether a call may go through, the permit records how it went
+    pub fn permit(self: &Arc<Self>, now: Instant) -> Result<CallPermit, CircuitOpenError> {
+        let mut breaker = self.breaker.lock().unwrap();
+        if breaker.state == BreakerState::Open && now >= breaker.open_until {
+            self.transition(&mut breaker, BreakerState::HalfOpen, now);
+        }
+
+        match breaker.state {
+            BreakerState::Closed => Ok(CallPermit::new(self, None, now)),
+            BreakerState::HalfOpen if breaker.probes_in_flight + breaker.probe_successes < self.configuration.probes => {
+                breaker.probes_in_flight += 1;
+                Ok(CallPermit::new(self, Some(breaker.half_open_period), now))
+            },
+            _ => {
+                metrics::BREAKER_REJECTED_CALLS.with_label_values(&[&self.host]).inc();
+                Err(CircuitOpenError)
+            },
+        }
+    }
+
+    // A probe carries the half-open period it was let through in
+    pub(super) fn record(&self, probe: Option<u64>, failed: bool, latency: Duration, now: Instant) {
+        let call = Call{failed: failed, slow: latency >= self.configuration.slow_call};
+        let mut breaker = self.breaker.lock().unwrap();
+
+        match breaker.state {
+            BreakerState::HalfOpen if probe == Some(breaker.half_open_period) => {
+                breaker.probes_in_flight -= 1;
+                if call.failed || call.slow {
+                    self.transition(&mut breaker, BreakerState::Open, now);
+                } else {
+                    breaker.probe_successes += 1;
+                    if breaker.probe_successes >= self.configuration.probes {
+                        self.transition(&mut breaker, BreakerState::Closed, now);
+                    }
+                }
+            },
+            BreakerState::Closed => {
+                breaker.calls.push_back(call);
+                if breaker.calls.len() > self.configuration.window {
+                    breaker.calls.pop_front();
+                }
+                let (error_rate, slow_rate) = rates(&breaker.calls);
+                if breaker.calls.len() >= self.configuration.min_calls
+                    && (error_rate >= self.configuration.max_error_rate || slow_rate >= self.configuration.max_slow_rate) {
+                    self.transition(&mut breaker, BreakerState::Open, now);
+                }
+            },
+            // Calls let through before the breaker opened, and probes of an earlier half-open period,
+            // tell nothing about the probes
+            _ => {},
+        }
+    }
+
+    // How long calls will be turned away for, None when they'd go through
+    pub fn paused(&self, now: Instant) -> Option<Duration> {
+        let breaker = self.breaker.lock().unwrap();
+        match breaker.state {
+            BreakerState::Open if now < breaker.open_until => Some(breaker.open_until - now),
+            // Until the probes in flight tell how the host is doing
+            BreakerState::HalfOpen if breaker.probes_in_flight + breaker.probe_successes >= self.configuration.probes => Some(self.configuration.open_for / 10),
+            _ => None,
+        }
+    }
+
+    fn status(&self, now: Instant, datasources: Vec<String>) -> BreakerStatus {
+        let breaker = self.breaker.lock().unwrap();
+        let (error_rate, slow_rate) = rates(&breaker.calls);
+        let retry_in = match breaker.state {
+            BreakerState::Open if now < breaker.open_until => Some((breaker.open_until - now).as_millis() as u64),
+            _ => None,
+        };
+        BreakerStatus{
+            host: self.host.to_owned(),
+            state: breaker.state,
+            calls: breaker.calls.len(),
+            error_rate: error_rate,
+            slow_rate: slow_rate,
+            retry_in_ms: retry_in,
+            datasources: datasources,
+        }
+    }
+
+    fn transition(&self, breaker: &mut Breaker, state: BreakerState, now: Instant) {
+        match state {
+            BreakerState::Open => {
+                let (error_rate, slow_rate) = rates(&breaker.calls);
+                warn!(host = %self.host, error_rate, slow_rate, open_for = ?self.configuration.open_for, "Circuit breaker opened, calls are turned away");
+                breaker.open_until = now + self.configuration.open_for;
+            },
+            BreakerState::HalfOpen => {
+                info!(host = %self.host, "circuit breaker half-open, probing");
+                breaker.half_open_period += 1;
+            },
+            BreakerState::Closed => {
+                info!(host = %self.host, "circuit breaker closed");
+                breaker.calls.clear();
+            },
+        }
+        breaker.state = state;
+        breaker.probes_in_flight = 0;
+        breaker.probe_successes = 0;
+        metrics::BREAKER_STATE.with_label_values(&[&self.host]).set(state.level());
+        metrics::BREAKER_TRANSITIONS.with_label_values(&[&self.host, state.name()]).inc();
+    }
+}
+
+// A call let through by a breaker, a probe dropped without finishing counts as failed
+// so the half-open breaker isn't left waiting on it forever
+pub struct CallPermit {
+    breaker: Arc<CircuitBreaker>,
+    // The half-open period a probe was let through in
+    probe: Option<u64>,
+    started: Instant,
+    finished: bool,
+}
+
+impl CallPermit {
+    fn new(breaker: &Arc<CircuitBreaker>, probe: Option<u64>, now: Instant) -> CallPermit {
+        CallPermit{breaker: Arc::clone(breaker), probe: probe, started: now, finished: false}
+    }
+
+    pub fn probe(&self) -> bool {
+        self.probe.is_some()
+    }
+
+    pub fn finish(mut self, failed: bool, now: Instant) {
+        self.finished = true;
+        self.breaker.record(self.probe, failed, now.saturating_duration_since(self.started), now);
+    }
+}
+
+impl Drop for CallPermit {
+    fn drop(&mut self) {
+        if !self.finished && self.probe.is_some() {
+            let now = Instant::now();
+            self.breaker.record(self.probe, true, now.saturating_duration_since(self.started), now);
+        }
+    }
+}
+
+fn rates(calls: &VecDeque<Call>) -> (f64, f64) {
+    if calls.is_empty() {
+        return (0.0, 0.0);
+    }
+    let failed = calls.iter().filter(|call| call.failed).count();
+    let slow = calls.iter().filter(|call| call.slow).count();
+    (failed as f64 / calls.len() as f64, slow as f64 / calls.len() as f64)
+}
+
+// A breaker per host, and the hosts each datasource fetched from so its consumption can be paused
+pub struct CircuitBreakers {
+    configuration: BreakerConfiguration,
+    breakers: Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+    hosts: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl CircuitBreakers {
+    pub fn new(configuration: BreakerConfiguration) -> CircuitBreakers {
+        CircuitBreakers{configuration: configuration, breakers: Mutex::new(HashMap::new()), hosts: Mutex::new(HashMap::new())}
+    }
+
+    pub fn for_host(&self, host: &str, datasource_name: &str) -> Arc<CircuitBreaker> {
+        self.hosts.lock().unwrap()
+            .entry(datasource_name.to_owned())
+            .or_insert_with(HashSet::new)
+            .insert(host.to_owned());
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers.entry(host.to_owned())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(host, self.configuration.clone())));
+        Arc::clone(breaker)
+    }
+
+    // The longest any of the datasource's hosts turns calls away for
+    pub fn paused(&self, datasource_name: &str, now: Instant) -> Option<Duration> {
+        let hosts = match self.hosts.lock().unwrap().get(datasource_name) {
+            Some(hosts) => hosts.clone(),
+            None => return None,
+        };
+        let breakers = self.breakers.lock().unwrap();
+        hosts.iter()
+            .filter_map(|host| breakers.get(host))
+            .filter_map(|breaker| breaker.paused(now))
+            .max()
+    }
+
+    pub fn status(&self, now: Instant) -> Vec<BreakerStatus> {
+        let hosts = self.hosts.lock().unwrap();
+        let breakers = self.breakers.lock().unwrap();
+        let mut status: Vec<BreakerStatus> = breakers.values()
+            .map(|breaker| {
+                let mut datasources: Vec<String> = hosts.iter()
+                    .filter(|(_, hosts)| hosts.contains(&breaker.host))
+                    .map(|(datasource, _)| datasource.to_owned())
+                    .collect();
+                datasources.sort();
+                breaker.status(now, datasources)
+            })
+            .collect();
+        status.sort_by(|a, b| a.host.cmp(&b.host));
+        status
+    }
+}
@@ -0,0 +1,6 @@
+mod breaker;
+
+#[cfg(test)]
+mod tests;
+
+pub use breaker::{CircuitBreakers, BreakerConfiguration, SLOW_CALL};
@@ -0,0 +1,170 @@
+use {
+    std::io,
+    std::error::Error,
+    std::time::{Duration, Instant},
+    async_trait::async_trait,
+    tokio_test,
+    crate::model::charset::Webpage,
+    crate::model::http_client::{HttpClient, CircuitBreakingHttpClient},
+    super::breaker::{CircuitBreakers, BreakerConfiguration, BreakerState},
+};
+
+const HOST: &str = "bachtrack.com";
+const LISTING: &str = "datasource.bachtrack_listing";
+const FAST: Duration = Duration::from_millis(100);
+
+fn configuration() -> BreakerConfiguration {
+    BreakerConfiguration{window: 4, min_calls: 4, probes: 2, ..BreakerConfiguration::default()}
+}
+
+fn state(breakers: &CircuitBreakers, now: Instant) -> BreakerState {
+    breakers.status(now)[0].state
+}
+
+#[test]
+fn test_opens_on_errors() {
+    let breakers = CircuitBreakers::new(configuration());
+    let breaker = breakers.for_host(HOST, LISTING);
+    let now = Instant::now();
+
+    for failed in &[true, false, true] {
+        let permit = breaker.permit(now).unwrap();
+        assert!(!permit.probe());
+        permit.finish(*failed, now + FAST);
+    }
+    // Not enough calls yet
+    assert_eq!(state(&breakers, now), BreakerState::Closed);
+
+    breaker.record(None, false, FAST, now);
+    assert_eq!(state(&breakers, now), BreakerState::Open);
+    assert!(breaker.permit(now).is_err());
+    assert_eq!(breakers.paused(LISTING, now), Some(Duration::from_secs(30)));
+    assert_eq!(breakers.paused("datasource.bachtrack_review", now), None);
+}
+
+#[test]
+fn test_opens_on_slow_calls() {
+    let breakers = CircuitBreakers::new(configuration());
+    let breaker = breakers.for_host(HOST, LISTING);
+    let now = Instant::now();
+
+    for latency in &[FAST, Duration::from_secs(12), Duration::from_secs(15), Duration::from_secs(11)] {
+        breaker.record(None, false, *latency, now);
+    }
+    assert_eq!(state(&breakers, now), BreakerState::Closed);
+    breaker.record(None, false, Duration::from_secs(20), now);
+    assert_eq!(state(&breakers, now), BreakerState::Open);
+}
+
+#[test]
+fn test_half_open_probes() {
+    let breakers = CircuitBreakers::new(configuration());
+    let breaker = breakers.for_host(HOST, LISTING);
+    let now = Instant::now();
+    for _ in 0..4 {
+        breaker.record(None, true, FAST, now);
+    }
+
+    // Probes go through once the breaker was open long enough, the calls beyond them don't
+    let later = now + Duration::from_secs(31);
+    let first = breaker.permit(later).unwrap();
+    assert!(first.probe());
+    assert_eq!(state(&breakers, later), BreakerState::HalfOpen);
+    let second = breaker.permit(later).unwrap();
+    assert!(second.probe());
+    assert!(breaker.permit(later).is_err());
+    assert!(breakers.paused(LISTING, later).is_some());
+
+    // A failed probe opens it again
+    first.finish(true, later);
+    assert_eq!(state(&breakers, later), BreakerState::Open);
+    // Late results of the other probe change nothing
+    second.finish(false, later);
+    assert_eq!(state(&breakers, later), BreakerState::Open);
+
+    let much_later = later + Duration::from_secs(31);
+    for _ in 0..2 {
+        let permit = breaker.permit(much_later).unwrap();
+        assert!(permit.probe());
+        permit.finish(false, much_later);
+    }
+    assert_eq!(state(&breakers, much_later), BreakerState::Closed);
+    assert_eq!(breakers.paused(LISTING, much_later), None);
+    assert_eq!(breakers.status(much_later)[0].calls, 0);
+}
+
+#[test]
+fn test_dropped_probe_fails() {
+    let breakers = CircuitBreakers::new(configuration());
+    let breaker = breakers.for_host(HOST, LISTING);
+    let now = Instant::now();
+    for _ in 0..4 {
+        breaker.record(None, true, FAST, now);
+    }
+
+    // A probe whose fetch was cancelled frees its slot instead of holding the breaker half-open
+    let later = now + Duration::from_secs(31);
+    let probe = breaker.permit(later).unwrap();
+    assert_eq!(state(&breakers, later), BreakerState::HalfOpen);
+    drop(probe);
+    assert_eq!(state(&breakers, later), BreakerState::Open);
+}
+
+// A probe of an earlier half-open period finishing late is neither counted nor underflows the probes in flight
+#[test]
+fn test_stale_probe_is_ignored() {
+    let breakers = CircuitBreakers::new(configuration());
+    let breaker = breakers.for_host(HOST, LISTING);
+    let now = Instant::now();
+    for _ in 0..4 {
+        breaker.record(None, true, FAST, now);
+    }
+
+    let later = now + Duration::from_secs(31);
+    let stale = breaker.permit(later).unwrap();
+    breaker.permit(later).unwrap().finish(true, later);
+    assert_eq!(state(&breakers, later), BreakerState::Open);
+
+    let much_later = later + Duration::from_secs(31);
+    let probe = breaker.permit(much_later).unwrap();
+    assert_eq!(state(&breakers, much_later), BreakerState::HalfOpen);
+    // Its failure was about the host back then
+    stale.finish(true, much_later);
+    assert_eq!(state(&breakers, much_later), BreakerState::HalfOpen);
+    // Still room for exactly one more probe
+    let last = breaker.permit(much_later).unwrap();
+    assert!(breaker.permit(much_later).is_err());
+
+    probe.finish(false, much_later);
+    last.finish(false, much_later);
+    assert_eq!(state(&breakers, much_later), BreakerState::Closed);
+}
+
+struct Unreachable;
+
+#[async_trait]
+impl HttpClient for Unreachable {
+    async fn fetch(&self, _url: &str) -> Result<Webpage, Box<dyn Error>> {
+        Err(Box::new(io::Error::new(io::ErrorKind::ConnectionRefused, "connection refused")))
+    }
+}
+
+#[test]
+fn test_http_client() {
+    let breakers = CircuitBreakers::new(configuration());
+    let http_client = CircuitBreakingHttpClient::new(Unreachable, &breakers, LISTING);
+
+    for _ in 0..4 {
+        let error = tokio_test::block_on(http_client.fetch("https://bachtrack.com/concert-event/1")).err().unwrap();
+        assert_eq!(error.to_string(), "connection refused");
+    }
+    let error = tokio_test::block_on(http_client.fetch("https://bachtrack.com/concert-event/2")).err().unwrap();
+    assert_eq!(error.to_string(), "The host's circuit breaker is open, the call was turned away");
+    assert!(http_client.paused().is_some());
+
+    let status = serde_json::to_value(breakers.status(Instant::now())).unwrap();
+    assert_eq!(status[0]["host"], HOST);
+    assert_eq!(status[0]["state"], "open");
+    assert_eq!(status[0]["error_rate"], 1.0);
+    assert_eq!(status[0]["datasources"], serde_json::json!([LISTING]));
+}
@@ -1,13 +1,80 @@
 use {
     async_trait::async_trait,
+    serde::{Serialize, Deserialize},
     super::Extracted,
     std::error::Error,
+    std::fmt::Display,
+    std::time::Duration,
+    tracing::warn,
 };
 
 #[async_trait]
 pub trait Datasource{
     async fn extract(&self, config: &Vec<u8>) -> ExtractResult;
+    // Extracts from an already fetched page
+    fn parse(&self, webpage: &str) -> ExtractResult;
+    // Like parse, for datasources whose items also depend on the configuration the page was fetched for
+    fn parse_for(&self, webpage: &str, _configuration: &[u8]) -> ExtractResult {
+        self.parse(webpage)
+    }
     fn get_name(&self) -> String;
+
+    // How long extractions would fail for, e.g. while the site's circuit breaker is open
+    fn paused(&self) -> Option<Duration> {
+        None
+    }
+
+    // Called with the report's consumed inputs once its items were published, e.g. to mark mail as read
+    fn commit(&self, _consumed: &[String]) {}
+}
+
+// A value on the page the datasource couldn't make sense of, the rest of the page is still extracted
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ExtractionWarning {
+    pub field: String,
+    pub selector: String,
+    pub raw_text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExtractionReport {
+    pub items: Vec<Extracted>,
+    // Selectors the datasource relies on that matched nothing on the page
+    pub unmatched_selectors: Vec<String>,
+    pub warnings: Vec<ExtractionWarning>,
+    pub parsed_fields: usize,
+    // What the extraction read that's done with once the items are published, see Datasource::commit
+    pub consumed: Vec<String>,
+}
+
+impl ExtractionReport {
+    pub fn new(items: Vec<Extracted>) -> ExtractionReport {
+        ExtractionReport{items: items, unmatched_selectors: Vec::new(), warnings: Vec::new(), parsed_fields: 0, consumed: Vec::new()}
+    }
+
+    pub fn field_parsed(&mut self) {
+        self.parsed_fields += 1;
+    }
+
+    pub fn field_failed<E: Display>(&mut self, field: &str, selector: &str, raw_text: &str, reason: E) {
+        warn!(field, selector, raw_text, %reason, "Couldn't parse field");
+        self.warnings.push(ExtractionWarning{
+            field: field.to_owned(),
+            selector: selector.to_owned(),
+            raw_text: raw_text.trim().to_owned(),
+            reason: reason.to_string(),
+        });
+    }
+
+    // Share of the values found on the page that were parsed, 1 when nothing failed
+    pub fn completeness(&self) -> f64 {
+        let attempted = self.parsed_fields + self.warnings.len();
+        if attempted == 0 {
+            return 1.0;
+        }
+        self.parsed_fields as f64 / attempted as f64
+    }
 }
 
-pub type ExtractResult = Result<Vec<Extracted>, Box<dyn Error>>;
\ No newline at end of file
+pub type ExtractResult = Result<ExtractionReport, Box<dyn Error>>;
\ No newline at end of file
@@ -0,0 +1,139 @@
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Italian,
+    Spanish,
+}
+
+pub const ALL_LANGUAGES: &[Language] = &[
+    Language::English,
+    Language::German,
+    Language::French,
+    Language::Italian,
+    Language::Spanish,
+];
+
+impl Language {
+    // Localized month and day names (lowercase) mapped to the english names chrono understands.
+    pub fn names(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::German => GERMAN_NAMES,
+            Language::French => FRENCH_NAMES,
+            Language::Italian => ITALIAN_NAMES,
+            Language::Spanish => SPANISH_NAMES,
+        }
+    }
+
+    // Words that carry no date information and only get in the way of the formats.
+    pub fn filler_words(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => &["at", "on", "the", "from"],
+            Language::German => &["um", "uhr", "am", "den", "von"],
+            Language::French => &["à", "a", "le", "de"],
+            Language::Italian => &["alle", "ore", "il", "dalle"],
+            Language::Spanish => &["de", "del", "a", "las", "el"],
+        }
+    }
+
+    // Words separating the two ends of a time range, e.g. "19:30 bis 21:30".
+    pub fn range_separators(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => &["to", "until"],
+            Language::German => &["bis"],
+            Language::French => &["à", "a", "jusqu'à"],
+            Language::Italian => &["alle", "a"],
+            Language::Spanish => &["a", "hasta"],
+        }
+    }
+}
+
+const GERMAN_NAMES: &[(&str, &str)] = &[
+    ("januar", "january"), ("jänner", "january"), ("jan", "january"),
+    ("februar", "february"), ("feb", "february"),
+    ("märz", "march"), ("maerz", "march"), ("mär", "march"),
+    ("april", "april"), ("apr", "april"),
+    ("mai", "may"),
+    ("juni", "june"), ("jun", "june"),
+    ("juli", "july"), ("jul", "july"),
+    ("august", "august"), ("aug", "august"),
+    ("september", "september"), ("sept", "september"), ("sep", "september"),
+    ("oktober", "october"), ("okt", "october"),
+    ("november", "november"), ("nov", "november"),
+    ("dezember", "december"), ("dez", "december"),
+    ("montag", "monday"),
+    ("dienstag", "tuesday"),
+    ("mittwoch", "wednesday"),
+    ("donnerstag", "thursday"),
+    ("freitag", "friday"),
+    ("samstag", "saturday"), ("sonnabend", "saturday"),
+    ("sonntag", "sunday"),
+];
+
+const FRENCH_NAMES: &[(&str, &str)] = &[
+    ("janvier", "january"), ("janv", "january"),
+    ("février", "february"), ("fevrier", "february"), ("févr", "february"), ("fevr", "february"),
+    ("mars", "march"),
+    ("avril", "april"), ("avr", "april"),
+    ("mai", "may"),
+    ("juin", "june"),
+    ("juillet", "july"), ("juil", "july"),
+    ("août", "august"), ("aout", "august"),
+    ("septembre", "september"), ("sept", "september"),
+    ("octobre", "october"), ("oct", "october"),
+    ("novembre", "november"), ("nov", "november"),
+    ("décembre", "december"), ("decembre", "december"), ("déc", "december"), ("dec", "december"),
+    ("lundi", "monday"),
+    ("mardi", "tuesday"),
+    ("mercredi", "wednesday"),
+    ("jeudi", "thursday"),
+    ("vendredi", "friday"),
+    ("samedi", "saturday"),
+    ("dimanche", "sunday"),
+];
+
+const ITALIAN_NAMES: &[(&str, &str)] = &[
+    ("gennaio", "january"), ("gen", "january"),
+    ("febbraio", "february"), ("feb", "february"),
+    ("marzo", "march"), ("mar", "march"),
+    ("aprile", "april"), ("apr", "april"),
+    ("maggio", "may"), ("mag", "may"),
+    ("giugno", "june"), ("giu", "june"),
+    ("luglio", "july"), ("lug", "july"),
+    ("agosto", "august"), ("ago", "august"),
+    ("settembre", "september"), ("set", "september"),
+    ("ottobre", "october"), ("ott", "october"),
+    ("novembre", "november"), ("nov", "november"),
+    ("dicembre", "december"), ("dic", "december"),
+    ("lunedì", "monday"), ("lunedi", "monday"),
+    ("martedì", "tuesday"), ("martedi", "tuesday"),
+    ("mercoledì", "wednesday"), ("mercoledi", "wednesday"),
+    ("giovedì", "thursday"), ("giovedi", "thursday"),
+    ("venerdì", "friday"), ("venerdi", "friday"),
+    ("sabato", "saturday"),
+    ("domenica", "sunday"),
+];
+
+const SPANISH_NAMES: &[(&str, &str)] = &[
+    ("enero", "january"), ("ene", "january"),
+    ("febrero", "february"), ("feb", "february"),
+    ("marzo", "march"),
+    ("abril", "april"), ("abr", "april"),
+    ("mayo", "may"),
+    ("junio", "june"),
+    ("julio", "july"),
+    ("agosto", "august"), ("ago", "august"),
+    ("septiembre", "september"), ("setiembre", "september"), ("sept", "september"),
+    ("octubre", "october"), ("oct", "october"),
+    ("noviembre", "november"), ("nov", "november"),
+    ("diciembre", "december"), ("dic", "december"),
+    ("lunes", "monday"),
+    ("martes", "tuesday"),
+    ("miércoles", "wednesday"), ("miercoles", "wednesday"),
+    ("jueves", "thursday"),
+    ("viernes", "friday"),
+    ("sábado", "saturday"), ("sabado", "saturday"),
+    ("domingo", "sunday"),
+];
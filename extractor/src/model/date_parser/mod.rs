@@ -0,0 +1,8 @@
+mod languages;
+mod parser;
+
+#[cfg(test)]
+mod tests;
+
+pub use languages::{Language, ALL_LANGUAGES};
+pub use parser::{DateTimeParser, ParsedTime, end_time_after};
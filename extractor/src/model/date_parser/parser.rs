@@ -0,0 +1,141 @@
+use {
+    std::error::Error,
+    chrono::{NaiveDate, NaiveDateTime, NaiveTime, Duration},
+    regex::Regex,
+    crate::model::errors,
+    super::languages::{Language, ALL_LANGUAGES},
+};
+
+const TIME_FORMAT: &str = "%H:%M";
+
+lazy_static! {
+    static ref RE_HOUR_SEPARATOR: Regex = Regex::new(r"(\d{1,2})h(\d{2})").unwrap();
+    static ref RE_GERMAN_TIME: Regex = Regex::new(r"(\d{1,2})\.(\d{2})\s*uhr").unwrap();
+    static ref RE_ORDINAL: Regex = Regex::new(r"(\d{1,2})(?:st|nd|rd|th|er|º|°|ª)(\s|,|$)").unwrap();
+    static ref RE_DAY_DOT: Regex = Regex::new(r"(\d{1,2})\.(\s|,|$)").unwrap();
+    // Time ranges, with each language's own separators besides the dashes
+    static ref RE_RANGES: Vec<(Language, Regex)> = ALL_LANGUAGES
+        .iter()
+        .map(|language| (*language, range_regex(language)))
+        .collect();
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ParsedTime {
+    DateTime(NaiveDateTime),
+    Date(NaiveDate),
+    Range(NaiveDateTime, NaiveDateTime),
+}
+
+// A set of languages and chrono formats a datasource expects its dates in.
+// Localized names are translated to english before the formats are applied, 
+// so the formats are always written with english names in mind (%A, %B).
+#[derive(Debug, Copy, Clone)]
+pub struct DateTimeParser {
+    languages: &'static [Language],
+    date_time_formats: &'static [&'static str],
+    date_formats: &'static [&'static str],
+}
+
+impl DateTimeParser {
+    pub const fn new(
+        languages: &'static [Language], 
+        date_time_formats: &'static [&'static str], 
+        date_formats: &'static [&'static str]
+    ) -> DateTimeParser {
+        DateTimeParser{
+            languages: languages,
+            date_time_formats: date_time_formats,
+            date_formats: date_formats,
+        }
+    }
+
+    pub fn parse(&self, text: &str) -> Result<ParsedTime, Box<dyn Error>> {
+        for language in self.languages {
+            let (normalized, range_end) = normalize(text, language);
+
+            for format in self.date_time_formats {
+                let start = match NaiveDateTime::parse_from_str(&normalized, format) {
+                    Ok(start) => start,
+                    Err(_) => continue,
+                };
+
+                return match range_end {
+                    Some(end) => Ok(ParsedTime::Range(start, end_time_after(start, end)?)),
+                    None => Ok(ParsedTime::DateTime(start)),
+                };
+            }
+
+            for format in self.date_formats {
+                if let Ok(date) = NaiveDate::parse_from_str(&normalized, format) {
+                    return Ok(ParsedTime::Date(date));
+                }
+            }
+        }
+
+        Err(Box::new(errors::UnrecognizedDateTimeError{}))
+    }
+}
+
+// Places a time of day on the first occurrence after the start time.
+pub fn end_time_after(start: NaiveDateTime, end: NaiveTime) -> Result<NaiveDateTime, Box<dyn Error>> {
+    let end_time = start.date().and_time(end);
+    if end_time > start {
+        return Ok(end_time);
+    }
+
+    // The event ends after midnight
+    match end_time.checked_add_signed(Duration::days(1)) {
+        Some(time) => Ok(time),
+        None => Err(Box::new(errors::DateTimeCalculationError{})),
+    }
+}
+
+// Brings the text to the english "<names> <numbers> <HH:MM>" shape the formats are written for.
+// Returns the normalized text and the end of a time range, if the text contains one.
+fn normalize(text: &str, language: &Language) -> (String, Option<NaiveTime>) {
+    let mut text = text.to_lowercase();
+
+    text = RE_HOUR_SEPARATOR.replace_all(&text, "$1:$2").to_string();
+    text = RE_GERMAN_TIME.replace_all(&text, "$1:$2").to_string();
+
+    let mut range_end = None;
+    if let Some((_, re_range)) = RE_RANGES.iter().find(|(ranged, _)| ranged == language) {
+        if let Some(capture) = re_range.captures(&text) {
+            range_end = NaiveTime::parse_from_str(&capture[2], TIME_FORMAT).ok();
+            text = re_range.replace(&text, "$1").to_string();
+        }
+    }
+
+    text = RE_ORDINAL.replace_all(&text, "$1$2").to_string();
+    text = RE_DAY_DOT.replace_all(&text, "$1$2").to_string();
+
+    let words = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '|')
+        .filter(|word| !word.is_empty())
+        .filter_map(|word| {
+            let name = word.trim_end_matches('.');
+            if !name.chars().all(char::is_alphabetic) {
+                return Some(word);
+            }
+            if language.filler_words().contains(&name) {
+                return None;
+            }
+            match language.names().iter().find(|(localized, _)| *localized == name) {
+                Some((_, english)) => Some(*english),
+                None => Some(name),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (words.join(" "), range_end)
+}
+
+fn range_regex(language: &Language) -> Regex {
+    let separators = language.range_separators()
+        .iter()
+        .map(|separator| regex::escape(separator))
+        .collect::<Vec<_>>()
+        .join("|");
+    Regex::new(&format!(r"(\d{{1,2}}:\d{{2}})(?:\s*[-–—]\s*|\s+(?:{})\s+)(\d{{1,2}}:\d{{2}})", separators)).unwrap()
+}
@@ -0,0 +1,88 @@
+use {
+    chrono::{NaiveDate, NaiveDateTime},
+    super::{DateTimeParser, ParsedTime, ALL_LANGUAGES, Language},
+};
+
+const PARSER: DateTimeParser = DateTimeParser::new(
+    ALL_LANGUAGES,
+    &["%A %d %B %Y %H:%M", "%d %B %Y %H:%M", "%d.%m.%Y %H:%M", "%Y-%m-%d %H:%M"],
+    &["%A %d %B %Y", "%d %B %Y", "%d.%m.%Y"],
+);
+
+fn date_time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(year, month, day).and_hms(hour, minute, 0)
+}
+
+#[test]
+fn test_parse_date_times() {
+    let cases = vec![
+        ("Friday 23 October 2020 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("23 October 2020 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("Thursday 1st October 2020 at 18:30", date_time(2020, 10, 1, 18, 30)),
+        ("Freitag, 23. Oktober 2020, 19:30 Uhr", date_time(2020, 10, 23, 19, 30)),
+        ("Sonntag, 1. März 2020 um 11.00 Uhr", date_time(2020, 3, 1, 11, 0)),
+        ("23. Okt. 2020 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("vendredi 23 octobre 2020 à 19h30", date_time(2020, 10, 23, 19, 30)),
+        ("samedi 1er août 2020 20h00", date_time(2020, 8, 1, 20, 0)),
+        ("venerdì 23 ottobre 2020, ore 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("domenica 1 marzo 2020 alle 17:00", date_time(2020, 3, 1, 17, 0)),
+        ("viernes, 23 de octubre de 2020, 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("sábado 1 de agosto de 2020 a las 20:00", date_time(2020, 8, 1, 20, 0)),
+        ("23.10.2020 19:30", date_time(2020, 10, 23, 19, 30)),
+        ("2020-10-23 19:30", date_time(2020, 10, 23, 19, 30)),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(PARSER.parse(text).unwrap(), ParsedTime::DateTime(expected), "parsing '{}'", text);
+    }
+}
+
+#[test]
+fn test_parse_dates() {
+    let cases = vec![
+        ("Friday 23 October 2020", NaiveDate::from_ymd(2020, 10, 23)),
+        ("Freitag, 23. Oktober 2020", NaiveDate::from_ymd(2020, 10, 23)),
+        ("vendredi 23 octobre 2020", NaiveDate::from_ymd(2020, 10, 23)),
+        ("23 ottobre 2020", NaiveDate::from_ymd(2020, 10, 23)),
+        ("23 de octubre de 2020", NaiveDate::from_ymd(2020, 10, 23)),
+        ("23.10.2020", NaiveDate::from_ymd(2020, 10, 23)),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(PARSER.parse(text).unwrap(), ParsedTime::Date(expected), "parsing '{}'", text);
+    }
+}
+
+#[test]
+fn test_parse_ranges() {
+    let cases = vec![
+        ("Friday 23 October 2020 19:30 - 21:45", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("Friday 23 October 2020 19:30 to 21:45", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("Freitag, 23. Oktober 2020, von 19:30 bis 21:45 Uhr", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("vendredi 23 octobre 2020 de 19h30 à 21h45", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("venerdì 23 ottobre 2020 dalle 19:30 alle 21:45", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("viernes 23 de octubre de 2020 de 19:30 a 21:45", date_time(2020, 10, 23, 19, 30), date_time(2020, 10, 23, 21, 45)),
+        ("Saturday 24 October 2020 22:00–01:00", date_time(2020, 10, 24, 22, 0), date_time(2020, 10, 25, 1, 0)),
+    ];
+
+    for (text, start, end) in cases {
+        assert_eq!(PARSER.parse(text).unwrap(), ParsedTime::Range(start, end), "parsing '{}'", text);
+    }
+}
+
+#[test]
+fn test_parse_failures() {
+    let english_only = DateTimeParser::new(&[Language::English], &["%A %d %B %Y %H:%M"], &[]);
+
+    let cases = vec![
+        (PARSER, ""),
+        (PARSER, "Tickets available soon"),
+        (PARSER, "Friday 31 February 2020 19:30"),
+        (english_only, "Freitag 23 Oktober 2020 19:30"),
+        (english_only, "Friday 23 October 2020"),
+    ];
+
+    for (parser, text) in cases {
+        assert!(parser.parse(text).is_err(), "parsing '{}'", text);
+    }
+}
@@ -0,0 +1,61 @@
+use {
+    chrono::Duration,
+    crate::model::Piece,
+};
+
+// Typical lengths in minutes, matched against the words of the lowercase work name in order.
+const WORK_LENGTHS: &[(&[&str], i64)] = &[
+    (&["opera", "oper", "opéra"], 150),
+    (&["oratorio", "passion"], 120),
+    (&["requiem", "missa", "mass", "messe"], 45),
+    (&["symphony", "symphonie", "sinfonie", "sinfonia", "sinfonía"], 40),
+    (&["concerto", "konzert"], 30),
+    (&["quartet", "quintet", "sextet", "octet", "trio"], 25),
+    (&["sonata", "sonate"], 20),
+    (&["overture", "ouvertüre", "ouverture"], 10),
+    (&["motet", "anthem", "aria", "song", "lied"], 8),
+];
+const DEFAULT_WORK_LENGTH: i64 = 15;
+
+// Programmes longer than this usually have an interval
+const INTERVAL_THRESHOLD: i64 = 75;
+const INTERVAL_LENGTH: i64 = 20;
+const ROUNDING: i64 = 5;
+
+// Estimates how long a performance of the given programme takes. 
+// Returns None when there's no programme to estimate from.
+pub fn estimate_programme_length(pieces: &[Piece]) -> Option<Duration> {
+    if pieces.is_empty() {
+        return None;
+    }
+
+    let mut minutes: i64 = pieces
+        .iter()
+        .map(|piece| work_length(&piece.name))
+        .sum();
+
+    if minutes > INTERVAL_THRESHOLD {
+        minutes += INTERVAL_LENGTH;
+    }
+
+    // Round up, nobody announces a concert ending at 21:07
+    minutes = (minutes + ROUNDING - 1) / ROUNDING * ROUNDING;
+
+    Some(Duration::minutes(minutes))
+}
+
+fn work_length(name: &str) -> i64 {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name.split(|c: char| !c.is_alphanumeric()).collect();
+    for (keywords, length) in WORK_LENGTHS {
+        if keywords.iter().any(|keyword| words.iter().any(|word| is_word(word, keyword))) {
+            return *length;
+        }
+    }
+    DEFAULT_WORK_LENGTH
+}
+
+// Whole words only, "Goldberg Variations" has no aria in it. Plurals count too
+fn is_word(word: &str, keyword: &str) -> bool {
+    word == keyword || word.strip_suffix('s') == Some(keyword)
+}
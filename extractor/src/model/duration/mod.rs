@@ -0,0 +1,8 @@
+mod source;
+mod estimate;
+
+#[cfg(test)]
+mod tests;
+
+pub use source::{find_duration, find_end_time};
+pub use estimate::estimate_programme_length;
@@ -0,0 +1,57 @@
+use {
+    chrono::{Duration, NaiveTime},
+    regex::Regex,
+};
+
+const DURATION_KEYWORDS: &str = "running time|duration|length|spieldauer|aufführungsdauer|dauer|durée|durata|duración|duracion";
+// Only phrases announcing an end time, "end" or "final" alone are as likely to be about something else
+const END_KEYWORDS: &str = "ends|finishes|endet|ende|fin vers|fin prévue|fin du concert|fine prevista|termina";
+const HOUR_UNITS: &str = "hours|hour|hrs|hr|stunden|stunde|std|heures|heure|horas|hora|ore|ora|h";
+const MINUTE_UNITS: &str = "minutes|minute|minuten|minuti|minutos|mins|min|m";
+const CONJUNCTIONS: &str = "and|und|et|e|y";
+
+lazy_static! {
+    // Ends on a word boundary, so a bare "h" or "m" can't be the start of a word like "movements"
+    static ref RE_DURATION: Regex = Regex::new(&format!(
+        r"\b(?:{})\b\D{{0,20}}?(\d+)\s*({}|{})\.?(?:\s*(?:{})?\s*(\d+)\s*(?:{})?)?\b",
+        DURATION_KEYWORDS, HOUR_UNITS, MINUTE_UNITS, CONJUNCTIONS, MINUTE_UNITS
+    )).unwrap();
+    static ref RE_MINUTE_UNIT: Regex = Regex::new(&format!(r"^(?:{})$", MINUTE_UNITS)).unwrap();
+    static ref RE_END: Regex = Regex::new(&format!(r"\b(?:{})\b\D{{0,20}}?(\d{{1,2}})[:.h](\d{{2}})", END_KEYWORDS)).unwrap();
+}
+
+// Finds a running time given in free text, e.g. "Running time: approx. 2 hours 30 minutes" or "Dauer: ca. 90 Min."
+pub fn find_duration(text: &str) -> Option<Duration> {
+    let text = text.to_lowercase();
+    let capture = RE_DURATION.captures(&text)?;
+    let amount: i64 = capture[1].parse().ok()?;
+
+    if RE_MINUTE_UNIT.is_match(&capture[2]) {
+        return positive(Duration::minutes(amount));
+    }
+
+    let minutes: i64 = match capture.get(3) {
+        Some(minutes) => minutes.as_str().parse().ok()?,
+        None => 0,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+
+    positive(Duration::hours(amount) + Duration::minutes(minutes))
+}
+
+// Finds an end time given in free text, e.g. "Ends approx. 21:45" or "Ende gegen 22.15 Uhr"
+pub fn find_end_time(text: &str) -> Option<NaiveTime> {
+    let text = text.to_lowercase();
+    let capture = RE_END.captures(&text)?;
+    NaiveTime::from_hms_opt(capture[1].parse().ok()?, capture[2].parse().ok()?, 0)
+}
+
+fn positive(duration: Duration) -> Option<Duration> {
+    if duration > Duration::zero() {
+        Some(duration)
+    } else {
+        None
+    }
+}
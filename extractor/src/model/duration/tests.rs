@@ -0,0 +1,84 @@
+use {
+    chrono::{Duration, NaiveTime},
+    crate::model::Piece,
+    super::{find_duration, find_end_time, estimate_programme_length},
+};
+
+#[test]
+fn test_find_duration() {
+    let cases = vec![
+        ("Running time: approx. 2 hours 30 minutes", Some(Duration::minutes(150))),
+        ("Running time 2h30 including one interval", Some(Duration::minutes(150))),
+        ("Duration: 90 minutes, no interval", Some(Duration::minutes(90))),
+        ("Dauer: ca. 2 Stunden und 15 Minuten", Some(Duration::minutes(135))),
+        ("Spieldauer ca. 100 Min.", Some(Duration::minutes(100))),
+        ("Durée : 1h45 avec entracte", Some(Duration::minutes(105))),
+        ("Durata: 2 ore e 10 minuti", Some(Duration::minutes(130))),
+        ("Duración aproximada: 1 hora 50 minutos", Some(Duration::minutes(110))),
+        ("Every Thursday and Saturday you can expect a special cultural hallmark", None),
+        ("Duration: 0 minutes", None),
+        ("Duration of the 3 movements", None),
+        ("Wavelength 5 m", None),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(find_duration(text), expected, "finding duration in '{}'", text);
+    }
+}
+
+#[test]
+fn test_find_end_time() {
+    let cases = vec![
+        ("The concert ends approx. 21:45", Some(NaiveTime::from_hms(21, 45, 0))),
+        ("Ende gegen 22.15 Uhr", Some(NaiveTime::from_hms(22, 15, 0))),
+        ("Fin vers 22h30", Some(NaiveTime::from_hms(22, 30, 0))),
+        ("Fine prevista alle 23:00", Some(NaiveTime::from_hms(23, 0, 0))),
+        ("Doors open at 19:00, end of the weekend series", None),
+        ("Ends approx. 25:00", None),
+        ("The final concert begins at 20:00", None),
+        ("Da capo al fine, 20:00", None),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(find_end_time(text), expected, "finding end time in '{}'", text);
+    }
+}
+
+fn piece(name: &str) -> Piece {
+    Piece{name: name.to_owned(), artists: vec![]}
+}
+
+#[test]
+fn test_estimate_programme_length() {
+    let cases = vec![
+        (vec![], None),
+        (vec![piece("Symphony no. 5 in C minor, Op.67")], Some(Duration::minutes(40))),
+        (vec![piece("Die Zauberflöte")], Some(Duration::minutes(15))),
+        (vec![piece("Goldberg Variations, BWV 988")], Some(Duration::minutes(15))),
+        (vec![piece("Massenet: Méditation from Thaïs")], Some(Duration::minutes(15))),
+        (vec![piece("A patriotic medley")], Some(Duration::minutes(15))),
+        (vec![piece("Piano Trios, Op.1")], Some(Duration::minutes(25))),
+        (vec![piece("Le nozze di Figaro (opera buffa)")], Some(Duration::minutes(170))),
+        (
+            vec![
+                piece("Overture to Egmont, Op.84"),
+                piece("Piano Concerto no. 4 in G major, Op.58"),
+                piece("Symphony no. 7 in A major, Op.92"),
+            ],
+            Some(Duration::minutes(100)),
+        ),
+        (
+            vec![
+                piece("Missa in C, \"Coronation\", K317"),
+                piece("Zadok the Priest, HWV 258: God save the King"),
+                piece("Motet in D, \"Ave verum Corpus\", K618"),
+                piece("My heart is inditing; Coronation Anthem No. 3, HWV 261"),
+            ],
+            Some(Duration::minutes(100)),
+        ),
+    ];
+
+    for (pieces, expected) in cases {
+        assert_eq!(estimate_programme_length(&pieces), expected, "estimating {:?}", pieces);
+    }
+}
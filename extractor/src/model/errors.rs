@@ -47,3 +47,338 @@ impl Error for RegexDidNotMatchError {
     }
 }
 
+
+#[derive(Debug)]
+pub struct UnrecognizedDateTimeError;
+
+impl fmt::Display for UnrecognizedDateTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The text did not match any of the known date formats")
+    }
+}
+
+impl Error for UnrecognizedDateTimeError {
+    fn description(&self) -> &str {
+        "The text did not match any of the known date formats"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct ReprocessUsageError;
+
+impl fmt::Display for ReprocessUsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Usage: extractor reprocess <from YYYY-MM-DD> <to YYYY-MM-DD> [datasource...]")
+    }
+}
+
+impl Error for ReprocessUsageError {
+    fn description(&self) -> &str {
+        "Usage: extractor reprocess <from YYYY-MM-DD> <to YYYY-MM-DD> [datasource...]"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct TooManyRedirectsError;
+
+impl fmt::Display for TooManyRedirectsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The request was redirected too many times")
+    }
+}
+
+impl Error for TooManyRedirectsError {
+    fn description(&self) -> &str {
+        "The request was redirected too many times"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct CrossHostRedirectError;
+
+impl fmt::Display for CrossHostRedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The request was redirected to another host, which the session doesn't follow")
+    }
+}
+
+impl Error for CrossHostRedirectError {
+    fn description(&self) -> &str {
+        "The request was redirected to another host, which the session doesn't follow"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct MissingColumnError;
+
+impl fmt::Display for MissingColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The file has no column for a field the profile requires")
+    }
+}
+
+impl Error for MissingColumnError {
+    fn description(&self) -> &str {
+        "The file has no column for a field the profile requires"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct XlsxUnsupportedError;
+
+impl fmt::Display for XlsxUnsupportedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Reading xlsx files needs the extractor built with the xlsx feature")
+    }
+}
+
+impl Error for XlsxUnsupportedError {
+    fn description(&self) -> &str {
+        "Reading xlsx files needs the extractor built with the xlsx feature"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct IncompleteRowError;
+
+impl fmt::Display for IncompleteRowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The row is missing a value the field needs")
+    }
+}
+
+impl Error for IncompleteRowError {
+    fn description(&self) -> &str {
+        "The row is missing a value the field needs"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct EmptyImportError;
+
+impl fmt::Display for EmptyImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The import has neither a path nor a content")
+    }
+}
+
+impl Error for EmptyImportError {
+    fn description(&self) -> &str {
+        "The import has neither a path nor a content"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct UnknownItemTypeError;
+
+impl fmt::Display for UnknownItemTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The item type is none of the extracted types")
+    }
+}
+
+impl Error for UnknownItemTypeError {
+    fn description(&self) -> &str {
+        "The item type is none of the extracted types"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PluginError;
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The plugin broke the plugin interface or went over its limits")
+    }
+}
+
+impl Error for PluginError {
+    fn description(&self) -> &str {
+        "The plugin broke the plugin interface or went over its limits"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PluginsUnsupportedError;
+
+impl fmt::Display for PluginsUnsupportedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Loading plugins needs the extractor built with the plugins feature")
+    }
+}
+
+impl Error for PluginsUnsupportedError {
+    fn description(&self) -> &str {
+        "Loading plugins needs the extractor built with the plugins feature"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PluginFetchNotAllowedError;
+
+impl fmt::Display for PluginFetchNotAllowedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The plugin asked for pages outside its allowed hosts or over its fetch limit")
+    }
+}
+
+impl Error for PluginFetchNotAllowedError {
+    fn description(&self) -> &str {
+        "The plugin asked for pages outside its allowed hosts or over its fetch limit"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PluginUnloadedError;
+
+impl fmt::Display for PluginUnloadedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The plugin was removed from the plugin directory")
+    }
+}
+
+impl Error for PluginUnloadedError {
+    fn description(&self) -> &str {
+        "The plugin was removed from the plugin directory"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct InvalidPluginNameError;
+
+impl fmt::Display for InvalidPluginNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Plugin names may only contain lowercase letters, digits, '_' and '-'")
+    }
+}
+
+impl Error for InvalidPluginNameError {
+    fn description(&self) -> &str {
+        "Plugin names may only contain lowercase letters, digits, '_' and '-'"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PluginConfigurationNotAllowedError;
+
+impl fmt::Display for PluginConfigurationNotAllowedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The plugin may only pass configurations on to its own datasource")
+    }
+}
+
+impl Error for PluginConfigurationNotAllowedError {
+    fn description(&self) -> &str {
+        "The plugin may only pass configurations on to its own datasource"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct InvalidRouteError;
+
+impl fmt::Display for InvalidRouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "A route has an unknown item type or a malformed subject")
+    }
+}
+
+impl Error for InvalidRouteError {
+    fn description(&self) -> &str {
+        "A route has an unknown item type or a malformed subject"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct CircuitOpenError;
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The host's circuit breaker is open, the call was turned away")
+    }
+}
+
+impl Error for CircuitOpenError {
+    fn description(&self) -> &str {
+        "The host's circuit breaker is open, the call was turned away"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct PathOutsideRootError;
+
+impl fmt::Display for PathOutsideRootError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The path is outside the directory the datasource may read from")
+    }
+}
+
+impl Error for PathOutsideRootError {
+    fn description(&self) -> &str {
+        "The path is outside the directory the datasource may read from"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct ImportOnDiskError;
+
+impl fmt::Display for ImportOnDiskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The import's file or profile is on disk, only an extraction reads it")
+    }
+}
+
+impl Error for ImportOnDiskError {
+    fn description(&self) -> &str {
+        "The import's file or profile is on disk, only an extraction reads it"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct InvalidDateRangeError;
+
+impl fmt::Display for InvalidDateRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The search starts after it ends")
+    }
+}
+
+impl Error for InvalidDateRangeError {
+    fn description(&self) -> &str {
+        "The search starts after it ends"
+    }
+}
+
+
+#[derive(Debug)]
+pub struct TooManySearchWindowsError;
+
+impl fmt::Display for TooManySearchWindowsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The search spans more windows than a single configuration may search")
+    }
+}
+
+impl Error for TooManySearchWindowsError {
+    fn description(&self) -> &str {
+        "The search spans more windows than a single configuration may search"
+    }
+}
@@ -1,12 +1,34 @@
 use {
+    std::error::Error,
     serde::{Serialize, Deserialize},
-    chrono::NaiveDateTime,
+    chrono::{NaiveDate, NaiveDateTime},
+    super::errors::UnknownItemTypeError,
 };
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum EndTimePrecision {
+    // The end time (or the duration) was given by the source
+    Exact,
+    // The end time was estimated from the programme
+    Estimated,
+    // Nothing was known about the end, a fixed length was used
+    Default,
+}
+
+// Events stored before the precision was recorded say nothing about their end
+impl Default for EndTimePrecision {
+    fn default() -> Self {
+        EndTimePrecision::Default
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
 pub struct EventTime {
     pub start_time: NaiveDateTime,
     pub end_time: NaiveDateTime,
+    #[serde(default)]
+    pub end_time_precision: EndTimePrecision,
     pub local_timezone: bool,
 }
 
@@ -47,23 +69,56 @@ pub struct MusicEvent{
     // venue: Venue,
     pub description: String,
     pub time: EventTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub festival_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Festival {
+    pub id: String,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub city: Option<City>,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Review {
+    pub reviewer: Person,
+    // Stars out of 5, not every review is rated
+    pub rating: Option<u8>,
+    pub published: NaiveDate,
+    pub excerpt: String,
+    // The reviewed event, same id as the listing's Configuration::id
+    pub listing_id: Option<String>,
+    pub event_url: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Configuration{
     pub ds_name: String, 
     pub value: String, 
+    // Stable across fetches of the same page, used to deduplicate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // Listings found on a festival page belong to that festival
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub festival_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum Extracted {
     MusicEvent(MusicEvent),
+    // Before the variants it would also deserialize as, they only need a name
+    Festival(Festival),
     Venue(Venue),
     City(City),
     Country(Country),
     Person(Person),
     Piece(Piece),
+    Review(Review),
     Configuration(Configuration),
 }
 
@@ -76,7 +131,39 @@ impl Extracted {
             Extracted::Person(_) => "normalizer.performer".to_owned(),
             Extracted::City(_) => "normalizer.city".to_owned(),
             Extracted::Piece(_) => "normalizer.piece".to_owned(),
+            Extracted::Review(_) => "normalizer.review".to_owned(),
+            Extracted::Festival(_) => "normalizer.festival".to_owned(),
             Extracted::Configuration(config) => config.ds_name.to_owned(),
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_type_name(&self) -> &'static str {
+        match self {
+            Extracted::MusicEvent(_) => "MusicEvent",
+            Extracted::Venue(_) => "Venue",
+            Extracted::Country(_) => "Country",
+            Extracted::Person(_) => "Person",
+            Extracted::City(_) => "City",
+            Extracted::Piece(_) => "Piece",
+            Extracted::Review(_) => "Review",
+            Extracted::Festival(_) => "Festival",
+            Extracted::Configuration(_) => "Configuration",
+        }
+    }
+
+    // The inverse of get_type_name, for items whose variant can't be told from their fields alone
+    pub fn from_type_name(type_name: &str, item: serde_json::Value) -> Result<Extracted, Box<dyn Error>> {
+        Ok(match type_name {
+            "MusicEvent" => Extracted::MusicEvent(serde_json::from_value(item)?),
+            "Venue" => Extracted::Venue(serde_json::from_value(item)?),
+            "Country" => Extracted::Country(serde_json::from_value(item)?),
+            "Person" => Extracted::Person(serde_json::from_value(item)?),
+            "City" => Extracted::City(serde_json::from_value(item)?),
+            "Piece" => Extracted::Piece(serde_json::from_value(item)?),
+            "Review" => Extracted::Review(serde_json::from_value(item)?),
+            "Festival" => Extracted::Festival(serde_json::from_value(item)?),
+            "Configuration" => Extracted::Configuration(serde_json::from_value(item)?),
+            _ => return Err(Box::new(UnknownItemTypeError)),
+        })
+    }
+}
@@ -1,11 +1,41 @@
 use {
     async_trait::async_trait,
     std::error::Error,
+    std::time::{Duration, Instant},
+    url::Url,
+    tracing::{warn, debug},
+    reqwest::header::CONTENT_TYPE,
+    crate::metrics,
+    crate::archive::PageArchive,
+    crate::model::links,
+    crate::model::session::Session,
+    crate::model::charset::Webpage,
+    crate::model::circuit_breaker::{CircuitBreakers, SLOW_CALL},
 };
 
+// A fetch that never returns would hold its breaker permit and its worker slot, and never be recorded.
+// Cut off well after it counts as slow, so timed out calls open the breaker as slow and failed ones
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(3 * SLOW_CALL.as_secs());
+pub const CONNECT_TIMEOUT: Duration = SLOW_CALL;
+
 #[async_trait]
-pub trait HttpClient:{
-    async fn get(&self, url: &str) -> Result<String, Box<dyn Error>>;
+pub trait HttpClient: Sync{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>>;
+
+    // The page's text, decoded with the encoding the page was detected to use
+    async fn get(&self, url: &str) -> Result<String, Box<dyn Error>> {
+        Ok(self.fetch(url).await?.text())
+    }
+
+    // Like get, for pages that need the configuration they were fetched for to be parsed again, see ArchivingHttpClient
+    async fn get_for(&self, url: &str, _configuration: &[u8]) -> Result<String, Box<dyn Error>> {
+        self.get(url).await
+    }
+
+    // How long fetches would be turned away for, callers can hold off instead
+    fn paused(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -18,16 +48,164 @@ impl WebpageHttpClient {
 
 #[async_trait]
 impl HttpClient for WebpageHttpClient{
-    async fn get(&self, url: &str) -> Result<String, Box<dyn Error>> {
-        let body = reqwest::get(url)
-            .await?
-            .text()
-            .await?;
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()?;
+        let response = client.get(url).send().await?;
+        // An error page is nothing to extract from, and a sign the site is struggling
+        if response.status().is_server_error() {
+            response.error_for_status_ref()?;
+        }
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned());
+        let bytes = response.bytes().await?;
     
-        Ok(body)
+        Ok(Webpage::new(bytes.to_vec(), content_type.as_deref()))
+    }
+}
+
+// Fetches through a datasource's session, for sites that need cookies or a warm-up before serving pages
+#[derive(Copy, Clone)]
+pub struct SessionHttpClient<'a>{
+    session: &'a Session,
+}
+
+impl<'a> SessionHttpClient<'a>{
+    pub fn new(session: &'a Session) -> SessionHttpClient<'a> {
+        SessionHttpClient{session: session}
+    }
+}
+
+#[async_trait]
+impl<'a> HttpClient for SessionHttpClient<'a>{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        self.session.fetch(url).await
+    }
+}
+
+// Records the fetch latency of the wrapped client under the datasource's name
+#[derive(Copy, Clone)]
+pub struct MeasuredHttpClient<H: HttpClient>{
+    http_client: H,
+    datasource_name: &'static str,
+}
+
+impl<H: HttpClient> MeasuredHttpClient<H>{
+    pub fn new(http_client: H, datasource_name: &'static str) -> MeasuredHttpClient<H> {
+        MeasuredHttpClient{http_client: http_client, datasource_name: datasource_name}
+    }
+}
+
+#[async_trait]
+impl<H: HttpClient + Send + Sync> HttpClient for MeasuredHttpClient<H>{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        tracing::Span::current().record("url", &url);
+        tracing::debug!(url, "fetching");
+
+        let timer = metrics::HTTP_FETCH_DURATION
+            .with_label_values(&[self.datasource_name])
+            .start_timer();
+        let result = self.http_client.fetch(url).await;
+        timer.observe_duration();
+
+        if let Ok(webpage) = &result {
+            debug!(url, encoding = webpage.encoding_name(), detected_from = %webpage.encoding_source, "decoding");
+            metrics::PAGE_ENCODINGS
+                .with_label_values(&[self.datasource_name, webpage.encoding_name(), &webpage.encoding_source.to_string()])
+                .inc();
+        }
+        result
+    }
+
+    fn paused(&self) -> Option<Duration> {
+        self.http_client.paused()
     }
 }
 
+// Keeps every fetched body in the archive, so pages can be parsed again without fetching them
+#[derive(Copy, Clone)]
+pub struct ArchivingHttpClient<'a, H: HttpClient>{
+    http_client: H,
+    archive: &'a PageArchive,
+    datasource_name: &'static str,
+}
+
+impl<'a, H: HttpClient + Send + Sync> ArchivingHttpClient<'a, H>{
+    pub fn new(http_client: H, archive: &'a PageArchive, datasource_name: &'static str) -> ArchivingHttpClient<'a, H> {
+        ArchivingHttpClient{http_client: http_client, archive: archive, datasource_name: datasource_name}
+    }
+
+    async fn fetch_archived(&self, url: &str, configuration: Option<&[u8]>) -> Result<Webpage, Box<dyn Error>> {
+        let webpage = self.http_client.fetch(url).await?;
+
+        // Pages are archived decoded, reprocessing them doesn't depend on the headers they were served with
+        // and losing a page from the archive shouldn't lose the extraction
+        let archived_url = links::canonicalize(url).unwrap_or(url.to_owned());
+        let configuration = configuration.map(|configuration| String::from_utf8_lossy(configuration));
+        if let Err(e) = self.archive.store(self.datasource_name, &archived_url, &webpage.text(), configuration.as_deref()) {
+            warn!(url, error = %e, "Error archiving the page");
+            metrics::ERRORS.with_label_values(&["archive"]).inc();
+        }
+
+        Ok(webpage)
+    }
+}
+
+#[async_trait]
+impl<'a, H: HttpClient + Send + Sync> HttpClient for ArchivingHttpClient<'a, H>{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        self.fetch_archived(url, None).await
+    }
+
+    // The configuration is archived along with the page
+    async fn get_for(&self, url: &str, configuration: &[u8]) -> Result<String, Box<dyn Error>> {
+        Ok(self.fetch_archived(url, Some(configuration)).await?.text())
+    }
+
+    fn paused(&self) -> Option<Duration> {
+        self.http_client.paused()
+    }
+}
+
+// Turns fetches from a host away while its breaker is open, so a site that's down isn't hammered
+#[derive(Copy, Clone)]
+pub struct CircuitBreakingHttpClient<'a, H: HttpClient>{
+    http_client: H,
+    breakers: &'a CircuitBreakers,
+    datasource_name: &'static str,
+}
+
+impl<'a, H: HttpClient> CircuitBreakingHttpClient<'a, H>{
+    pub fn new(http_client: H, breakers: &'a CircuitBreakers, datasource_name: &'static str) -> CircuitBreakingHttpClient<'a, H> {
+        CircuitBreakingHttpClient{http_client: http_client, breakers: breakers, datasource_name: datasource_name}
+    }
+}
+
+#[async_trait]
+impl<'a, H: HttpClient + Send + Sync> HttpClient for CircuitBreakingHttpClient<'a, H>{
+    async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        let host = match Url::parse(url).ok().and_then(|url| url.host_str().map(|host| host.to_lowercase())) {
+            Some(host) => host,
+            None => return self.http_client.fetch(url).await,
+        };
+        let breaker = self.breakers.for_host(&host, self.datasource_name);
+        let permit = breaker.permit(Instant::now())?;
+
+        let result = self.http_client.fetch(url).await;
+        permit.finish(result.is_err(), Instant::now());
+        result
+    }
+
+    fn paused(&self) -> Option<Duration> {
+        self.breakers.paused(self.datasource_name, Instant::now())
+    }
+}
+
+#[derive(Copy, Clone)]
 pub struct TestHttpClient<'a>{
     content: &'a str,
 }
@@ -40,7 +218,7 @@ impl<'a> TestHttpClient<'a>{
 
 #[async_trait]
 impl<'a> HttpClient for TestHttpClient<'a>{
-    async fn get(&self, _url: &str) -> Result<String, Box<dyn Error>> {
-        Ok(String::from(self.content))
+    async fn fetch(&self, _url: &str) -> Result<Webpage, Box<dyn Error>> {
+        Ok(Webpage::new(self.content.as_bytes().to_vec(), Some("text/html; charset=utf-8")))
     } 
 }
\ No newline at end of file
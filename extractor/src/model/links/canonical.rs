@@ -0,0 +1,69 @@
+use {
+    std::error::Error,
+    scraper::{Html, Selector},
+    url::Url,
+};
+
+const TRACKING_PARAMETER_PREFIXES: &[&str] = &["utm_"];
+const TRACKING_PARAMETERS: &[&str] = &["fbclid", "gclid", "dclid", "mc_cid", "mc_eid", "_ga", "_hsenc", "_hsmi"];
+
+// Resolves an href against the url of the page it was found on, absolute hrefs are kept as they are
+pub fn resolve(base_url: &str, href: &str) -> Result<String, Box<dyn Error>> {
+    let url = Url::parse(base_url)?.join(href.trim())?;
+    Ok(canonical(url))
+}
+
+// The url relative hrefs on the page resolve against, pages without a <base> element use the url they were fetched from.
+// Not canonicalized, the trailing slash of "/de_DE/" decides whether hrefs resolve under it or next to it
+pub fn page_base_url(document: &Html, fetched_from: &str) -> String {
+    let selector = Selector::parse("base[href]").unwrap();
+    document.select(&selector)
+        .next()
+        .and_then(|base| base.value().attr("href"))
+        .and_then(|href| Url::parse(fetched_from).and_then(|url| url.join(href.trim())).ok())
+        .map(|url| url.into_string())
+        .unwrap_or(fetched_from.to_owned())
+}
+
+// One spelling per page: lowercase scheme and host, no default port, fragment,
+// tracking parameters or trailing slash, and the remaining query parameters sorted
+pub fn canonicalize(url: &str) -> Result<String, Box<dyn Error>> {
+    Ok(canonical(Url::parse(url.trim())?))
+}
+
+fn canonical(mut url: Url) -> String {
+    url.set_fragment(None);
+
+    let mut query: Vec<(String, String)> = url.query_pairs()
+        .filter(|(name, _)| !is_tracking_parameter(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    query.sort();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+
+    let path = url.path().trim_end_matches('/').to_owned();
+    if !path.is_empty() {
+        url.set_path(&path);
+    }
+
+    url.into_string()
+}
+
+fn is_tracking_parameter(name: &str) -> bool {
+    TRACKING_PARAMETERS.contains(&name) || TRACKING_PARAMETER_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+// The numeric id sites like bachtrack end their urls with, "/concert-event/some-title/318719" has "318719"
+pub fn numeric_id(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let last_segment = url.path_segments()?.filter(|segment| !segment.is_empty()).last()?;
+    if last_segment.chars().all(|c| c.is_ascii_digit()) {
+        Some(last_segment.to_owned())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,6 @@
+mod canonical;
+
+#[cfg(test)]
+mod tests;
+
+pub use canonical::{resolve, canonicalize, numeric_id, page_base_url};
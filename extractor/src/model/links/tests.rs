@@ -0,0 +1,56 @@
+use {
+    scraper::Html,
+    super::{resolve, canonicalize, numeric_id, page_base_url},
+};
+
+const LISTING_URL: &str = "https://bachtrack.com/concert-event/residenz-serenade-munich-residenz-solisten-die-residenz-hofkapelle-5-september-2019/318719";
+
+#[test]
+fn test_resolve() {
+    let cases = vec![
+        ("https://bachtrack.com", "/concert-event/residenz-serenade-munich-residenz-solisten-die-residenz-hofkapelle-5-september-2019/318719", LISTING_URL),
+        ("https://bachtrack.com", LISTING_URL, LISTING_URL),
+        ("https://bachtrack.com/find-concerts/", "../concert-event/x/1", "https://bachtrack.com/concert-event/x/1"),
+        ("https://bachtrack.com/find-concerts/", "//bachtrack.com/concert-event/x/1", "https://bachtrack.com/concert-event/x/1"),
+        ("https://bachtrack.com", " /concert-event/x/1 ", "https://bachtrack.com/concert-event/x/1"),
+    ];
+
+    for (base_url, href, expected) in cases {
+        assert_eq!(resolve(base_url, href).unwrap(), expected, "resolving '{}' against '{}'", href, base_url);
+    }
+    assert!(resolve("not a url", "/concert-event/x/1").is_err());
+}
+
+#[test]
+fn test_canonicalize() {
+    let cases = vec![
+        ("HTTPS://BachTrack.com:443/concert-event/x/1/", "https://bachtrack.com/concert-event/x/1"),
+        ("https://bachtrack.com/concert-event/x/1#programme", "https://bachtrack.com/concert-event/x/1"),
+        ("https://bachtrack.com/concert-event/x/1?utm_source=newsletter&utm_medium=email&fbclid=abc", "https://bachtrack.com/concert-event/x/1"),
+        ("https://bachtrack.com/search?page=2&city=vienna&gclid=abc", "https://bachtrack.com/search?city=vienna&page=2"),
+        ("https://bachtrack.com/", "https://bachtrack.com/"),
+    ];
+
+    for (url, expected) in cases {
+        assert_eq!(canonicalize(url).unwrap(), expected, "canonicalizing '{}'", url);
+    }
+}
+
+#[test]
+fn test_numeric_id() {
+    assert_eq!(numeric_id(LISTING_URL), Some("318719".to_owned()));
+    assert_eq!(numeric_id("https://bachtrack.com/concert-event/x/318719/"), Some("318719".to_owned()));
+    assert_eq!(numeric_id("https://bachtrack.com/find-concerts"), None);
+    assert_eq!(numeric_id("https://bachtrack.com/"), None);
+}
+
+#[test]
+fn test_page_base_url() {
+    let with_base = Html::parse_document(r#"<html><head><base href="/de_DE/"></head></html>"#);
+    let without_base = Html::parse_document("<html><head></head></html>");
+
+    let base_url = page_base_url(&with_base, "https://bachtrack.com/find-concerts");
+    assert_eq!(base_url, "https://bachtrack.com/de_DE/");
+    assert_eq!(resolve(&base_url, "concert-event/x/318719").unwrap(), "https://bachtrack.com/de_DE/concert-event/x/318719");
+    assert_eq!(page_base_url(&without_base, "https://bachtrack.com/find-concerts"), "https://bachtrack.com/find-concerts");
+}
@@ -2,6 +2,14 @@ pub mod errors;
 pub mod datasource;
 pub mod http_client;
 pub mod extract;
+pub mod date_parser;
+pub mod duration;
+pub mod quality;
+pub mod links;
+pub mod session;
+pub mod charset;
+pub mod circuit_breaker;
+pub mod paths;
 
 pub use extract::*;
 pub use datasource::*;
\ No newline at end of file
@@ -0,0 +1,16 @@
+use {
+    std::error::Error,
+    std::path::{Path, PathBuf},
+    super::errors::PathOutsideRootError,
+};
+
+// Resolves a path from a configuration against the directory it has to stay in, relative paths
+// are taken from there. `..` and symlinks are followed before checking, so neither leads out
+pub fn resolve_within(root: &Path, path: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let root = root.canonicalize()?;
+    let resolved = root.join(path).canonicalize()?;
+    if !resolved.starts_with(&root) {
+        return Err(Box::new(PathOutsideRootError));
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,29 @@
+use {
+    serde::{Serialize, Deserialize},
+    crate::model::{ExtractionReport, ExtractionWarning},
+};
+
+pub const DIAGNOSTICS_SUBJECT: &str = "monitoring.diagnostics";
+
+// The field level problems of one extraction, published for whoever maintains the parser
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Diagnostics {
+    pub ds_name: String,
+    pub completeness: f64,
+    pub warnings: Vec<ExtractionWarning>,
+}
+
+impl Diagnostics {
+    // Nothing to report for a clean extraction
+    pub fn new(ds_name: &str, report: &ExtractionReport) -> Option<Diagnostics> {
+        if report.warnings.is_empty() {
+            return None;
+        }
+
+        Some(Diagnostics{
+            ds_name: ds_name.to_owned(),
+            completeness: report.completeness(),
+            warnings: report.warnings.to_vec(),
+        })
+    }
+}
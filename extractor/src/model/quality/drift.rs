@@ -0,0 +1,163 @@
+use {
+    std::collections::{HashMap, HashSet, VecDeque},
+    serde::{Serialize, Deserialize},
+    super::QualityReport,
+};
+
+pub const DRIFT_ALERT_SUBJECT: &str = "monitoring.drift";
+
+const RECENT_RUNS: usize = 20;
+const BASELINE_RUNS: usize = 200;
+
+// A metric is considered drifting when it falls below half of its baseline
+const MAX_RELATIVE_DROP: f64 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MetricDrop {
+    pub metric: String,
+    pub baseline: f64,
+    pub recent: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DriftAlert {
+    pub ds_name: String,
+    pub drops: Vec<MetricDrop>,
+}
+
+// Compares the latest runs of a datasource with a rolling baseline of the runs before them.
+pub struct DriftDetector {
+    ds_name: String,
+    recent: VecDeque<QualityReport>,
+    baseline: VecDeque<QualityReport>,
+    recent_runs: usize,
+    baseline_runs: usize,
+    drifting: bool,
+}
+
+impl DriftDetector {
+    pub fn new(ds_name: &str) -> DriftDetector {
+        DriftDetector::with_windows(ds_name, RECENT_RUNS, BASELINE_RUNS)
+    }
+
+    pub fn with_windows(ds_name: &str, recent_runs: usize, baseline_runs: usize) -> DriftDetector {
+        DriftDetector{
+            ds_name: ds_name.to_owned(),
+            recent: VecDeque::with_capacity(recent_runs + 1),
+            baseline: VecDeque::with_capacity(baseline_runs + 1),
+            recent_runs: recent_runs,
+            baseline_runs: baseline_runs,
+            drifting: false,
+        }
+    }
+
+    // Records a run, returns an alert the first time the recent runs drift away from the baseline.
+    pub fn push(&mut self, report: QualityReport) -> Option<DriftAlert> {
+        self.recent.push_back(report);
+        if self.recent.len() > self.recent_runs {
+            let oldest = self.recent.pop_front().unwrap();
+
+            // Keep the baseline healthy while drifting, otherwise the drift becomes the new normal
+            if !self.drifting {
+                self.baseline.push_back(oldest);
+                if self.baseline.len() > self.baseline_runs {
+                    self.baseline.pop_front();
+                }
+            }
+        }
+
+        if self.recent.len() < self.recent_runs || self.baseline.len() < self.recent_runs {
+            return None;
+        }
+
+        let drops = find_drops(&WindowStats::new(&self.baseline), &WindowStats::new(&self.recent));
+        if drops.is_empty() {
+            self.drifting = false;
+            return None;
+        }
+
+        if self.drifting {
+            return None;
+        }
+
+        self.drifting = true;
+        Some(DriftAlert{ds_name: self.ds_name.to_owned(), drops: drops})
+    }
+}
+
+struct WindowStats {
+    // Average items per run, per type
+    items_per_type: HashMap<String, f64>,
+    // Share of items with the field populated
+    field_coverage: HashMap<String, f64>,
+    // Share of runs in which the selector matched, only for selectors that missed at least once
+    selector_matches: HashMap<String, f64>,
+}
+
+impl WindowStats {
+    fn new(reports: &VecDeque<QualityReport>) -> WindowStats {
+        let runs = reports.len() as f64;
+        let mut items_per_type: HashMap<String, f64> = HashMap::new();
+        let mut populated_fields: HashMap<String, f64> = HashMap::new();
+        let mut selector_misses: HashMap<String, f64> = HashMap::new();
+
+        for report in reports {
+            for (type_name, count) in &report.items_per_type {
+                *items_per_type.entry(type_name.to_owned()).or_insert(0.0) += *count as f64;
+            }
+            for (field, count) in &report.populated_fields {
+                *populated_fields.entry(field.to_owned()).or_insert(0.0) += *count as f64;
+            }
+            for selector in &report.unmatched_selectors {
+                *selector_misses.entry(selector.to_owned()).or_insert(0.0) += 1.0;
+            }
+        }
+
+        let field_coverage = populated_fields
+            .into_iter()
+            .filter_map(|(field, populated)| {
+                let type_name = field.split('.').next().unwrap_or("");
+                let items = *items_per_type.get(type_name)?;
+                Some((field, populated / items))
+            })
+            .collect();
+
+        WindowStats{
+            items_per_type: items_per_type.into_iter().map(|(type_name, items)| (type_name, items / runs)).collect(),
+            field_coverage: field_coverage,
+            selector_matches: selector_misses.into_iter().map(|(selector, misses)| (selector, 1.0 - misses / runs)).collect(),
+        }
+    }
+}
+
+fn find_drops(baseline: &WindowStats, recent: &WindowStats) -> Vec<MetricDrop> {
+    let mut drops = Vec::new();
+
+    for (type_name, baseline_items) in &baseline.items_per_type {
+        let recent_items = *recent.items_per_type.get(type_name).unwrap_or(&0.0);
+        push_if_dropped(&mut drops, format!("items.{}", type_name), *baseline_items, recent_items);
+    }
+
+    for (field, baseline_coverage) in &baseline.field_coverage {
+        // A type that disappeared completely is already reported by its item count
+        if let Some(recent_coverage) = recent.field_coverage.get(field) {
+            push_if_dropped(&mut drops, format!("coverage.{}", field), *baseline_coverage, *recent_coverage);
+        }
+    }
+
+    let selectors: HashSet<&String> = baseline.selector_matches.keys().chain(recent.selector_matches.keys()).collect();
+    for selector in selectors {
+        let baseline_matches = *baseline.selector_matches.get(selector).unwrap_or(&1.0);
+        let recent_matches = *recent.selector_matches.get(selector).unwrap_or(&1.0);
+        push_if_dropped(&mut drops, format!("selector.{}", selector), baseline_matches, recent_matches);
+    }
+
+    drops.sort_by(|a, b| a.metric.cmp(&b.metric));
+    drops
+}
+
+fn push_if_dropped(drops: &mut Vec<MetricDrop>, metric: String, baseline: f64, recent: f64) {
+    if baseline > 0.0 && recent < baseline * (1.0 - MAX_RELATIVE_DROP) {
+        drops.push(MetricDrop{metric: metric, baseline: baseline, recent: recent});
+    }
+}
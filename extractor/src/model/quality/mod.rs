@@ -0,0 +1,10 @@
+mod report;
+mod drift;
+mod diagnostics;
+
+#[cfg(test)]
+mod tests;
+
+pub use report::{QualityReport, unmatched_selectors};
+pub use drift::{DriftDetector, DRIFT_ALERT_SUBJECT};
+pub use diagnostics::{Diagnostics, DIAGNOSTICS_SUBJECT};
@@ -0,0 +1,65 @@
+use {
+    std::collections::HashMap,
+    serde::{Serialize, Deserialize},
+    scraper::{Html, Selector},
+    crate::model::ExtractionReport,
+};
+
+// What a single datasource run found, used to notice when a parser stops matching the page.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct QualityReport {
+    pub ds_name: String,
+    pub items_found: usize,
+    // Number of items per type, e.g. "MusicEvent" -> 25
+    pub items_per_type: HashMap<String, usize>,
+    // Number of items with a non empty value per field, e.g. "MusicEvent.description" -> 24
+    pub populated_fields: HashMap<String, usize>,
+    pub unmatched_selectors: Vec<String>,
+}
+
+impl QualityReport {
+    pub fn new(ds_name: &str, report: &ExtractionReport) -> QualityReport {
+        let mut items_per_type = HashMap::new();
+        let mut populated_fields = HashMap::new();
+
+        for item in &report.items {
+            let type_name = item.get_type_name();
+            *items_per_type.entry(type_name.to_owned()).or_insert(0) += 1;
+
+            if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(item) {
+                for (field, value) in fields {
+                    let populated = populated_fields.entry(format!("{}.{}", type_name, field)).or_insert(0);
+                    if is_populated(&value) {
+                        *populated += 1;
+                    }
+                }
+            }
+        }
+
+        QualityReport{
+            ds_name: ds_name.to_owned(),
+            items_found: report.items.len(),
+            items_per_type: items_per_type,
+            populated_fields: populated_fields,
+            unmatched_selectors: report.unmatched_selectors.to_vec(),
+        }
+    }
+}
+
+fn is_populated(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(string) => !string.trim().is_empty(),
+        serde_json::Value::Array(array) => !array.is_empty(),
+        serde_json::Value::Object(object) => !object.is_empty(),
+        _ => true,
+    }
+}
+
+pub fn unmatched_selectors(document: &Html, selectors: &[&str]) -> Vec<String> {
+    selectors
+        .iter()
+        .filter(|selector| document.select(&Selector::parse(selector).unwrap()).next().is_none())
+        .map(|selector| selector.to_string())
+        .collect()
+}
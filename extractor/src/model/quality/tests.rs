@@ -0,0 +1,132 @@
+use {
+    chrono::NaiveDate,
+    crate::model::{ExtractionReport, ExtractionWarning, Extracted, MusicEvent, EventTime, EndTimePrecision, Person, Configuration},
+    super::{QualityReport, DriftDetector, Diagnostics},
+};
+
+fn music_event(description: &str, artists: usize) -> Extracted {
+    let start_time = NaiveDate::from_ymd(2020, 10, 23).and_hms(19, 30, 0);
+    Extracted::MusicEvent(MusicEvent{
+        artists: (0..artists).map(|i| Person{name: format!("artist {}", i)}).collect(),
+        pieces: vec![],
+        description: description.to_owned(),
+        time: EventTime{
+            start_time: start_time,
+            end_time: start_time,
+            end_time_precision: EndTimePrecision::Default,
+            local_timezone: true,
+        },
+        festival_id: None,
+    })
+}
+
+fn report(items: Vec<Extracted>, unmatched_selectors: &[&str]) -> QualityReport {
+    let mut report = ExtractionReport::new(items);
+    report.unmatched_selectors = unmatched_selectors.iter().map(|selector| selector.to_string()).collect();
+    QualityReport::new("datasource.test", &report)
+}
+
+fn healthy_report() -> QualityReport {
+    report(vec![music_event("description", 2), music_event("description", 1)], &[])
+}
+
+#[test]
+fn test_quality_report() {
+    let quality_report = report(
+        vec![
+            music_event("a concert", 2), 
+            music_event("", 0), 
+            Extracted::Configuration(Configuration{ds_name: "datasource.test".to_owned(), value: "/url".to_owned(), id: None, festival_id: None}),
+        ], 
+        &["div.listing-description"],
+    );
+
+    assert_eq!(quality_report.items_found, 3);
+    assert_eq!(quality_report.items_per_type["MusicEvent"], 2);
+    assert_eq!(quality_report.items_per_type["Configuration"], 1);
+    assert_eq!(quality_report.populated_fields["MusicEvent.description"], 1);
+    assert_eq!(quality_report.populated_fields["MusicEvent.artists"], 1);
+    assert_eq!(quality_report.populated_fields["MusicEvent.pieces"], 0);
+    assert_eq!(quality_report.populated_fields["MusicEvent.time"], 2);
+    assert_eq!(quality_report.populated_fields["Configuration.value"], 1);
+    assert_eq!(quality_report.unmatched_selectors, vec!["div.listing-description".to_owned()]);
+}
+
+#[test]
+fn test_no_drift_while_stable() {
+    let mut detector = DriftDetector::with_windows("datasource.test", 5, 20);
+    for _ in 0..50 {
+        assert_eq!(detector.push(healthy_report()), None);
+    }
+}
+
+#[test]
+fn test_no_drift_without_baseline() {
+    let mut detector = DriftDetector::with_windows("datasource.test", 5, 20);
+    for _ in 0..9 {
+        assert_eq!(detector.push(report(vec![], &["table#table_li_times"])), None);
+    }
+}
+
+#[test]
+fn test_drift_alerts() {
+    let cases = vec![
+        (report(vec![], &[]), vec!["items.MusicEvent"]),
+        (report(vec![music_event("", 2), music_event("", 1)], &[]), vec!["coverage.MusicEvent.description"]),
+        (report(vec![music_event("description", 2), music_event("description", 1)], &["div.listing-description"]), vec!["selector.div.listing-description"]),
+    ];
+
+    for (drifted_report, expected_metrics) in cases {
+        let mut detector = DriftDetector::with_windows("datasource.test", 5, 20);
+        for _ in 0..20 {
+            assert_eq!(detector.push(healthy_report()), None);
+        }
+
+        let mut alerts = Vec::new();
+        for _ in 0..10 {
+            if let Some(alert) = detector.push(drifted_report.clone()) {
+                alerts.push(alert);
+            }
+        }
+
+        assert_eq!(alerts.len(), 1, "expecting a single alert for {:?}", expected_metrics);
+        assert_eq!(alerts[0].ds_name, "datasource.test");
+        assert_eq!(alerts[0].drops.iter().map(|drop| drop.metric.as_str()).collect::<Vec<_>>(), expected_metrics);
+    }
+}
+
+#[test]
+fn test_drift_recovers() {
+    let mut detector = DriftDetector::with_windows("datasource.test", 5, 20);
+    for _ in 0..20 {
+        detector.push(healthy_report());
+    }
+    let alerts = (0..10).filter_map(|_| detector.push(report(vec![], &[]))).count();
+    assert_eq!(alerts, 1);
+
+    for _ in 0..10 {
+        assert_eq!(detector.push(healthy_report()), None);
+    }
+
+    let alerts = (0..10).filter_map(|_| detector.push(report(vec![], &[]))).count();
+    assert_eq!(alerts, 1);
+}
+
+#[test]
+fn test_diagnostics() {
+    let mut report = ExtractionReport::new(vec![music_event("description", 1)]);
+    report.field_parsed();
+    assert_eq!(Diagnostics::new("datasource.test", &report), None);
+
+    report.field_failed("time", "table#table_li_times", " Date to be announced ", "unknown format");
+    assert_eq!(Diagnostics::new("datasource.test", &report), Some(Diagnostics{
+        ds_name: "datasource.test".to_owned(),
+        completeness: 0.5,
+        warnings: vec![ExtractionWarning{
+            field: "time".to_owned(),
+            selector: "table#table_li_times".to_owned(),
+            raw_text: "Date to be announced".to_owned(),
+            reason: "unknown format".to_owned(),
+        }],
+    }));
+}
@@ -0,0 +1,158 @@
+use {
+    url::Url,
+    chrono::{DateTime, NaiveDateTime, Duration, Utc},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    // Without a Domain attribute the cookie only goes back to the exact host that set it
+    pub host_only: bool,
+    pub path: String,
+    pub secure: bool,
+    pub expires: Option<NaiveDateTime>,
+}
+
+impl Cookie {
+    // None for malformed cookies and for cookies a host isn't allowed to set
+    pub fn parse(url: &Url, set_cookie: &str, now: NaiveDateTime) -> Option<Cookie> {
+        let host = url.host_str()?.to_lowercase();
+        let mut attributes = set_cookie.split(';');
+        let (name, value) = split_pair(attributes.next()?)?;
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie{
+            name: name,
+            value: value,
+            domain: host.to_owned(),
+            host_only: true,
+            path: default_path(url),
+            secure: false,
+            expires: None,
+        };
+        let mut max_age = None;
+        for attribute in attributes {
+            let (key, value) = split_pair(attribute).unwrap_or((attribute.trim().to_owned(), String::new()));
+            match key.to_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                },
+                "path" if value.starts_with('/') => cookie.path = value,
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => {
+                    cookie.expires = cookie.expires.or(parse_expires(&value));
+                },
+                "secure" => cookie.secure = true,
+                _ => {},
+            }
+        }
+        // Max-Age wins over Expires
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(now + Duration::seconds(seconds.max(0)));
+        }
+        Some(cookie)
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires.map(|expires| expires <= now).unwrap_or(false)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host.to_lowercase(),
+            None => return false,
+        };
+        let host_matches = if self.host_only { host == self.domain } else { domain_matches(&host, &self.domain) };
+        host_matches && path_matches(url.path(), &self.path) && (!self.secure || url.scheme() == "https")
+    }
+}
+
+// The cookies a session collected, sent back the way a browser would
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar{cookies: Vec::new()}
+    }
+
+    pub fn store(&mut self, url: &Url, set_cookie: &str) {
+        let now = Utc::now().naive_utc();
+        let cookie = match Cookie::parse(url, set_cookie, now) {
+            Some(cookie) => cookie,
+            None => return,
+        };
+
+        self.cookies.retain(|stored| {
+            !(stored.name == cookie.name && stored.domain == cookie.domain && stored.path == cookie.path)
+        });
+        // An expiry in the past is how servers delete a cookie
+        if !cookie.is_expired(now) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    // The Cookie header for a request to the url, most specific paths first
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let now = Utc::now().naive_utc();
+        let mut cookies: Vec<&Cookie> = self.cookies.iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(url))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+
+        cookies.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+        Some(cookies.iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<String>>()
+            .join("; "))
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+fn split_pair(text: &str) -> Option<(String, String)> {
+    let mut parts = text.splitn(2, '=');
+    let key = parts.next()?.trim().to_owned();
+    let value = parts.next()?.trim().trim_matches('"').to_owned();
+    Some((key, value))
+}
+
+// The directory of the request path, "/" for the top level
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => url.path()[..index].to_owned(),
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+fn parse_expires(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc2822(value)
+        .map(|expires| expires.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%a, %d-%b-%Y %H:%M:%S GMT"))
+        .ok()
+}
@@ -0,0 +1,7 @@
+mod cookie_jar;
+mod session;
+
+#[cfg(test)]
+mod tests;
+
+pub use session::{Session, load_configurations};
@@ -0,0 +1,214 @@
+use {
+    std::collections::{BTreeMap, HashMap},
+    std::error::Error,
+    std::sync::Mutex,
+    std::fs,
+    serde::Deserialize,
+    reqwest::{Client, Method, Response, StatusCode},
+    reqwest::header::{COOKIE, SET_COOKIE, LOCATION, CONTENT_TYPE},
+    reqwest::redirect::Policy,
+    scraper::{Html, Selector},
+    url::Url,
+    tracing::debug,
+    crate::model::errors::{MissingDataInHtmlError, TooManyRedirectsError, CrossHostRedirectError},
+    crate::model::charset::Webpage,
+    crate::model::http_client::{FETCH_TIMEOUT, CONNECT_TIMEOUT},
+    super::cookie_jar::CookieJar,
+};
+
+pub const DEFAULT_USER_AGENT: &str = concat!("extractor/", env!("CARGO_PKG_VERSION"));
+const MAX_REDIRECTS: usize = 10;
+
+// How a datasource's site expects to be browsed. Header values, urls and form values
+// may refer to captured values as {{name}}.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfiguration {
+    pub user_agent: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub warm_up: Vec<WarmUpStep>,
+}
+
+// A request made before the first page is fetched, e.g. accepting the cookie consent
+// or logging in. Steps with a form are posted.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WarmUpStep {
+    pub url: String,
+    #[serde(default)]
+    pub form: BTreeMap<String, String>,
+    #[serde(default)]
+    pub capture: Option<Capture>,
+}
+
+// Keeps an attribute of the step's page for the following requests, typically a CSRF token
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Capture {
+    pub name: String,
+    pub selector: String,
+    pub attr: String,
+}
+
+// The session configurations of every datasource, keyed by datasource name
+pub fn load_configurations(path: &str) -> Result<HashMap<String, SessionConfiguration>, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+// Cookies and captured values shared by all the fetches of a datasource
+pub struct Session {
+    client: Client,
+    configuration: SessionConfiguration,
+    cookies: Mutex<CookieJar>,
+    captured: Mutex<HashMap<String, String>>,
+    // Counts the warm-ups, 0 until the first one
+    generation: tokio::sync::Mutex<u64>,
+    same_host_redirects: bool,
+}
+
+impl Session {
+    pub fn new(configuration: SessionConfiguration) -> Result<Session, Box<dyn Error>> {
+        let client = Client::builder()
+            .user_agent(configuration.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            // Redirects are followed here, so the cookies they set aren't lost
+            .redirect(Policy::none())
+            .timeout(FETCH_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()?;
+
+        Ok(Session{
+            client: client,
+            configuration: configuration,
+            cookies: Mutex::new(CookieJar::new()),
+            captured: Mutex::new(HashMap::new()),
+            generation: tokio::sync::Mutex::new(0),
+            same_host_redirects: false,
+        })
+    }
+
+    // For fetches whose urls were checked against the hosts they may go to, e.g. the pages plugins ask for.
+    // A redirect to any other host fails the fetch
+    pub fn same_host_only(configuration: SessionConfiguration) -> Result<Session, Box<dyn Error>> {
+        Ok(Session{same_host_redirects: true, ..Session::new(configuration)?})
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<Webpage, Box<dyn Error>> {
+        let generation = self.warm_up(None).await?;
+        let response = self.send(url, None).await?;
+
+        // The site dropped the session, start a new one once
+        let response = match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if !self.configuration.warm_up.is_empty() => {
+                debug!(url, status = %response.status(), "session expired, warming up again");
+                self.warm_up(Some(generation)).await?;
+                self.send(url, None).await?
+            },
+            _ => response,
+        };
+        into_webpage(response).await
+    }
+
+    // Warms the session up unless it already is. With the generation an expired fetch was sent with,
+    // starts a new session unless another fetch already did since. Returns the generation to fetch with
+    async fn warm_up(&self, expired: Option<u64>) -> Result<u64, Box<dyn Error>> {
+        // Concurrent fetches wait for the one warm-up instead of each running their own
+        let mut generation = self.generation.lock().await;
+        match expired {
+            None if *generation > 0 => return Ok(*generation),
+            Some(expired) if expired != *generation => return Ok(*generation),
+            None => {},
+            Some(_) => {
+                self.cookies.lock().unwrap().clear();
+                self.captured.lock().unwrap().clear();
+            },
+        }
+
+        for step in &self.configuration.warm_up {
+            let form: BTreeMap<String, String> = step.form.iter()
+                .map(|(key, value)| (key.to_owned(), self.fill(value)))
+                .collect();
+            let form = if form.is_empty() { None } else { Some(&form) };
+            let response = self.send(&self.fill(&step.url), form).await?;
+            let page = into_webpage(response).await?.text();
+
+            if let Some(capture) = &step.capture {
+                let value = select_attr(&page, &capture.selector, &capture.attr).ok_or(MissingDataInHtmlError)?;
+                self.captured.lock().unwrap().insert(capture.name.to_owned(), value);
+            }
+        }
+        *generation += 1;
+        Ok(*generation)
+    }
+
+    async fn send(&self, url: &str, form: Option<&BTreeMap<String, String>>) -> Result<Response, Box<dyn Error>> {
+        let mut url = Url::parse(url)?;
+        let mut form = form;
+
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request = match form {
+                Some(form) => self.client.request(Method::POST, url.as_str()).form(form),
+                None => self.client.request(Method::GET, url.as_str()),
+            };
+            for (name, value) in &self.configuration.headers {
+                request = request.header(name.as_str(), self.fill(value));
+            }
+            if let Some(cookies) = self.cookies.lock().unwrap().header(&url) {
+                request = request.header(COOKIE, cookies);
+            }
+
+            let response = request.send().await?;
+            {
+                let mut cookies = self.cookies.lock().unwrap();
+                for set_cookie in response.headers().get_all(SET_COOKIE) {
+                    if let Ok(set_cookie) = set_cookie.to_str() {
+                        cookies.store(&url, set_cookie);
+                    }
+                }
+            }
+
+            let location = match response.headers().get(LOCATION) {
+                Some(location) if response.status().is_redirection() => location.to_str()?.to_owned(),
+                _ => return Ok(response),
+            };
+            let redirected = url.join(&location)?;
+            if self.same_host_redirects && redirected.host_str() != url.host_str() {
+                return Err(Box::new(CrossHostRedirectError));
+            }
+            url = redirected;
+            // Like browsers, a redirected post is followed with a get, except for 307 and 308
+            if response.status() != StatusCode::TEMPORARY_REDIRECT && response.status() != StatusCode::PERMANENT_REDIRECT {
+                form = None;
+            }
+        }
+        Err(Box::new(TooManyRedirectsError))
+    }
+
+    fn fill(&self, template: &str) -> String {
+        let captured = self.captured.lock().unwrap();
+        captured.iter().fold(template.to_owned(), |filled, (name, value)| {
+            filled.replace(&format!("{{{{{}}}}}", name), value)
+        })
+    }
+}
+
+async fn into_webpage(response: Response) -> Result<Webpage, Box<dyn Error>> {
+    // An error page is nothing to extract from, and a sign the site is struggling
+    if response.status().is_server_error() {
+        response.error_for_status_ref()?;
+    }
+    let content_type = response.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_owned());
+    let bytes = response.bytes().await?;
+    Ok(Webpage::new(bytes.to_vec(), content_type.as_deref()))
+}
+
+fn select_attr(page: &str, selector: &str, attr: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    Html::parse_document(page)
+        .select(&selector)
+        .next()
+        .and_then(|element| element.value().attr(attr))
+        .map(|value| value.to_owned())
+}
@@ -0,0 +1,193 @@
+use {
+    std::convert::Infallible,
+    std::net::SocketAddr,
+    std::collections::BTreeMap,
+    std::sync::atomic::{AtomicUsize, Ordering},
+    chrono::{NaiveDate, Utc},
+    url::Url,
+    hyper::{Body, Request, Response, Server, StatusCode},
+    hyper::header::{COOKIE, SET_COOKIE, LOCATION, USER_AGENT, HOST},
+    hyper::service::{make_service_fn, service_fn},
+    super::cookie_jar::{Cookie, CookieJar},
+    super::session::{Session, SessionConfiguration, WarmUpStep, Capture, DEFAULT_USER_AGENT},
+};
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+#[test]
+fn test_parse_cookie() {
+    let now = NaiveDate::from_ymd(2021, 3, 1).and_hms(12, 0, 0);
+
+    let cookie = Cookie::parse(
+        &url("https://www.ticketing.example/events/list"),
+        "SESSIONID=\"a1b2\"; Domain=.ticketing.example; Path=/; Max-Age=3600; Secure; HttpOnly",
+        now,
+    );
+    assert_eq!(cookie, Some(Cookie{
+        name: "SESSIONID".to_owned(),
+        value: "a1b2".to_owned(),
+        domain: "ticketing.example".to_owned(),
+        host_only: false,
+        path: "/".to_owned(),
+        secure: true,
+        expires: Some(NaiveDate::from_ymd(2021, 3, 1).and_hms(13, 0, 0)),
+    }));
+
+    let cookie = Cookie::parse(&url("https://www.ticketing.example/events/list"), "consent=yes; Expires=Wed, 21 Oct 2015 07:28:00 GMT", now).unwrap();
+    assert_eq!((cookie.domain.as_str(), cookie.host_only, cookie.path.as_str()), ("www.ticketing.example", true, "/events"));
+    assert!(cookie.is_expired(now));
+
+    // Another site's cookies and cookies without a name are dropped
+    assert_eq!(Cookie::parse(&url("https://www.ticketing.example/"), "tracker=1; Domain=ads.example", now), None);
+    assert_eq!(Cookie::parse(&url("https://www.ticketing.example/"), "=1", now), None);
+}
+
+#[test]
+fn test_cookie_jar() {
+    let mut jar = CookieJar::new();
+    jar.store(&url("https://www.ticketing.example/"), "session=abc");
+    jar.store(&url("https://www.ticketing.example/"), "region=eu; Domain=ticketing.example");
+    jar.store(&url("https://www.ticketing.example/account/login"), "auth=1; Path=/events; Secure");
+    jar.store(&url("https://www.ticketing.example/"), "gone=1; Max-Age=0");
+
+    assert_eq!(jar.header(&url("https://www.ticketing.example/events/1")), Some("auth=1; session=abc; region=eu".to_owned()));
+    assert_eq!(jar.header(&url("http://www.ticketing.example/events/1")), Some("session=abc; region=eu".to_owned()));
+    assert_eq!(jar.header(&url("https://shop.ticketing.example/eventsarchive")), Some("region=eu".to_owned()));
+    assert_eq!(jar.header(&url("https://www.venue.example/")), None);
+
+    // A cookie set again replaces the previous value, an expired one deletes it
+    jar.store(&url("https://www.ticketing.example/"), "session=def");
+    jar.store(&url("https://www.ticketing.example/"), &format!("region=; Domain=ticketing.example; Expires={}", Utc::now().to_rfc2822()));
+    assert_eq!(jar.header(&url("https://www.ticketing.example/")), Some("session=def".to_owned()));
+}
+
+#[test]
+fn test_session_configuration() {
+    let configuration: SessionConfiguration = serde_json::from_str(r#"{
+        "user_agent": "Mozilla/5.0",
+        "warm_up": [
+            {"url": "https://www.ticketing.example/login", "capture": {"name": "csrf", "selector": "input[name='csrf']", "attr": "value"}},
+            {"url": "https://www.ticketing.example/login", "form": {"csrf": "{{csrf}}"}}
+        ]
+    }"#).unwrap();
+
+    assert_eq!(configuration.user_agent, Some("Mozilla/5.0".to_owned()));
+    assert_eq!(configuration.headers, BTreeMap::new());
+    assert_eq!(configuration.warm_up.len(), 2);
+    assert!(serde_json::from_str::<SessionConfiguration>(r#"{"cookies": {}}"#).is_err());
+}
+
+// Sessions started on /start, the first one expires
+static STARTED: AtomicUsize = AtomicUsize::new(0);
+
+// A site that wants the cookie consent accepted and a login with its CSRF token before showing events
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let cookies = request.headers().get(COOKIE).map(|value| value.to_str().unwrap().to_owned()).unwrap_or_default();
+    let mut response = Response::new(Body::empty());
+
+    match request.uri().path() {
+        "/consent" => {
+            *response.status_mut() = StatusCode::FOUND;
+            response.headers_mut().insert(SET_COOKIE, "consent=yes; Path=/".parse().unwrap());
+            response.headers_mut().insert(LOCATION, "/login".parse().unwrap());
+        },
+        "/login" if request.method() == hyper::Method::GET => {
+            response.headers_mut().insert(SET_COOKIE, "session=abc; Path=/; HttpOnly".parse().unwrap());
+            *response.body_mut() = Body::from("<form><input name='csrf' value='t0k3n'></form>");
+        },
+        "/login" => {
+            let form = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            if form.as_ref() == b"csrf=t0k3n" && cookies.contains("session=abc") {
+                response.headers_mut().insert(SET_COOKIE, "auth=1; Path=/events".parse().unwrap());
+            }
+            *response.status_mut() = StatusCode::SEE_OTHER;
+            response.headers_mut().insert(LOCATION, "/".parse().unwrap());
+        },
+        // The same server under another name
+        "/elsewhere" => {
+            let host = request.headers().get(HOST).unwrap().to_str().unwrap().replace("127.0.0.1", "localhost");
+            *response.status_mut() = StatusCode::FOUND;
+            response.headers_mut().insert(LOCATION, format!("http://{}/events/1", host).parse().unwrap());
+        },
+        "/start" => {
+            let visit = STARTED.fetch_add(1, Ordering::SeqCst) + 1;
+            response.headers_mut().insert(SET_COOKIE, format!("visit={}; Path=/", visit).parse().unwrap());
+        },
+        "/expiring" if cookies.contains("visit=1") => *response.status_mut() = StatusCode::UNAUTHORIZED,
+        "/expiring" => *response.body_mut() = Body::from(cookies),
+        path if path.starts_with("/events") => {
+            let csrf = request.headers().get("x-csrf-token").map(|value| value.to_str().unwrap()).unwrap_or_default();
+            let user_agent = request.headers().get(USER_AGENT).map(|value| value.to_str().unwrap()).unwrap_or_default();
+            *response.body_mut() = Body::from(format!("{}|{}|{}", cookies, csrf, user_agent));
+        },
+        _ => {},
+    }
+    Ok(response)
+}
+
+fn serve() -> SocketAddr {
+    let make_service = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(handle))
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+#[tokio::test]
+async fn test_session_warm_up() {
+    let addr = serve();
+    let mut headers = BTreeMap::new();
+    headers.insert("X-CSRF-Token".to_owned(), "{{csrf}}".to_owned());
+    let mut form = BTreeMap::new();
+    form.insert("csrf".to_owned(), "{{csrf}}".to_owned());
+
+    let session = Session::new(SessionConfiguration{
+        user_agent: None,
+        headers: headers,
+        warm_up: vec![
+            WarmUpStep{
+                url: format!("http://{}/consent", addr),
+                form: BTreeMap::new(),
+                capture: Some(Capture{name: "csrf".to_owned(), selector: "input[name='csrf']".to_owned(), attr: "value".to_owned()}),
+            },
+            WarmUpStep{url: format!("http://{}/login", addr), form: form, capture: None},
+        ],
+    }).unwrap();
+
+    let page = session.fetch(&format!("http://{}/events/1", addr)).await.unwrap().text();
+    assert_eq!(page, format!("auth=1; consent=yes; session=abc|t0k3n|{}", DEFAULT_USER_AGENT));
+}
+
+#[tokio::test]
+async fn test_expired_session_restarts_once() {
+    let addr = serve();
+    let session = Session::new(SessionConfiguration{
+        warm_up: vec![WarmUpStep{url: format!("http://{}/start", addr), form: BTreeMap::new(), capture: None}],
+        ..SessionConfiguration::default()
+    }).unwrap();
+
+    // Every fetch finds the first session expired, only the first of them starts a new one
+    let url = format!("http://{}/expiring", addr);
+    let pages = futures::future::join_all((0..5).map(|_| session.fetch(&url))).await;
+    for page in pages {
+        assert_eq!(page.unwrap().text(), "visit=2");
+    }
+    assert_eq!(STARTED.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_same_host_redirects() {
+    let addr = serve();
+    let session = Session::same_host_only(SessionConfiguration::default()).unwrap();
+
+    assert_eq!(session.fetch(&format!("http://{}/consent", addr)).await.unwrap().text(), "<form><input name='csrf' value='t0k3n'></form>");
+    let error = session.fetch(&format!("http://{}/elsewhere", addr)).await.err().unwrap();
+    assert_eq!(error.to_string(), "The request was redirected to another host, which the session doesn't follow");
+
+    let page = Session::new(SessionConfiguration::default()).unwrap().fetch(&format!("http://{}/elsewhere", addr)).await.unwrap().text();
+    assert!(page.ends_with(DEFAULT_USER_AGENT));
+}
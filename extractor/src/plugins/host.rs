@@ -0,0 +1,165 @@
+use {
+    std::error::Error,
+    std::time::Duration,
+    std::sync::{Arc, RwLock},
+    serde::Deserialize,
+    async_trait::async_trait,
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted},
+    crate::model::http_client::HttpClient,
+    crate::model::errors::{PluginError, PluginFetchNotAllowedError, PluginUnloadedError, InvalidPluginNameError, PluginConfigurationNotAllowedError},
+    crate::model::charset,
+    tracing::debug,
+    super::runtime::PluginRuntime,
+    super::manifest::PluginManifest,
+};
+
+pub struct LoadedPlugin {
+    pub runtime: Box<dyn PluginRuntime>,
+    pub manifest: PluginManifest,
+}
+
+impl LoadedPlugin {
+    fn call(&self, export: &str, input: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let output = self.runtime.call(export, input)?;
+        if output.len() > self.manifest.max_output_bytes {
+            return Err(Box::new(PluginError));
+        }
+        Ok(output)
+    }
+}
+
+// Plugin code runs on the blocking pool, a module using up its fuel doesn't hold up the other extractions
+async fn call_blocking(plugin: &Arc<LoadedPlugin>, export: &'static str, input: String) -> Result<String, Box<dyn Error>> {
+    let plugin = Arc::clone(plugin);
+    match tokio::task::spawn_blocking(move || plugin.call(export, &input)).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(e as Box<dyn Error>),
+        // The call panicked
+        Err(_) => Err(Box::new(PluginError)),
+    }
+}
+
+// The name ends up in the datasource's subject and durable names, like a queue group.
+// Wildcards or dots would let a plugin consume other datasources' messages
+pub fn valid_plugin_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+// A plugin datasource for the life of the process, its module is swapped underneath on reload
+pub struct PluginSlot {
+    pub ds_name: String,
+    plugin: RwLock<Option<Arc<LoadedPlugin>>>,
+}
+
+impl PluginSlot {
+    pub fn new(name: &str) -> Result<PluginSlot, Box<dyn Error>> {
+        if !valid_plugin_name(name) {
+            return Err(Box::new(InvalidPluginNameError));
+        }
+        Ok(PluginSlot{ds_name: format!("datasource.plugin_{}", name), plugin: RwLock::new(None)})
+    }
+
+    pub fn replace(&self, plugin: Option<LoadedPlugin>) {
+        *self.plugin.write().unwrap() = plugin.map(Arc::new);
+    }
+
+    // Extractions in progress keep the version they started with
+    pub fn current(&self) -> Option<Arc<LoadedPlugin>> {
+        self.plugin.read().unwrap().clone()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PluginItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    item: serde_json::Value,
+}
+
+#[derive(Copy, Clone)]
+pub struct DS<H: HttpClient>{
+    slot: &'static PluginSlot,
+    pub http_client: H,
+}
+
+impl<H: HttpClient> DS<H>{
+    pub fn new(slot: &'static PluginSlot, http_client: H) -> DS<H>{
+        DS{slot: slot, http_client: http_client}
+    }
+}
+
+#[async_trait]
+impl<H: HttpClient + Send + Sync> Datasource for DS<H>{
+    async fn extract(&self, configuration: &Vec<u8>) -> ExtractResult{
+        let plugin = self.slot.current().ok_or(PluginUnloadedError)?;
+        let input = serde_json::json!({"configuration": charset::decode_text(&configuration)}).to_string();
+        debug!(configuration = %input, "extracting");
+
+        // The plugin only names the pages, fetching them stays with the host.
+        // Its session doesn't follow redirects to other hosts, see main's datasource_sessions
+        let urls: Vec<String> = serde_json::from_str(&call_blocking(&plugin, "urls", input).await?)?;
+        if urls.len() > plugin.manifest.max_fetches || urls.iter().any(|url| !plugin.manifest.allows(url)) {
+            return Err(Box::new(PluginFetchNotAllowedError));
+        }
+
+        let mut report = ExtractionReport::new(Vec::new());
+        for url in urls {
+            let webpage = self.http_client.get(&url).await?;
+            let output = call_blocking(&plugin, "parse", parse_input(&url, &webpage)).await?;
+            let ExtractionReport{items, warnings, parsed_fields, ..} = parse_output(&output, &self.slot.ds_name)?;
+            report.warnings.extend(warnings);
+            report.parsed_fields += parsed_fields;
+            for item in items {
+                if !report.items.contains(&item) {
+                    report.items.push(item);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    // Only used reprocessing the archive, where nothing else waits on the thread
+    fn parse(&self, webpage: &str) -> ExtractResult{
+        let plugin = self.slot.current().ok_or(PluginUnloadedError)?;
+        let output = plugin.call("parse", &parse_input("", webpage)).map_err(|e| e as Box<dyn Error>)?;
+        parse_output(&output, &self.slot.ds_name)
+    }
+
+    fn get_name(&self) -> String{
+        self.slot.ds_name.to_owned()
+    }
+
+    fn paused(&self) -> Option<Duration>{
+        self.http_client.paused()
+    }
+}
+
+fn parse_input(url: &str, webpage: &str) -> String {
+    serde_json::json!({"url": url, "webpage": webpage}).to_string()
+}
+
+// Items the plugin got wrong are reported, the others are still published
+fn parse_output(output: &str, ds_name: &str) -> ExtractResult {
+    let output: Vec<serde_json::Value> = serde_json::from_str(output)?;
+
+    let mut report = ExtractionReport::new(Vec::new());
+    for value in output {
+        let raw = value.to_string();
+        let item = match serde_json::from_value::<PluginItem>(value) {
+            Ok(item) => Extracted::from_type_name(&item.item_type, item.item),
+            Err(e) => Err(e.into()),
+        };
+        match item {
+            // Other datasources fetch whatever url they're given, or read from disk
+            Ok(Extracted::Configuration(configuration)) if configuration.ds_name != ds_name => {
+                report.field_failed("item", ds_name, &raw, PluginConfigurationNotAllowedError);
+            },
+            Ok(item) => {
+                report.field_parsed();
+                report.items.push(item);
+            },
+            Err(e) => report.field_failed("item", ds_name, &raw, e),
+        }
+    }
+    Ok(report)
+}
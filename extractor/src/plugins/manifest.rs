@@ -0,0 +1,54 @@
+use {
+    std::error::Error,
+    std::fs,
+    std::path::Path,
+    serde::Deserialize,
+    url::Url,
+};
+
+// Limits and permissions of a plugin, from the <name>.json next to its module
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginManifest {
+    // Hosts the plugin may have pages fetched from, their subdomains included. None by default.
+    pub allowed_hosts: Vec<String>,
+    // Pages fetched per extraction
+    pub max_fetches: usize,
+    // Instructions a single call may execute
+    pub fuel: u64,
+    // The most memory the module may declare, in 64KiB wasm pages
+    pub max_memory_pages: u32,
+    pub max_output_bytes: usize,
+}
+
+impl Default for PluginManifest {
+    fn default() -> PluginManifest {
+        PluginManifest{
+            allowed_hosts: Vec::new(),
+            max_fetches: 20,
+            fuel: 1_000_000_000,
+            max_memory_pages: 512,
+            max_output_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+impl PluginManifest {
+    // Plugins without a manifest get the defaults
+    pub fn load(path: &Path) -> Result<PluginManifest, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(PluginManifest::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn allows(&self, url: &str) -> bool {
+        let host = match Url::parse(url).ok().and_then(|url| url.host_str().map(|host| host.to_lowercase())) {
+            Some(host) => host,
+            None => return false,
+        };
+        self.allowed_hosts.iter()
+            .map(|allowed| allowed.to_lowercase())
+            .any(|allowed| host == allowed || host.ends_with(&format!(".{}", allowed)))
+    }
+}
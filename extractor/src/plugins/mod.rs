@@ -0,0 +1,17 @@
+// Datasources compiled to WebAssembly and loaded from PLUGIN_DIR at runtime. A plugin <name>.wasm
+// (with optional limits in <name>.json) consumes datasource.plugin_<name>, and exports:
+//   memory, alloc(len: i32) -> i32
+//   urls(ptr: i32, len: i32) -> i64: {"configuration": "..."} to the urls to fetch, ["https://..."]
+//   parse(ptr: i32, len: i32) -> i64: {"url": "...", "webpage": "..."} to [{"type": "MusicEvent", "item": {...}}]
+// Inputs and outputs are utf-8 JSON in the module's memory, results pack the pointer in the high
+// 32 bits and the length in the low ones. Modules get no imports, pages are fetched by the host.
+// Configurations a plugin yields can only go back to its own datasource.
+mod manifest;
+mod runtime;
+mod host;
+mod registry;
+
+#[cfg(test)]
+mod tests;
+
+pub use registry::{run, DEFAULT_PLUGIN_DIR, SESSION_NAME};
@@ -0,0 +1,181 @@
+use {
+    std::io,
+    std::fs,
+    std::sync::Arc,
+    std::time::{Duration, SystemTime},
+    std::collections::HashMap,
+    std::path::{Path, PathBuf},
+    futures::stream::{FuturesUnordered, StreamExt},
+    tracing::{info, warn, error, debug},
+    crate::bus::MessageBus,
+    crate::model::http_client::HttpClient,
+    crate::metrics,
+    crate::routing::RoutingTable,
+    common::Shutdown,
+    crate::worker,
+    super::host::{DS, PluginSlot, LoadedPlugin, valid_plugin_name},
+    super::manifest::PluginManifest,
+    super::runtime::{self, PluginLoader},
+};
+
+pub const DEFAULT_PLUGIN_DIR: &str = "plugins";
+// Plugins fetch through one shared session, configured under this name
+pub const SESSION_NAME: &str = "datasource.plugins";
+const MODULE_EXTENSION: &str = "wasm";
+const MANIFEST_EXTENSION: &str = "json";
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+struct Registered {
+    slot: &'static PluginSlot,
+    // Of the module or its manifest, whichever changed last
+    modified: SystemTime,
+}
+
+// The plugins in a directory, kept in sync with it by scanning
+pub struct PluginRegistry {
+    dir: PathBuf,
+    loader: PluginLoader,
+    plugins: HashMap<String, Registered>,
+    // When the files were last changed, for plugins that failed to load since
+    failed: HashMap<String, SystemTime>,
+}
+
+impl PluginRegistry {
+    pub fn new<P: AsRef<Path>>(dir: P, loader: PluginLoader) -> PluginRegistry {
+        PluginRegistry{dir: dir.as_ref().to_path_buf(), loader: loader, plugins: HashMap::new(), failed: HashMap::new()}
+    }
+
+    // Loads new and changed plugins and unloads removed ones. Returns the plugins loaded for the first time,
+    // they need a worker. A plugin that fails to load keeps running its previous version.
+    pub fn scan(&mut self) -> Vec<&'static PluginSlot> {
+        let found = match self.modules() {
+            Ok(found) => found,
+            Err(e) => {
+                debug!(dir = ?self.dir, error = %e, "No plugins to load");
+                Vec::new()
+            }
+        };
+
+        for (name, registered) in self.plugins.iter() {
+            if !found.iter().any(|(found_name, _)| found_name == name) && registered.slot.current().is_some() {
+                info!(plugin = %name, "unloading plugin");
+                registered.slot.replace(None);
+            }
+        }
+
+        let mut new_slots = Vec::new();
+        for (name, modified) in found {
+            let unchanged = self.plugins.get(&name)
+                .map(|registered| registered.modified == modified && registered.slot.current().is_some())
+                .unwrap_or(false);
+            // Not retried until the files change again
+            if unchanged || self.failed.get(&name) == Some(&modified) {
+                continue;
+            }
+
+            let plugin = match self.load(&name) {
+                Ok(plugin) => plugin,
+                Err(e) => {
+                    error!(plugin = %name, error = %e, "Error loading plugin");
+                    metrics::ERRORS.with_label_values(&["plugin"]).inc();
+                    self.failed.insert(name, modified);
+                    continue;
+                }
+            };
+            self.failed.remove(&name);
+
+            info!(plugin = %name, "loading plugin");
+            match self.plugins.get_mut(&name) {
+                Some(registered) => {
+                    registered.slot.replace(Some(plugin));
+                    registered.modified = modified;
+                },
+                None => {
+                    let slot = match PluginSlot::new(&name) {
+                        Ok(slot) => slot,
+                        Err(e) => {
+                            error!(plugin = %name, error = %e, "Error loading plugin");
+                            continue;
+                        }
+                    };
+                    // Workers refer to the slot for as long as the process runs
+                    let slot: &'static PluginSlot = Box::leak(Box::new(slot));
+                    slot.replace(Some(plugin));
+                    self.plugins.insert(name, Registered{slot: slot, modified: modified});
+                    new_slots.push(slot);
+                },
+            }
+        }
+        new_slots
+    }
+
+    fn load(&self, name: &str) -> Result<LoadedPlugin, Box<dyn std::error::Error>> {
+        let manifest = PluginManifest::load(&self.dir.join(name).with_extension(MANIFEST_EXTENSION))?;
+        let module = fs::read(self.dir.join(name).with_extension(MODULE_EXTENSION))?;
+        Ok(LoadedPlugin{runtime: (self.loader)(&module, &manifest)?, manifest: manifest})
+    }
+
+    // The plugin names in the directory, with when they last changed
+    fn modules(&self) -> io::Result<Vec<(String, SystemTime)>> {
+        let mut modules = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(MODULE_EXTENSION) {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) if valid_plugin_name(name) => name.to_owned(),
+                _ => {
+                    debug!(?path, "Skipping a module without a valid plugin name");
+                    continue;
+                },
+            };
+
+            let mut modified = fs::metadata(&path)?.modified()?;
+            if let Ok(manifest) = fs::metadata(path.with_extension(MANIFEST_EXTENSION)) {
+                modified = modified.max(manifest.modified()?);
+            }
+            modules.push((name, modified));
+        }
+        Ok(modules)
+    }
+}
+
+// Runs a worker per plugin in the directory, picking up new and changed plugins until shutdown.
+// A worker that stops before then, e.g. because it couldn't subscribe, is started again
+pub async fn run<H: HttpClient + Copy + Send + Sync, B: MessageBus + 'static>(dir: &str, http_client: H, bus: Arc<B>, routes: &RoutingTable, queue_group: &str, shutdown: Shutdown){
+    let mut registry = PluginRegistry::new(dir, runtime::load_wasm);
+    let mut workers = FuturesUnordered::new();
+    let stopped = shutdown.clone().wait();
+    tokio::pin!(stopped);
+
+    let start = |slot: &'static PluginSlot, delay: Duration| {
+        let bus = Arc::clone(&bus);
+        let shutdown = shutdown.clone();
+        async move {
+            tokio::select! {
+                _ = tokio::time::delay_for(delay) => {},
+                _ = shutdown.clone().wait() => return slot,
+            }
+            worker::run(DS::new(slot, http_client), bus, routes, queue_group, shutdown).await;
+            slot
+        }
+    };
+
+    loop {
+        for slot in registry.scan() {
+            workers.push(start(slot, Duration::from_secs(0)));
+        }
+
+        tokio::select! {
+            _ = tokio::time::delay_for(RELOAD_INTERVAL) => {},
+            Some(slot) = workers.next(), if !workers.is_empty() => {
+                warn!(plugin = %slot.ds_name, retry_in = ?RELOAD_INTERVAL, "plugin worker stopped, restarting it");
+                workers.push(start(slot, RELOAD_INTERVAL));
+            },
+            _ = &mut stopped => break,
+        }
+    }
+    // Workers drain their in-flight messages on the same shutdown
+    while workers.next().await.is_some() {}
+}
@@ -0,0 +1,89 @@
+use {
+    std::error::Error,
+    super::manifest::PluginManifest,
+};
+
+// A loaded plugin module. Every call runs on a fresh instance, nothing is kept from one call to the next.
+pub trait PluginRuntime: Send + Sync {
+    // Calls one of the module's exports with a JSON input and returns its JSON output.
+    // Blocks until the module returns, the errors are sent back from the blocking pool
+    fn call(&self, export: &str, input: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
+}
+
+pub type PluginLoader = fn(&[u8], &PluginManifest) -> Result<Box<dyn PluginRuntime>, Box<dyn Error>>;
+
+#[cfg(feature = "plugins")]
+pub fn load_wasm(module: &[u8], manifest: &PluginManifest) -> Result<Box<dyn PluginRuntime>, Box<dyn Error>> {
+    Ok(Box::new(wasm::WasmPlugin::load(module, manifest)?))
+}
+
+#[cfg(not(feature = "plugins"))]
+pub fn load_wasm(_module: &[u8], _manifest: &PluginManifest) -> Result<Box<dyn PluginRuntime>, Box<dyn Error>> {
+    Err(Box::new(crate::model::errors::PluginsUnsupportedError))
+}
+
+#[cfg(feature = "plugins")]
+mod wasm {
+    use {
+        std::error::Error,
+        wasmtime::{Config, Engine, Module, Store, Instance, ExternType},
+        crate::model::errors::PluginError,
+        super::{PluginRuntime, PluginManifest},
+    };
+
+    pub struct WasmPlugin {
+        engine: Engine,
+        module: Module,
+        fuel: u64,
+        max_output_bytes: usize,
+    }
+
+    impl WasmPlugin {
+        pub fn load(bytes: &[u8], manifest: &PluginManifest) -> Result<WasmPlugin, Box<dyn Error>> {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config)?;
+            let module = Module::new(&engine, bytes)?;
+
+            // Sandboxed: nothing to call into, and a bounded memory
+            if module.imports().len() > 0 {
+                return Err(Box::new(PluginError));
+            }
+            for export in module.exports() {
+                if let ExternType::Memory(memory) = export.ty() {
+                    match memory.limits().max() {
+                        Some(max) if max <= manifest.max_memory_pages => {},
+                        _ => return Err(Box::new(PluginError)),
+                    }
+                }
+            }
+
+            Ok(WasmPlugin{engine: engine, module: module, fuel: manifest.fuel, max_output_bytes: manifest.max_output_bytes})
+        }
+    }
+
+    impl PluginRuntime for WasmPlugin {
+        fn call(&self, export: &str, input: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+            let store = Store::new(&self.engine);
+            store.add_fuel(self.fuel)?;
+            let instance = Instance::new(&store, &self.module, &[])?;
+
+            let memory = instance.get_memory("memory").ok_or(PluginError)?;
+            let alloc = instance.get_typed_func::<i32, i32>("alloc")?;
+            let function = instance.get_typed_func::<(i32, i32), i64>(export)?;
+
+            let input_ptr = alloc.call(input.len() as i32)?;
+            memory.write(input_ptr as usize, input.as_bytes()).map_err(|_| PluginError)?;
+            let packed = function.call((input_ptr, input.len() as i32))?;
+
+            let output_ptr = (packed as u64 >> 32) as usize;
+            let output_len = (packed as u64 & 0xffff_ffff) as usize;
+            if output_len > self.max_output_bytes {
+                return Err(Box::new(PluginError));
+            }
+            let mut output = vec![0; output_len];
+            memory.read(output_ptr, &mut output).map_err(|_| PluginError)?;
+            Ok(String::from_utf8(output)?)
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use {
+    std::error::Error,
+    std::fs,
+    std::path::{Path, PathBuf},
+    std::time::{Duration, SystemTime},
+    std::sync::atomic::{AtomicUsize, Ordering},
+    tokio_test,
+    uuid::Uuid,
+    crate::model::{Datasource, Extracted, Person, Configuration},
+    crate::model::http_client::TestHttpClient,
+    crate::model::errors::PluginError,
+    super::manifest::PluginManifest,
+    super::runtime::PluginRuntime,
+    super::host::{DS, LoadedPlugin, PluginSlot},
+    super::registry::PluginRegistry,
+};
+
+// Stands in for a wasm module: the "module" is the JSON each export answers with
+struct FakePlugin {
+    outputs: serde_json::Value,
+}
+
+impl PluginRuntime for FakePlugin {
+    fn call(&self, export: &str, _input: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        Ok(self.outputs[export].to_string())
+    }
+}
+
+fn fake_loader(module: &[u8], _manifest: &PluginManifest) -> Result<Box<dyn PluginRuntime>, Box<dyn Error>> {
+    match serde_json::from_slice(module) {
+        Ok(outputs) => Ok(Box::new(FakePlugin{outputs: outputs})),
+        Err(_) => Err(Box::new(PluginError)),
+    }
+}
+
+fn plugin_slot(outputs: serde_json::Value, manifest: PluginManifest) -> &'static PluginSlot {
+    let slot: &'static PluginSlot = Box::leak(Box::new(PluginSlot::new("test").unwrap()));
+    slot.replace(Some(LoadedPlugin{runtime: Box::new(FakePlugin{outputs: outputs}), manifest: manifest}));
+    slot
+}
+
+fn allowing(host: &str) -> PluginManifest {
+    PluginManifest{allowed_hosts: vec![host.to_owned()], ..PluginManifest::default()}
+}
+
+fn person(name: &str) -> serde_json::Value {
+    serde_json::json!({"type": "Person", "item": {"name": name}})
+}
+
+#[test]
+fn test_manifest() {
+    let manifest = allowing("Example.org");
+
+    assert!(manifest.allows("https://example.org/concerts"));
+    assert!(manifest.allows("https://www.example.org/concerts"));
+    assert!(!manifest.allows("https://notexample.org/concerts"));
+    assert!(!manifest.allows("https://example.com/"));
+    assert!(!manifest.allows("not a url"));
+
+    assert_eq!(PluginManifest::load(Path::new("/nonexistent/plugin.json")).unwrap(), PluginManifest::default());
+    assert!(!PluginManifest::default().allows("https://example.org/"));
+}
+
+#[test]
+fn test_extract() -> Result<(), Box<dyn Error>> {
+    let slot = plugin_slot(serde_json::json!({
+        "urls": ["https://example.org/1", "https://example.org/2"],
+        "parse": [person("Mozart, Wolfgang Amadeus"), {"type": "Venue", "item": {"name": "Hall"}}, {"item": {}}],
+    }), allowing("example.org"));
+    let datasource = DS::new(slot, TestHttpClient::new("<html></html>"));
+
+    let report = tokio_test::block_on(datasource.extract(&b"configuration".to_vec()))?;
+
+    assert_eq!(datasource.get_name(), "datasource.plugin_test");
+    // Both pages yield the same person
+    assert_eq!(report.items, vec![Extracted::Person(Person{name: "Mozart, Wolfgang Amadeus".to_owned()})]);
+    assert_eq!(report.warnings.len(), 4);
+    assert_eq!(report.warnings[0].field, "item");
+    assert_eq!(report.warnings[0].selector, "datasource.plugin_test");
+    Ok(())
+}
+
+#[test]
+fn test_fetch_limits() {
+    let cases = vec![
+        (vec!["https://example.org/1", "https://example.com/2"], allowing("example.org")),
+        (vec!["https://example.org/1", "https://example.org/2"], PluginManifest{max_fetches: 1, ..allowing("example.org")}),
+    ];
+
+    for (urls, manifest) in cases {
+        let slot = plugin_slot(serde_json::json!({"urls": urls, "parse": []}), manifest);
+        let datasource = DS::new(slot, TestHttpClient::new("<html></html>"));
+
+        let result = tokio_test::block_on(datasource.extract(&b"configuration".to_vec()));
+        assert_eq!(result.err().unwrap().to_string(), "The plugin asked for pages outside its allowed hosts or over its fetch limit");
+    }
+}
+
+#[test]
+fn test_output_limit() {
+    let slot = plugin_slot(serde_json::json!({"urls": [], "parse": [person("Mozart, Wolfgang Amadeus")]}), PluginManifest{max_output_bytes: 16, ..PluginManifest::default()});
+    let datasource = DS::new(slot, TestHttpClient::new("<html></html>"));
+
+    assert_eq!(datasource.parse("<html></html>").err().unwrap().to_string(), "The plugin broke the plugin interface or went over its limits");
+}
+
+#[test]
+fn test_configurations_stay_with_the_plugin() {
+    let configuration = |ds_name: &str| serde_json::json!({"type": "Configuration", "item": {"ds_name": ds_name, "value": "https://example.org/2"}});
+    let slot = plugin_slot(serde_json::json!({
+        "urls": [],
+        "parse": [configuration("datasource.plugin_test"), configuration("datasource.bachtrack_listing"), configuration("datasource.email_newsletter")],
+    }), PluginManifest::default());
+    let datasource = DS::new(slot, TestHttpClient::new("<html></html>"));
+
+    let report = datasource.parse("<html></html>").unwrap();
+    assert_eq!(report.items, vec![Extracted::Configuration(Configuration{ds_name: "datasource.plugin_test".to_owned(), value: "https://example.org/2".to_owned(), id: None, festival_id: None})]);
+    assert_eq!(report.warnings.len(), 2);
+    assert_eq!(report.warnings[0].reason, "The plugin may only pass configurations on to its own datasource");
+}
+
+fn temporary_plugin_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("plugins-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Sets the modification time explicitly, rewrites within the filesystem's time resolution would go unnoticed
+fn write_module(dir: &Path, name: &str, module: &str, seconds: u64) {
+    let path = dir.join(format!("{}.wasm", name));
+    fs::write(&path, module).unwrap();
+    fs::File::options().write(true).open(&path).unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+}
+
+fn parse_output(slot: &PluginSlot) -> String {
+    slot.current().unwrap().runtime.call("parse", "").unwrap()
+}
+
+#[test]
+fn test_hot_reload() {
+    let dir = temporary_plugin_dir();
+    let mut registry = PluginRegistry::new(&dir, fake_loader);
+    write_module(&dir, "concerts", r#"{"parse": "v1"}"#, 1);
+    fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+    // Would subscribe to every datasource
+    write_module(&dir, "*", r#"{"parse": "v1"}"#, 1);
+    write_module(&dir, "Concerts.v2", r#"{"parse": "v1"}"#, 1);
+
+    let slots = registry.scan();
+    assert_eq!(slots.len(), 1);
+    let slot = slots[0];
+    assert_eq!(slot.ds_name, "datasource.plugin_concerts");
+    assert_eq!(parse_output(slot), r#""v1""#);
+
+    // Unchanged files aren't reloaded
+    assert!(registry.scan().is_empty());
+
+    write_module(&dir, "concerts", r#"{"parse": "v2"}"#, 2);
+    assert!(registry.scan().is_empty());
+    assert_eq!(parse_output(slot), r#""v2""#);
+
+    // A broken update keeps the running version
+    write_module(&dir, "concerts", "broken", 3);
+    registry.scan();
+    assert_eq!(parse_output(slot), r#""v2""#);
+
+    fs::remove_file(dir.join("concerts.wasm")).unwrap();
+    registry.scan();
+    assert!(slot.current().is_none());
+
+    // Coming back reuses the datasource
+    write_module(&dir, "concerts", r#"{"parse": "v3"}"#, 4);
+    assert!(registry.scan().is_empty());
+    assert_eq!(parse_output(slot), r#""v3""#);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_plugin_names() {
+    for name in &["concerts", "konzerthaus_wien", "opera-2021"] {
+        assert!(PluginSlot::new(name).is_ok(), "{}", name);
+    }
+    for name in &["", "*", ">", "concerts.eu", "Concerts", "concerts wien"] {
+        assert_eq!(PluginSlot::new(name).err().unwrap().to_string(), "Plugin names may only contain lowercase letters, digits, '_' and '-'", "{:?}", name);
+    }
+}
+
+#[test]
+fn test_missing_plugin_dir() {
+    let mut registry = PluginRegistry::new(std::env::temp_dir().join(format!("plugins-{}", Uuid::new_v4())), fake_loader);
+    assert!(registry.scan().is_empty());
+}
+
+static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+fn counting_loader(module: &[u8], manifest: &PluginManifest) -> Result<Box<dyn PluginRuntime>, Box<dyn Error>> {
+    LOADS.fetch_add(1, Ordering::SeqCst);
+    fake_loader(module, manifest)
+}
+
+#[test]
+fn test_failed_load_waits_for_changes() {
+    let dir = temporary_plugin_dir();
+    let mut registry = PluginRegistry::new(&dir, counting_loader);
+    write_module(&dir, "concerts", "broken", 1);
+
+    assert!(registry.scan().is_empty());
+    assert!(registry.scan().is_empty());
+    assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+
+    write_module(&dir, "concerts", r#"{"parse": "v1"}"#, 2);
+    let slots = registry.scan();
+    assert_eq!(slots.len(), 1);
+    assert_eq!(parse_output(slots[0]), r#""v1""#);
+    assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,6 @@
+mod reprocess;
+
+#[cfg(test)]
+mod tests;
+
+pub use reprocess::{run, ReprocessArgs};
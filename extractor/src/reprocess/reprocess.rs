@@ -0,0 +1,100 @@
+use {
+    std::io,
+    std::error::Error,
+    std::collections::HashMap,
+    chrono::NaiveDate,
+    tracing::{info, warn},
+    crate::model::{Datasource, errors},
+    common::TraceContext,
+    crate::archive::{PageArchive, ArchiveRecord},
+    crate::bus::MessageBus,
+    crate::routing::RoutingTable,
+    crate::worker::publish_items,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, PartialEq)]
+pub struct ReprocessArgs {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    // Every datasource when empty
+    pub datasources: Vec<String>,
+}
+
+impl ReprocessArgs {
+    // The arguments following `reprocess`: <from> <to> [datasource...]
+    pub fn parse(args: &[String]) -> Result<ReprocessArgs, Box<dyn Error>> {
+        if args.len() < 2 {
+            return Err(Box::new(errors::ReprocessUsageError));
+        }
+
+        Ok(ReprocessArgs{
+            from: NaiveDate::parse_from_str(&args[0], DATE_FORMAT)?,
+            to: NaiveDate::parse_from_str(&args[1], DATE_FORMAT)?,
+            datasources: args[2..].to_vec(),
+        })
+    }
+
+    pub fn includes(&self, datasource: &str) -> bool {
+        self.datasources.is_empty() || self.datasources.iter().any(|name| name == datasource)
+    }
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct ReprocessSummary {
+    pub pages: usize,
+    pub failed_pages: usize,
+    pub items: usize,
+}
+
+// Parses the datasource's archived pages again and publishes the results, without fetching anything.
+pub async fn run<T: Datasource, B: MessageBus>(
+    datasource: &T, 
+    archive: &PageArchive, 
+    publisher: &B, 
+    routes: &RoutingTable,
+    from: NaiveDate, 
+    to: NaiveDate,
+) -> io::Result<ReprocessSummary> {
+    let datasource_name = datasource.get_name();
+    let mut summary = ReprocessSummary::default();
+
+    for record in latest_fetches(archive.records(&datasource_name, from, to)?) {
+        let webpage = archive.load(&record.digest)?;
+        let parsed = match &record.configuration {
+            Some(configuration) => datasource.parse_for(&webpage, configuration.as_bytes()),
+            None => datasource.parse(&webpage),
+        };
+        let report = match parsed {
+            Ok(report) => report,
+            Err(e) => {
+                warn!(datasource = %datasource_name, url = %record.url, fetched_at = %record.fetched_at, error = %e, "Error parsing an archived page");
+                summary.failed_pages += 1;
+                continue;
+            }
+        };
+
+        summary.pages += 1;
+        summary.items += report.items.len();
+        publish_items(publisher, routes, report.items, &TraceContext::new(), &datasource_name).await?;
+    }
+
+    info!(datasource = %datasource_name, ?summary, "reprocessed archived pages");
+    Ok(summary)
+}
+
+// A page fetched several times in the range only needs its latest version parsed
+fn latest_fetches(records: Vec<ArchiveRecord>) -> Vec<ArchiveRecord> {
+    let mut latest: HashMap<String, ArchiveRecord> = HashMap::new();
+    for record in records {
+        match latest.get(&record.url) {
+            Some(existing) if existing.fetched_at > record.fetched_at => {},
+            _ => { latest.insert(record.url.to_owned(), record); },
+        };
+    }
+
+    let mut records: Vec<ArchiveRecord> = latest.into_iter().map(|(_, record)| record).collect();
+    records.sort_by_key(|record| record.fetched_at);
+    records
+}
@@ -0,0 +1,84 @@
+use {
+    std::path::Path,
+    std::error::Error,
+    std::fs,
+    chrono::{NaiveDate, Utc},
+    futures::stream::StreamExt,
+    uuid::Uuid,
+    crate::archive::PageArchive,
+    crate::bus::{MessageBus, InMemoryBus},
+    crate::datasources::bachtrack::listing,
+    crate::model::http_client::TestHttpClient,
+    common::Envelope,
+    crate::routing::RoutingTable,
+    super::{run, ReprocessArgs},
+};
+
+fn read_test_webpage(path: &str) -> Result<String, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    let webpage_path = Path::new(&base_path).join(path);
+    Ok(fs::read_to_string(webpage_path)?)
+}
+
+#[test]
+fn test_parse_args() {
+    let args: Vec<String> = vec!["2020-10-01".to_owned(), "2020-10-31".to_owned(), listing::DS_NAME.to_owned()];
+    let parsed = ReprocessArgs::parse(&args).unwrap();
+
+    assert_eq!(parsed, ReprocessArgs{
+        from: NaiveDate::from_ymd(2020, 10, 1),
+        to: NaiveDate::from_ymd(2020, 10, 31),
+        datasources: vec![listing::DS_NAME.to_owned()],
+    });
+    assert!(parsed.includes(listing::DS_NAME));
+    assert!(!parsed.includes("datasource.bachtrack_discovery"));
+
+    assert!(ReprocessArgs::parse(&args[..1]).is_err());
+    assert!(ReprocessArgs::parse(&["01/10/2020".to_owned(), "2020-10-31".to_owned()]).is_err());
+}
+
+#[tokio::test]
+async fn test_reprocess_archived_pages() -> Result<(), Box<dyn Error>>{
+    let dir = std::env::temp_dir().join(format!("archive-{}", Uuid::new_v4()));
+    let archive = PageArchive::new(&dir);
+    let webpage = read_test_webpage("resources/tests/bachtrack_listing2")?;
+    archive.store(listing::DS_NAME, "https://bachtrack.com/concert-event/1", &webpage, None)?;
+    archive.store(listing::DS_NAME, "https://bachtrack.com/concert-event/1", &webpage, None)?;
+    archive.store(listing::DS_NAME, "https://bachtrack.com/concert-event/2", "<html></html>", None)?;
+
+    let bus = InMemoryBus::new();
+    let mut events = bus.subscribe("normalizer.>", "normalizer").await?;
+    // Nothing is fetched, the client would answer with an empty page
+    let datasource = listing::DS::new(TestHttpClient::new(""));
+    let today = Utc::now().naive_utc().date();
+    let summary = run(&datasource, &archive, &bus, &RoutingTable::default(), today, today).await?;
+
+    // The first page was fetched twice but is parsed once, the empty page yields nothing
+    assert_eq!((summary.pages, summary.failed_pages, summary.items), (2, 0, 1));
+    assert_eq!(events.next().await.unwrap().subject, "normalizer.event.music");
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reprocessing_keeps_the_festival() -> Result<(), Box<dyn Error>>{
+    let dir = std::env::temp_dir().join(format!("archive-{}", Uuid::new_v4()));
+    let archive = PageArchive::new(&dir);
+    let webpage = read_test_webpage("resources/tests/bachtrack_listing2")?;
+    let configuration = r#"{"ds_name":"datasource.bachtrack_listing","value":"/concert-event/1","festival_id":"bachtrack:festival:42"}"#;
+    archive.store(listing::DS_NAME, "https://bachtrack.com/concert-event/1", &webpage, Some(configuration))?;
+
+    let bus = InMemoryBus::new();
+    let mut events = bus.subscribe("normalizer.>", "normalizer").await?;
+    let datasource = listing::DS::new(TestHttpClient::new(""));
+    let today = Utc::now().naive_utc().date();
+    run(&datasource, &archive, &bus, &RoutingTable::default(), today, today).await?;
+
+    let (_, event) = Envelope::open(&events.next().await.unwrap().data);
+    let event: serde_json::Value = serde_json::from_slice(&event)?;
+    assert_eq!(event["festival_id"], "bachtrack:festival:42");
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
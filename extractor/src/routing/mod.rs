@@ -0,0 +1,6 @@
+mod routing;
+
+#[cfg(test)]
+mod tests;
+
+pub use routing::RoutingTable;
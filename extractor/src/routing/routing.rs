@@ -0,0 +1,147 @@
+use {
+    std::error::Error,
+    std::fs,
+    serde::Deserialize,
+    serde_json::Value,
+    crate::model::Extracted,
+    crate::model::errors::InvalidRouteError,
+};
+
+const ITEM_TYPES: &[&str] = &["MusicEvent", "Venue", "Country", "Person", "City", "Piece", "Review", "Festival", "Configuration"];
+// Stands in for template fields the item doesn't have, so the item still goes somewhere
+const MISSING_FIELD: &str = "unknown";
+
+// Where extracted items are published. Routes are tried in order and the first one matching an item decides
+// its subjects, items no route matches go to their default queue (see Extracted::get_queue_name).
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingTable {
+    routes: Vec<Route>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Route {
+    #[serde(rename = "type")]
+    item_type: String,
+    // Datasources the route applies to, any when empty
+    #[serde(default)]
+    datasources: Vec<String>,
+    #[serde(default)]
+    when: Vec<Predicate>,
+    // No subjects drops the items. A subject can take fields of the item, "normalizer.venue.{city.country.name}"
+    subjects: Vec<String>,
+}
+
+// A test on a field of the item, named by its dotted path in the item's JSON ("artists.0.name")
+#[derive(Deserialize, Debug)]
+struct Predicate {
+    field: String,
+    #[serde(flatten)]
+    test: Test,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Test {
+    Equals(Value),
+    OneOf(Vec<Value>),
+    Exists(bool),
+}
+
+impl RoutingTable {
+    pub fn load(path: &str) -> Result<RoutingTable, Box<dyn Error>> {
+        let table: RoutingTable = serde_json::from_str(&fs::read_to_string(path)?)?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    // Mistakes would otherwise only show as items going to the wrong place
+    fn validate(&self) -> Result<(), InvalidRouteError> {
+        for route in &self.routes {
+            if !ITEM_TYPES.contains(&route.item_type.as_str()) {
+                return Err(InvalidRouteError);
+            }
+            for subject in &route.subjects {
+                if render(subject, &Value::Null).is_none() {
+                    return Err(InvalidRouteError);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The subjects an item extracted by a datasource is published to, none when it's dropped
+    pub fn subjects(&self, item: &Extracted, source: &str) -> Vec<String> {
+        let value = match serde_json::to_value(item) {
+            Ok(value) => value,
+            Err(_) => Value::Null,
+        };
+        let route = self.routes.iter().find(|route| route.item_type == item.get_type_name()
+            && (route.datasources.is_empty() || route.datasources.iter().any(|datasource| datasource == source))
+            && route.when.iter().all(|predicate| predicate.matches(&value)));
+
+        match route {
+            Some(route) => route.subjects.iter().filter_map(|subject| render(subject, &value)).collect(),
+            None => vec![item.get_queue_name()],
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, item: &Value) -> bool {
+        let field = lookup(item, &self.field);
+        match &self.test {
+            Test::Equals(expected) => field == Some(expected),
+            Test::OneOf(expected) => field.map(|value| expected.contains(value)).unwrap_or(false),
+            Test::Exists(exists) => field.map(|value| !value.is_null()).unwrap_or(false) == *exists,
+        }
+    }
+}
+
+fn lookup<'a>(item: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(item, |value, segment| match value {
+        Value::Object(fields) => fields.get(segment),
+        Value::Array(values) => segment.parse::<usize>().ok().and_then(|index| values.get(index)),
+        _ => None,
+    })
+}
+
+// Fills the {field} placeholders of a subject, None when the template is malformed
+fn render(template: &str, item: &Value) -> Option<String> {
+    let mut subject = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        subject.push_str(&rest[..start]);
+        let field = &rest[start + 1..end];
+        if field.is_empty() || field.contains('{') {
+            return None;
+        }
+        subject.push_str(&subject_token(lookup(item, field)));
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return None;
+    }
+    subject.push_str(rest);
+    Some(subject)
+}
+
+// A field value as a single subject token: lowercase, and nothing the subject syntax gives a meaning to
+fn subject_token(value: Option<&Value>) -> String {
+    let text = match value {
+        Some(Value::String(text)) => text.to_owned(),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::Bool(boolean)) => boolean.to_string(),
+        _ => String::new(),
+    };
+    let token: String = text.trim().to_lowercase().chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    if token.is_empty() {
+        MISSING_FIELD.to_owned()
+    } else {
+        token
+    }
+}
@@ -0,0 +1,86 @@
+use {
+    std::fs,
+    chrono::NaiveDate,
+    uuid::Uuid,
+    crate::model::{Extracted, Venue, City, Country, Person, Festival},
+    super::routing::RoutingTable,
+};
+
+const ROUTES: &str = r#"{"routes": [
+    {"type": "Venue", "datasources": ["datasource.spreadsheet_import"], "subjects": ["staging.normalizer.venue"]},
+    {"type": "Venue", "subjects": ["normalizer.venue.{city.country.name}", "analytics.venue"]},
+    {"type": "Festival", "when": [{"field": "city", "exists": false}], "subjects": []},
+    {"type": "Festival", "when": [{"field": "city.country.name", "one_of": ["Austria", "Germany"]}], "subjects": ["normalizer.festival.dach"]},
+    {"type": "Person", "when": [{"field": "name", "equals": "Anonymous"}], "subjects": []}
+]}"#;
+
+fn venue(country: &str) -> Extracted {
+    Extracted::Venue(Venue{
+        name: "Musikverein".to_owned(),
+        address: "Musikvereinsplatz 1".to_owned(),
+        city: City{name: "Vienna".to_owned(), country: Country{name: country.to_owned()}},
+    })
+}
+
+fn festival(country: Option<&str>) -> Extracted {
+    Extracted::Festival(Festival{
+        id: "1".to_owned(),
+        name: "Salzburger Festspiele".to_owned(),
+        start_date: NaiveDate::from_ymd(2021, 7, 17),
+        end_date: NaiveDate::from_ymd(2021, 8, 31),
+        city: country.map(|country| City{name: "Salzburg".to_owned(), country: Country{name: country.to_owned()}}),
+        url: "https://bachtrack.com/festival/salzburg".to_owned(),
+    })
+}
+
+fn person(name: &str) -> Extracted {
+    Extracted::Person(Person{name: name.to_owned()})
+}
+
+#[test]
+fn test_default_routes() {
+    let routes = RoutingTable::default();
+
+    assert_eq!(routes.subjects(&venue("Austria"), "datasource.bachtrack_listing"), vec!["normalizer.venue"]);
+    assert_eq!(routes.subjects(&person("Levit, Igor"), "manual"), vec!["normalizer.performer"]);
+}
+
+#[test]
+fn test_routes() {
+    let routes: RoutingTable = serde_json::from_str(ROUTES).unwrap();
+    let cases = vec![
+        (venue("Austria"), "datasource.spreadsheet_import", vec!["staging.normalizer.venue"]),
+        (venue("United Kingdom"), "datasource.bachtrack_listing", vec!["normalizer.venue.united_kingdom", "analytics.venue"]),
+        (venue(" "), "datasource.bachtrack_listing", vec!["normalizer.venue.unknown", "analytics.venue"]),
+        (festival(None), "datasource.bachtrack_festival", vec![]),
+        (festival(Some("Austria")), "datasource.bachtrack_festival", vec!["normalizer.festival.dach"]),
+        // Falls through to the default queue
+        (festival(Some("France")), "datasource.bachtrack_festival", vec!["normalizer.festival"]),
+        (person("Anonymous"), "manual", vec![]),
+        (person("Levit, Igor"), "manual", vec!["normalizer.performer"]),
+    ];
+
+    for (item, source, expected) in cases {
+        assert_eq!(routes.subjects(&item, source), expected, "{:?} from {}", item, source);
+    }
+}
+
+#[test]
+fn test_invalid_routes() {
+    let cases = vec![
+        r#"{"routes": [{"type": "Concert", "subjects": ["normalizer.concert"]}]}"#,
+        r#"{"routes": [{"type": "Venue", "subjects": ["normalizer.venue.{city.name"]}]}"#,
+        r#"{"routes": [{"type": "Venue", "subjects": ["normalizer.venue.{}"]}]}"#,
+        r#"{"routes": [{"type": "Venue", "subjects": ["normalizer.venue"], "when": [{"field": "name", "like": "Hall"}]}]}"#,
+    ];
+
+    let path = std::env::temp_dir().join(format!("routes-{}.json", Uuid::new_v4()));
+    for routes in cases {
+        fs::write(&path, routes).unwrap();
+        assert!(RoutingTable::load(path.to_str().unwrap()).is_err(), "{}", routes);
+    }
+
+    fs::write(&path, ROUTES).unwrap();
+    assert!(RoutingTable::load(path.to_str().unwrap()).is_ok());
+    fs::remove_file(path).unwrap();
+}
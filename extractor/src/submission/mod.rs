@@ -0,0 +1,7 @@
+mod submission;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+pub use server::{serve, DEFAULT_SUBMISSION_ADDR};
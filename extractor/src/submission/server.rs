@@ -0,0 +1,141 @@
+use {
+    std::convert::Infallible,
+    std::net::SocketAddr,
+    std::sync::Arc,
+    serde::Serialize,
+    hyper::{Body, Method, Request, Response, Server, StatusCode},
+    hyper::body::HttpBody,
+    hyper::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE},
+    hyper::service::{make_service_fn, service_fn},
+    tracing::{info, warn, error},
+    crate::bus::MessageBus,
+    common::TraceContext,
+    crate::routing::RoutingTable,
+    crate::worker::publish_items,
+    crate::metrics,
+    common::Shutdown,
+    super::submission::{Submission, MANUAL_SOURCE},
+};
+
+// Only reachable from the host unless SUBMISSION_ADDR says otherwise
+pub const DEFAULT_SUBMISSION_ADDR: &str = "127.0.0.1:9899";
+const EVENTS_PATH: &str = "/events";
+// Far more than any single event needs
+const MAX_SUBMISSION_SIZE: usize = 1024 * 1024;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+enum SubmissionResponse {
+    Accepted{correlation_id: String, published: usize},
+    Rejected{errors: Vec<String>},
+}
+
+// Submitters authenticate with the shared token as a bearer token
+pub async fn serve<B: MessageBus + 'static>(addr: SocketAddr, token: String, bus: Arc<B>, routes: Arc<RoutingTable>, shutdown: Shutdown) -> Result<(), hyper::Error> {
+    let token = Arc::new(token);
+    let make_service = make_service_fn(move |_| {
+        let bus = Arc::clone(&bus);
+        let routes = Arc::clone(&routes);
+        let token = Arc::clone(&token);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle(request, Arc::clone(&token), Arc::clone(&bus), Arc::clone(&routes))))
+        }
+    });
+
+    info!(%addr, "accepting event submissions");
+    Server::bind(&addr)
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown.wait())
+        .await
+}
+
+// POST /events with a Submission, the event is routed like an extracted one
+pub async fn handle<B: MessageBus>(request: Request<Body>, token: Arc<String>, bus: Arc<B>, routes: Arc<RoutingTable>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != EVENTS_PATH {
+        return Ok(empty_response(StatusCode::NOT_FOUND));
+    }
+    if request.method() != Method::POST {
+        return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    if !authorized(&request, &token) {
+        warn!("Rejected a submission without a valid token");
+        metrics::SUBMISSIONS.with_label_values(&["unauthorized"]).inc();
+        return Ok(empty_response(StatusCode::UNAUTHORIZED));
+    }
+
+    let too_large = || rejected(StatusCode::PAYLOAD_TOO_LARGE, vec!["the submission is too large".to_owned()]);
+    let content_length = request.headers().get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<usize>().ok());
+    if content_length.map_or(false, |length| length > MAX_SUBMISSION_SIZE) {
+        return Ok(too_large());
+    }
+    let body = match read_body(request.into_body(), MAX_SUBMISSION_SIZE).await {
+        Ok(Some(body)) => body,
+        Ok(None) => return Ok(too_large()),
+        Err(e) => return Ok(rejected(StatusCode::BAD_REQUEST, vec![e.to_string()])),
+    };
+    let submission: Submission = match serde_json::from_slice(&body) {
+        Ok(submission) => submission,
+        Err(e) => return Ok(rejected(StatusCode::BAD_REQUEST, vec![e.to_string()])),
+    };
+    let problems = submission.validate();
+    if !problems.is_empty() {
+        return Ok(rejected(StatusCode::UNPROCESSABLE_ENTITY, problems));
+    }
+
+    let trace = TraceContext::new();
+    let items = submission.into_items();
+    let published = items.len();
+    if let Err(e) = publish_items(bus.as_ref(), &routes, items, &trace, MANUAL_SOURCE).await {
+        error!(correlation_id = %trace.correlation_id, error = %e, "Error publishing a submitted event");
+        metrics::SUBMISSIONS.with_label_values(&["failed"]).inc();
+        return Ok(empty_response(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    info!(correlation_id = %trace.correlation_id, published, "published a submitted event");
+    metrics::SUBMISSIONS.with_label_values(&["accepted"]).inc();
+    Ok(json_response(StatusCode::ACCEPTED, &SubmissionResponse::Accepted{correlation_id: trace.correlation_id, published: published}))
+}
+
+// Compares every byte, so the time taken doesn't tell how much of a guess was right
+fn authorized(request: &Request<Body>, token: &str) -> bool {
+    let given = match request.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok()) {
+        Some(value) if value.starts_with("Bearer ") => value["Bearer ".len()..].as_bytes(),
+        _ => return false,
+    };
+    given.len() == token.len() && given.iter().zip(token.as_bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+// None once the body grows past the limit, without reading the rest of it
+async fn read_body(body: Body, limit: usize) -> Result<Option<Vec<u8>>, hyper::Error> {
+    let mut body = body;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+fn rejected(status: StatusCode, errors: Vec<String>) -> Response<Body> {
+    warn!(%status, ?errors, "Rejected a submitted event");
+    metrics::SUBMISSIONS.with_label_values(&["rejected"]).inc();
+    json_response(status, &SubmissionResponse::Rejected{errors: errors})
+}
+
+fn json_response(status: StatusCode, content: &SubmissionResponse) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_vec(content).unwrap_or_default()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
@@ -0,0 +1,83 @@
+use {
+    serde::Deserialize,
+    crate::model::{Extracted, MusicEvent, Venue, Person},
+};
+
+// Provenance of the events curators add by hand
+pub const MANUAL_SOURCE: &str = "manual";
+
+// An event added by hand, in the extractor's model, with what it refers to
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Submission {
+    pub event: MusicEvent,
+    #[serde(default)]
+    pub venue: Option<Venue>,
+    // Performers that aren't already among the event's artists
+    #[serde(default)]
+    pub performers: Vec<Person>,
+}
+
+impl Submission {
+    // What a scraped page would never yield, every problem at once so the curator can fix them together
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let event = &self.event;
+
+        if event.time.end_time <= event.time.start_time {
+            problems.push("event.time.end_time must be after event.time.start_time".to_owned());
+        }
+        if event.description.trim().is_empty() && event.artists.is_empty() && self.performers.is_empty() {
+            problems.push("the event needs a description or at least one artist".to_owned());
+        }
+        for (index, artist) in event.artists.iter().enumerate() {
+            if artist.name.trim().is_empty() {
+                problems.push(format!("event.artists[{}].name is empty", index));
+            }
+        }
+        for (index, piece) in event.pieces.iter().enumerate() {
+            if piece.name.trim().is_empty() {
+                problems.push(format!("event.pieces[{}].name is empty", index));
+            }
+            for (artist_index, artist) in piece.artists.iter().enumerate() {
+                if artist.name.trim().is_empty() {
+                    problems.push(format!("event.pieces[{}].artists[{}].name is empty", index, artist_index));
+                }
+            }
+        }
+        if event.festival_id.as_ref().map(|id| id.trim().is_empty()).unwrap_or(false) {
+            problems.push("event.festival_id is empty".to_owned());
+        }
+        for (index, performer) in self.performers.iter().enumerate() {
+            if performer.name.trim().is_empty() {
+                problems.push(format!("performers[{}].name is empty", index));
+            }
+        }
+        if let Some(venue) = &self.venue {
+            let names = [("venue.name", &venue.name), ("venue.city.name", &venue.city.name), ("venue.city.country.name", &venue.city.country.name)];
+            for (field, name) in names.iter() {
+                if name.trim().is_empty() {
+                    problems.push(format!("{} is empty", field));
+                }
+            }
+        }
+        problems
+    }
+
+    // The items a listing page with the same content would have been extracted into
+    pub fn into_items(self) -> Vec<Extracted> {
+        let mut event = self.event;
+        for performer in self.performers {
+            if !event.artists.contains(&performer) {
+                event.artists.push(performer);
+            }
+        }
+
+        let mut items = Vec::new();
+        items.extend(self.venue.map(Extracted::Venue));
+        items.extend(event.artists.iter().cloned().map(Extracted::Person));
+        items.extend(event.pieces.iter().cloned().map(Extracted::Piece));
+        items.push(Extracted::MusicEvent(event));
+        items
+    }
+}
@@ -0,0 +1,137 @@
+use {
+    std::sync::Arc,
+    futures::stream::StreamExt,
+    hyper::{Body, Method, Request, StatusCode},
+    crate::bus::{MessageBus, InMemoryBus},
+    crate::model::{Extracted, Person, EndTimePrecision},
+    common::Envelope,
+    crate::routing::RoutingTable,
+    super::server::handle,
+    super::submission::Submission,
+};
+
+const SUBMISSION: &str = r#"{
+    "event": {
+        "artists": [{"name": "Levit, Igor"}],
+        "pieces": [{"name": "Piano Sonata no. 29 in B flat major, Op.106 \"Hammerklavier\"", "artists": [{"name": "Beethoven, Ludwig van"}]}],
+        "description": "Igor Levit plays the Hammerklavier sonata",
+        "time": {"start_time": "2021-11-05T19:30:00", "end_time": "2021-11-05T21:00:00", "end_time_precision": "exact", "local_timezone": true}
+    },
+    "venue": {"name": "Wigmore Hall", "address": "36 Wigmore Street", "city": {"name": "London", "country": {"name": "United Kingdom"}}},
+    "performers": [{"name": "Levit, Igor"}, {"name": "Beethoven, Ludwig van"}]
+}"#;
+
+const TOKEN: &str = "s3cr3t";
+
+fn request(method: Method, path: &str, body: &str) -> Request<Body> {
+    Request::builder().method(method).uri(path).header("Authorization", format!("Bearer {}", TOKEN)).body(Body::from(body.to_owned())).unwrap()
+}
+
+fn token() -> Arc<String> {
+    Arc::new(TOKEN.to_owned())
+}
+
+async fn response_body(response: hyper::Response<Body>) -> serde_json::Value {
+    serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap()
+}
+
+#[test]
+fn test_validate() {
+    let submission: Submission = serde_json::from_str(SUBMISSION).unwrap();
+    assert_eq!(submission.validate(), Vec::<String>::new());
+
+    let mut invalid = submission.to_owned();
+    invalid.event.time.end_time = invalid.event.time.start_time;
+    invalid.event.artists.push(Person{name: " ".to_owned()});
+    invalid.venue.as_mut().unwrap().city.name = "".to_owned();
+    assert_eq!(invalid.validate(), vec![
+        "event.time.end_time must be after event.time.start_time".to_owned(),
+        "event.artists[1].name is empty".to_owned(),
+        "venue.city.name is empty".to_owned(),
+    ]);
+}
+
+#[test]
+fn test_submission_without_end_time_precision() {
+    let submission: Submission = serde_json::from_str(&SUBMISSION.replace(r#""end_time_precision": "exact", "#, "")).unwrap();
+    assert_eq!(submission.event.time.end_time_precision, EndTimePrecision::Default);
+}
+
+#[test]
+fn test_into_items() {
+    let submission: Submission = serde_json::from_str(SUBMISSION).unwrap();
+    let items = submission.into_items();
+
+    let types: Vec<&str> = items.iter().map(|item| item.get_type_name()).collect();
+    assert_eq!(types, vec!["Venue", "Person", "Person", "Piece", "MusicEvent"]);
+    match &items[4] {
+        Extracted::MusicEvent(event) => assert_eq!(event.artists, vec![Person{name: "Levit, Igor".to_owned()}, Person{name: "Beethoven, Ludwig van".to_owned()}]),
+        item => panic!("expected an event, got {:?}", item),
+    }
+}
+
+#[tokio::test]
+async fn test_submission_is_published() {
+    let bus = Arc::new(InMemoryBus::new());
+    let mut published = bus.subscribe("normalizer.>", "normalizer").await.unwrap();
+
+    let response = handle(request(Method::POST, "/events", SUBMISSION), token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(response_body(response).await["published"], 5);
+
+    let mut subjects = Vec::new();
+    for _ in 0..5 {
+        let delivery = published.next().await.unwrap();
+        let envelope: Envelope<serde_json::Value> = serde_json::from_slice(&delivery.data).unwrap();
+        assert_eq!(envelope.source, Some("manual".to_owned()));
+        subjects.push(delivery.subject);
+    }
+    assert_eq!(subjects, vec!["normalizer.venue", "normalizer.performer", "normalizer.performer", "normalizer.piece", "normalizer.event.music"]);
+}
+
+#[tokio::test]
+async fn test_invalid_submissions_are_rejected() {
+    let bus = Arc::new(InMemoryBus::new());
+
+    let response = handle(request(Method::POST, "/events", r#"{"event": {"description": "no time"}}"#), token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response_body(response).await["errors"][0].as_str().unwrap().contains("missing field"));
+
+    let without_content = SUBMISSION.replace("Igor Levit plays the Hammerklavier sonata", "").replace(r#""artists": [{"name": "Levit, Igor"}],"#, r#""artists": [],"#).replace(r#""performers": [{"name": "Levit, Igor"}, {"name": "Beethoven, Ludwig van"}]"#, r#""performers": []"#);
+    let response = handle(request(Method::POST, "/events", &without_content), token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_body(response).await, serde_json::json!({"errors": ["the event needs a description or at least one artist"]}));
+
+    let response = handle(request(Method::GET, "/events", ""), token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    let response = handle(request(Method::POST, "/venues", SUBMISSION), token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_submissions_need_the_token() {
+    let bus = Arc::new(InMemoryBus::new());
+
+    let without_token = Request::builder().method(Method::POST).uri("/events").body(Body::from(SUBMISSION)).unwrap();
+    let response = handle(without_token, token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let wrong_token = Request::builder().method(Method::POST).uri("/events").header("Authorization", "Bearer s3cr3x").body(Body::from(SUBMISSION)).unwrap();
+    let response = handle(wrong_token, token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_large_submissions_are_rejected() {
+    let bus = Arc::new(InMemoryBus::new());
+
+    // Turned away on the announced length, before the body is read
+    let mut announced = request(Method::POST, "/events", SUBMISSION);
+    announced.headers_mut().insert("Content-Length", "2000000".parse().unwrap());
+    let response = handle(announced, token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let unannounced = request(Method::POST, "/events", &" ".repeat(2 * 1024 * 1024));
+    let response = handle(unannounced, token(), Arc::clone(&bus), Arc::new(RoutingTable::default())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
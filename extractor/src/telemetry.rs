@@ -0,0 +1,86 @@
+use {
+    tracing::Span,
+    tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter},
+    common::TraceContext,
+};
+
+#[cfg(feature = "otlp")]
+use {
+    std::collections::HashMap,
+    opentelemetry::{global, sdk::propagation::TraceContextPropagator},
+    tracing_opentelemetry::OpenTelemetrySpanExt,
+};
+
+const DEFAULT_LOG_FILTER: &str = "info";
+
+// Keeps the trace exporter alive, traces are flushed when it's dropped.
+pub struct Guard {
+    #[cfg(feature = "otlp")]
+    _uninstall: Option<opentelemetry_otlp::Uninstall>,
+}
+
+// Sets up structured logging (filtered by RUST_LOG) and, when built with the `otlp` feature 
+// and OTLP_ENDPOINT is set, exports spans to an OpenTelemetry collector.
+pub fn init() -> Guard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer());
+
+    #[cfg(feature = "otlp")]
+    {
+        if let Ok(endpoint) = std::env::var("OTLP_ENDPOINT") {
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            match opentelemetry_otlp::new_pipeline().with_endpoint(&endpoint).install() {
+                Ok((tracer, uninstall)) => {
+                    registry.with(tracing_opentelemetry::layer().with_tracer(tracer)).init();
+                    tracing::info!(endpoint = %endpoint, "exporting traces");
+                    return Guard{_uninstall: Some(uninstall)};
+                },
+                Err(e) => eprintln!("Error setting up the trace exporter. err: {}", e),
+            }
+        }
+        registry.init();
+        return Guard{_uninstall: None};
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        registry.init();
+        Guard{}
+    }
+}
+
+// The trace context to put on messages published while in the given span.
+pub fn child_context(span: &Span, trace: &TraceContext) -> TraceContext {
+    TraceContext{
+        correlation_id: trace.correlation_id.to_owned(),
+        traceparent: traceparent(span),
+    }
+}
+
+#[cfg(feature = "otlp")]
+fn traceparent(span: &Span) -> Option<String> {
+    let mut carrier = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&span.context(), &mut carrier));
+    carrier.remove("traceparent")
+}
+
+#[cfg(not(feature = "otlp"))]
+fn traceparent(_span: &Span) -> Option<String> {
+    None
+}
+
+// Continues the trace of the publishing side, if the message carries one.
+#[cfg(feature = "otlp")]
+pub fn set_parent(span: &Span, trace: &TraceContext) {
+    if let Some(traceparent) = &trace.traceparent {
+        let mut carrier = HashMap::new();
+        carrier.insert("traceparent".to_owned(), traceparent.to_owned());
+        let context = global::get_text_map_propagator(|propagator| propagator.extract(&carrier));
+        span.set_parent(&context);
+    }
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn set_parent(_span: &Span, _trace: &TraceContext) {}
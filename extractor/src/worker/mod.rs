@@ -0,0 +1,7 @@
+mod worker;
+mod sync;
+
+#[cfg(test)]
+mod tests;
+
+pub use worker::{run, publish_items, MAX_CONCURRENT_MESSAGES};
@@ -0,0 +1,136 @@
+use {
+    std::sync::Arc,
+    std::time::Duration,
+    serde::{Serialize, Deserialize},
+    futures::stream::StreamExt,
+    tracing::{info, warn, error, info_span},
+    tracing_futures::Instrument,
+    crate::metrics,
+    crate::telemetry,
+    crate::model::{Datasource, Extracted, ExtractionWarning},
+    common::{Envelope, TraceContext},
+    crate::bus::MessageBus,
+    crate::routing::RoutingTable,
+    common::Shutdown,
+    super::worker::{publish_items, is_retryable, MAX_CONCURRENT_MESSAGES},
+};
+
+pub const SYNC_SUFFIX: &str = "sync";
+// Requesters should wait a little longer, the reply still has to travel back
+pub const SYNC_DEADLINE: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SyncReply {
+    Extracted{
+        correlation_id: String,
+        items: Vec<Extracted>,
+        warnings: Vec<ExtractionWarning>,
+        // The items are returned either way, false when they didn't make it downstream
+        published: bool,
+    },
+    Failed{
+        correlation_id: String,
+        error: SyncError,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncError {
+    DeadlineExceeded{deadline_ms: u64},
+    // Retryable when the same request may succeed later, e.g. the page couldn't be fetched
+    ExtractFailed{message: String, retryable: bool},
+}
+
+impl SyncReply {
+    fn outcome(&self) -> &'static str {
+        match self {
+            SyncReply::Extracted{published: true, ..} => "extracted",
+            SyncReply::Extracted{published: false, ..} => "unpublished",
+            SyncReply::Failed{error: SyncError::DeadlineExceeded{..}, ..} => "deadline_exceeded",
+            SyncReply::Failed{error: SyncError::ExtractFailed{..}, ..} => "failed",
+        }
+    }
+}
+
+pub fn sync_subject(datasource_name: &str) -> String {
+    format!("{}.{}", datasource_name, SYNC_SUFFIX)
+}
+
+// Answers requests on <subject>.sync with what the configuration extracts right now, until shutdown.
+// The items are published downstream like those of the asynchronous subject.
+pub async fn serve<T: Datasource + Copy, B: MessageBus + 'static>(datasource: T, bus: Arc<B>, routes: &RoutingTable, queue_group: &str, shutdown: Shutdown){
+    let datasource_name = datasource.get_name();
+    let subject = sync_subject(&datasource_name);
+    let requests = match bus.subscribe(&subject, queue_group).await {
+        Ok(requests) => requests,
+        Err(e) => {
+            error!(datasource = %datasource_name, error = %e, "Error subscribing to synchronous requests");
+            return;
+        }
+    };
+
+    // Unsubscribes on shutdown so the group's other members get the following requests
+    requests.drain_when(shutdown.wait()).for_each_concurrent(MAX_CONCURRENT_MESSAGES, move |request| {
+        let publisher = Arc::clone(&bus);
+        let (trace, configuration) = Envelope::open(&request.data);
+        let span = info_span!(
+            "sync_request",
+            datasource = %datasource.get_name(),
+            subject = %request.subject,
+            correlation_id = %trace.correlation_id,
+        );
+        telemetry::set_parent(&span, &trace);
+
+        async move {
+            let reply = extract(datasource, &configuration, &trace, publisher.as_ref(), routes).await;
+            metrics::SYNC_REQUESTS.with_label_values(&[&datasource.get_name(), reply.outcome()]).inc();
+            info!(outcome = reply.outcome(), "Answered a synchronous request");
+
+            let response = match serde_json::to_vec(&reply) {
+                Ok(response) => response,
+                Err(e) => {
+                    error!(error = %e, "Error serializing a synchronous reply");
+                    metrics::ERRORS.with_label_values(&["serialize"]).inc();
+                    return;
+                }
+            };
+            if let Err(e) = request.respond(&response).await {
+                warn!(error = %e, "Error replying to a synchronous request");
+                metrics::ERRORS.with_label_values(&["respond"]).inc();
+            }
+        }.instrument(span)
+    }).await;
+}
+
+pub async fn extract<T: Datasource, B: MessageBus>(datasource: T, configuration: &Vec<u8>, trace: &TraceContext, publisher: &B, routes: &RoutingTable) -> SyncReply {
+    let datasource_name = datasource.get_name();
+    let correlation_id = trace.correlation_id.to_owned();
+
+    let timer = metrics::EXTRACTION_DURATION.with_label_values(&[&datasource_name]).start_timer();
+    let extract_result = tokio::time::timeout(SYNC_DEADLINE, datasource.extract(configuration)).await;
+    timer.observe_duration();
+
+    let report = match extract_result {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) => {
+            error!(error = %e, "Error occured in the extract logic");
+            metrics::ERRORS.with_label_values(&["extract"]).inc();
+            let error = SyncError::ExtractFailed{message: e.to_string(), retryable: is_retryable(&e)};
+            return SyncReply::Failed{correlation_id: correlation_id, error: error};
+        },
+        Err(_) => {
+            warn!(deadline = ?SYNC_DEADLINE, "Extraction didn't finish before the deadline");
+            let error = SyncError::DeadlineExceeded{deadline_ms: SYNC_DEADLINE.as_millis() as u64};
+            return SyncReply::Failed{correlation_id: correlation_id, error: error};
+        },
+    };
+
+    let child_trace = telemetry::child_context(&tracing::Span::current(), trace);
+    let published = publish_items(publisher, routes, report.items.clone(), &child_trace, &datasource_name).await.is_ok();
+    if published {
+        datasource.commit(&report.consumed);
+    }
+    SyncReply::Extracted{correlation_id: correlation_id, items: report.items, warnings: report.warnings, published: published}
+}
@@ -0,0 +1,227 @@
+use {
+    std::io,
+    std::sync::Arc,
+    std::path::Path,
+    std::error::Error,
+    std::fs,
+    std::time::Duration,
+    std::sync::atomic::{AtomicUsize, Ordering},
+    async_trait::async_trait,
+    futures::channel::oneshot,
+    futures::stream::StreamExt,
+    crate::bus::{MessageBus, InMemoryBus},
+    crate::datasources::bachtrack::{discovery, listing},
+    crate::model::{Datasource, ExtractResult, ExtractionReport, Extracted, MusicEvent, Configuration},
+    common::{Envelope, TraceContext},
+    crate::model::errors::CircuitOpenError,
+    crate::jetstream::MAX_DELIVER,
+    crate::model::http_client::TestHttpClient,
+    crate::routing::RoutingTable,
+    common::Shutdown,
+    super::sync::{SyncReply, SyncError},
+};
+
+const DISCOVERY_URL: &str = "https://bachtrack.com/find-concerts/";
+
+fn read_test_webpage(path: &str) -> Result<String, Box<dyn Error>>{
+    let base_path = std::env::var("CARGO_MANIFEST_DIR")?;
+    let webpage_path = Path::new(&base_path).join(path);
+    Ok(fs::read_to_string(webpage_path)?)
+}
+
+// Discovery feeds listing through the bus, a normalizer stand-in consumes the events
+#[tokio::test]
+async fn test_discovery_to_normalizer() -> Result<(), Box<dyn Error>>{
+    let discovery_page = read_test_webpage("resources/tests/bachtrack_discovery")?;
+    let listing_page = read_test_webpage("resources/tests/bachtrack_listing2")?;
+
+    let bus = Arc::new(InMemoryBus::new());
+    let routes = RoutingTable::default();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let shutdown = Shutdown::when(async { let _ = stopped.await; });
+
+    // The workers' groups, so what's published before the workers subscribe is kept for them
+    for subject in &[discovery::DS_NAME, listing::DS_NAME] {
+        drop(bus.subscribe(subject, "extractor").await?);
+    }
+    bus.publish(discovery::DS_NAME, DISCOVERY_URL.as_bytes()).await?;
+    let mut events = bus.subscribe("normalizer.>", "normalizer").await?;
+    let mut dead_letters = bus.subscribe("dlq.>", "monitoring").await?;
+
+    let normalizer = async {
+        let mut received = Vec::new();
+        // One event on each of the 50 listings
+        while received.len() < 50 {
+            let delivery = events.next().await.unwrap();
+            assert_eq!(delivery.subject, "normalizer.event.music");
+            let (_, payload) = Envelope::open(&delivery.data);
+            let event: Extracted = serde_json::from_slice(&payload).unwrap();
+            received.push(event);
+        }
+        stop.send(()).unwrap();
+        received
+    };
+
+    let (received, _, _) = futures::join!(
+        normalizer,
+        crate::worker::run(discovery::DS::new(TestHttpClient::new(&discovery_page)), Arc::clone(&bus), &routes, "extractor", shutdown.clone()),
+        crate::worker::run(listing::DS::new(TestHttpClient::new(&listing_page)), Arc::clone(&bus), &routes, "extractor", shutdown.clone()),
+    );
+
+    match &received[0] {
+        Extracted::MusicEvent(MusicEvent{artists, ..}) => assert_eq!(artists[0].name, "Mozart, Wolfgang Amadeus"),
+        other => panic!("expected a music event, got {:?}", other),
+    };
+    bus.close().await?;
+    assert!(dead_letters.next().await.is_none());
+    Ok(())
+}
+
+// Fails every extraction like an unreachable page would
+#[derive(Copy, Clone)]
+struct Unreachable;
+
+#[async_trait]
+impl Datasource for Unreachable {
+    async fn extract(&self, _configuration: &Vec<u8>) -> ExtractResult {
+        Err(Box::new(io::Error::new(io::ErrorKind::ConnectionRefused, "connection refused")))
+    }
+
+    fn parse(&self, _webpage: &str) -> ExtractResult {
+        Ok(ExtractionReport::new(Vec::new()))
+    }
+
+    fn get_name(&self) -> String {
+        "datasource.unreachable".to_owned()
+    }
+}
+
+// Requests find no responder until the worker subscribed
+async fn request_when_ready<B: MessageBus>(bus: &B, subject: &str, payload: &[u8]) -> SyncReply {
+    loop {
+        match bus.request(subject, payload, Duration::from_secs(5)).await {
+            Ok(response) => return serde_json::from_slice(&response).unwrap(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => tokio::time::delay_for(Duration::from_millis(10)).await,
+            Err(e) => panic!("request failed: {}", e),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_sync_requests() -> Result<(), Box<dyn Error>>{
+    let listing_page = read_test_webpage("resources/tests/bachtrack_listing2")?;
+
+    let bus = Arc::new(InMemoryBus::new());
+    let routes = RoutingTable::default();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let shutdown = Shutdown::when(async { let _ = stopped.await; });
+    let mut events = bus.subscribe("normalizer.event.music", "normalizer").await?;
+
+    let requester = async {
+        let trace = TraceContext{correlation_id: "c0ffee".to_owned(), traceparent: None};
+        let configuration = Extracted::Configuration(Configuration{
+            ds_name: listing::DS_NAME.to_owned(),
+            value: "/concert-event/1".to_owned(),
+            id: None,
+            festival_id: None,
+        });
+        let request = serde_json::to_vec(&Envelope::new(trace, &configuration))?;
+        let extracted = request_when_ready(bus.as_ref(), "datasource.bachtrack_listing.sync", &request).await;
+        let failed = request_when_ready(bus.as_ref(), "datasource.unreachable.sync", b"https://example.org/").await;
+        stop.send(()).unwrap();
+        Ok::<_, Box<dyn Error>>((extracted, failed))
+    };
+
+    let (replies, _, _) = futures::join!(
+        requester,
+        crate::worker::run(listing::DS::new(TestHttpClient::new(&listing_page)), Arc::clone(&bus), &routes, "extractor", shutdown.clone()),
+        crate::worker::run(Unreachable, Arc::clone(&bus), &routes, "extractor", shutdown.clone()),
+    );
+    let (extracted, failed) = replies?;
+
+    match extracted {
+        SyncReply::Extracted{correlation_id, items, published, ..} => {
+            assert_eq!(correlation_id, "c0ffee");
+            assert_eq!(items.len(), 1);
+            assert!(published);
+            // Still published downstream
+            let (_, payload) = Envelope::open(&events.next().await.unwrap().data);
+            assert_eq!(serde_json::from_slice::<Extracted>(&payload)?, items[0]);
+        },
+        other => panic!("expected extracted items, got {:?}", other),
+    };
+    match failed {
+        SyncReply::Failed{error, ..} => assert_eq!(error, SyncError::ExtractFailed{message: "connection refused".to_owned(), retryable: true}),
+        other => panic!("expected an error, got {:?}", other),
+    };
+    Ok(())
+}
+
+#[test]
+fn test_sync_reply_format() {
+    let reply = SyncReply::Failed{correlation_id: "c0ffee".to_owned(), error: SyncError::DeadlineExceeded{deadline_ms: 10000}};
+    assert_eq!(serde_json::to_value(&reply).unwrap(), serde_json::json!({
+        "status": "failed",
+        "correlation_id": "c0ffee",
+        "error": {"kind": "deadline_exceeded", "deadline_ms": 10000},
+    }));
+}
+
+static BREAKER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+// Turned away by an open breaker on more calls than a message has deliveries
+#[derive(Copy, Clone)]
+struct BreakerOpen;
+
+#[async_trait]
+impl Datasource for BreakerOpen {
+    async fn extract(&self, _configuration: &Vec<u8>) -> ExtractResult {
+        if BREAKER_CALLS.fetch_add(1, Ordering::SeqCst) < MAX_DELIVER as usize {
+            return Err(Box::new(CircuitOpenError));
+        }
+        Ok(ExtractionReport::new(Vec::new()))
+    }
+
+    fn parse(&self, _webpage: &str) -> ExtractResult {
+        Ok(ExtractionReport::new(Vec::new()))
+    }
+
+    fn get_name(&self) -> String {
+        "datasource.breaker_open".to_owned()
+    }
+
+    // Not before the first call, so the message is taken at all
+    fn paused(&self) -> Option<Duration> {
+        match BREAKER_CALLS.load(Ordering::SeqCst) {
+            0 => None,
+            calls if calls <= MAX_DELIVER as usize => Some(Duration::from_millis(1)),
+            _ => None,
+        }
+    }
+}
+
+// Messages turned away by a breaker are held rather than redelivered into the dead letter queue
+#[tokio::test]
+async fn test_held_while_breaker_open() -> Result<(), Box<dyn Error>>{
+    let bus = Arc::new(InMemoryBus::new());
+    let routes = RoutingTable::default();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let shutdown = Shutdown::when(async { let _ = stopped.await; });
+
+    drop(bus.subscribe("datasource.breaker_open", "extractor").await?);
+    bus.publish("datasource.breaker_open", b"https://example.org/").await?;
+    let mut dead_letters = bus.subscribe("dlq.>", "monitoring").await?;
+
+    let stopper = async {
+        while BREAKER_CALLS.load(Ordering::SeqCst) <= MAX_DELIVER as usize {
+            tokio::time::delay_for(Duration::from_millis(5)).await;
+        }
+        stop.send(()).unwrap();
+    };
+    futures::join!(stopper, crate::worker::run(BreakerOpen, Arc::clone(&bus), &routes, "extractor", shutdown));
+
+    bus.close().await?;
+    assert!(dead_letters.next().await.is_none());
+    assert_eq!(BREAKER_CALLS.load(Ordering::SeqCst), MAX_DELIVER as usize + 1);
+    Ok(())
+}
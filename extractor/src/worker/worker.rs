@@ -0,0 +1,240 @@
+use {
+    std::io,
+    serde::Serialize,
+    std::sync::{Arc, Mutex},
+    std::time::Duration,
+    futures::future::{self, Either},
+    futures::stream::StreamExt,
+    tracing::{info, warn, error, debug, info_span, field},
+    tracing_futures::Instrument,
+    crate::metrics,
+    crate::telemetry,
+    crate::model::{Datasource, Extracted, ExtractResult},
+    crate::model::errors::CircuitOpenError,
+    common::{Envelope, TraceContext},
+    crate::model::quality::{QualityReport, DriftDetector, Diagnostics, DRIFT_ALERT_SUBJECT, DIAGNOSTICS_SUBJECT},
+    crate::bus::{MessageBus, Delivery, Outcome},
+    crate::routing::RoutingTable,
+    common::Shutdown,
+    super::sync,
+};
+
+pub const MAX_CONCURRENT_MESSAGES: usize = 100;
+// How long in-flight messages get to finish once a shutdown signal arrives
+const DRAIN_DEADLINE: Duration = Duration::from_secs(30);
+// Longest a held message waits between checks of its breaker, well within the consumer's ack wait
+const HOLD_INTERVAL: Duration = Duration::from_secs(20);
+
+// Consumes the datasource's subject and answers requests on its sync subject until shutdown
+pub async fn run<T: Datasource + Copy, B: MessageBus + 'static>(datasource: T, bus: Arc<B>, routes: &RoutingTable, queue_group: &str, shutdown: Shutdown){
+    futures::join!(
+        consume(datasource, Arc::clone(&bus), routes, queue_group, shutdown.clone()),
+        sync::serve(datasource, bus, routes, queue_group, shutdown),
+    );
+}
+
+// Consumes the datasource's subject until shutdown, then gives in-flight messages until the deadline to finish.
+async fn consume<T: Datasource + Copy, B: MessageBus + 'static>(datasource: T, bus: Arc<B>, routes: &RoutingTable, queue_group: &str, shutdown: Shutdown){
+    let datasource_name = datasource.get_name();
+    info!(datasource = %datasource_name, %queue_group, "listening to queue");
+
+    let subscriber = match bus.subscribe(&datasource_name, queue_group).await {
+        Ok(subscriber) => subscriber,
+        Err(e) => {
+            error!(datasource = %datasource_name, error = %e, "Error subscribing");
+            return;
+        }
+    };
+
+    let arc_drift_detector = Arc::new(Mutex::new(DriftDetector::new(&datasource_name)));
+
+    // Unsubscribes on shutdown, the messages received until then are still processed
+    let subscriber = subscriber.drain_when(shutdown.clone().wait());
+
+    // Holds the next message back while the datasource's site turns fetches away
+    let subscriber = subscriber.then(move |message| async move {
+        if let Some(wait) = datasource.paused() {
+            info!(datasource = %datasource.get_name(), ?wait, "consumption paused");
+            let _paused = metrics::Paused::start(&datasource.get_name());
+            let mut wait = wait;
+            loop {
+                tokio::time::delay_for(wait).await;
+                wait = match datasource.paused() {
+                    Some(wait) => wait,
+                    None => break,
+                };
+            }
+            info!(datasource = %datasource.get_name(), "consumption resumed");
+        }
+        message
+    });
+
+    let processing = subscriber.for_each_concurrent(MAX_CONCURRENT_MESSAGES, move |message|{
+        metrics::MESSAGES_CONSUMED.with_label_values(&[&message.subject]).inc();
+        let publisher = Arc::clone(&bus);
+        let drift_detector = Arc::clone(&arc_drift_detector);
+
+        let (trace, configuration) = Envelope::open(&message.data);
+        let span = info_span!(
+            "message", 
+            datasource = %datasource_name, 
+            subject = %message.subject, 
+            correlation_id = %trace.correlation_id,
+            url = field::Empty,
+        );
+        telemetry::set_parent(&span, &trace);
+
+        async move{
+            info!("Starting extraction");
+            let _in_flight = metrics::InFlight::start(&datasource.get_name());
+
+            let outcome = process_message(datasource, &message, &configuration, &trace, publisher.as_ref(), routes, &drift_detector).await;
+            if let Err(e) = message.settle(&outcome).await {
+                error!(error = %e, ?outcome, "Error settling the message");
+                metrics::ERRORS.with_label_values(&["settle"]).inc();
+            }
+            info!(?outcome, "Finished extraction");
+        }.instrument(span)
+    });
+
+    let datasource_name = datasource.get_name();
+    let deadline = async move {
+        shutdown.wait().await;
+        tokio::time::delay_for(DRAIN_DEADLINE).await;
+    };
+
+    futures::pin_mut!(processing, deadline);
+    match future::select(processing, deadline).await {
+        Either::Left(_) => info!(datasource = %datasource_name, "drained in-flight messages"),
+        Either::Right(_) => warn!(datasource = %datasource_name, deadline = ?DRAIN_DEADLINE, "in-flight messages didn't finish before the deadline, they'll be redelivered"),
+    };
+}
+
+// Extracts a single configuration and publishes the results. 
+// The message is only acknowledged when everything extracted was stored downstream.
+async fn process_message<T: Datasource, B: MessageBus>(
+    datasource: T, 
+    message: &Delivery,
+    configuration: &Vec<u8>, 
+    trace: &TraceContext,
+    publisher: &B, 
+    routes: &RoutingTable,
+    drift_detector: &Mutex<DriftDetector>,
+) -> Outcome {
+    let datasource_name = datasource.get_name();
+
+    let timer = metrics::EXTRACTION_DURATION.with_label_values(&[&datasource_name]).start_timer();
+    let extract_result = extract_held(&datasource, message, configuration).await;
+    timer.observe_duration();
+
+    let report = match extract_result{
+        Ok(k) => k,
+        Err(e) => {
+            error!(error = %e, "Error occured in the extract logic");
+            metrics::ERRORS.with_label_values(&["extract"]).inc();
+            if is_retryable(&e) {
+                return Outcome::Retry(e.to_string());
+            }
+            return Outcome::Fail(e.to_string());
+        }
+    };
+
+    if let Some(diagnostics) = Diagnostics::new(&datasource_name, &report) {
+        publish_monitoring(publisher, DIAGNOSTICS_SUBJECT, &diagnostics).await;
+    }
+
+    let quality_report = QualityReport::new(&datasource_name, &report);
+    if !quality_report.unmatched_selectors.is_empty() {
+        warn!(selectors = ?quality_report.unmatched_selectors, "Selectors matched nothing");
+    }
+
+    let drift_alert = drift_detector.lock().unwrap().push(quality_report);
+    if let Some(alert) = drift_alert {
+        warn!(drops = ?alert.drops, "Extraction quality dropped");
+        publish_monitoring(publisher, DRIFT_ALERT_SUBJECT, &alert).await;
+    }
+
+    let child_trace = telemetry::child_context(&tracing::Span::current(), trace);
+    if let Err(e) = publish_items(publisher, routes, report.items, &child_trace, &datasource_name).await {
+        // Items published so far will be published again, downstream has to tolerate duplicates
+        return Outcome::Retry(e.to_string());
+    }
+    datasource.commit(&report.consumed);
+
+    Outcome::Done
+}
+
+// A breaker turning the fetch away says nothing about the message, so it's held until the host
+// may be called again instead of spending its deliveries on redeliveries that would be turned away too
+async fn extract_held<T: Datasource>(datasource: &T, message: &Delivery, configuration: &Vec<u8>) -> ExtractResult {
+    loop {
+        match datasource.extract(configuration).await {
+            Err(e) if e.is::<CircuitOpenError>() => {},
+            result => return result,
+        };
+
+        let wait = datasource.paused().unwrap_or(HOLD_INTERVAL).min(HOLD_INTERVAL);
+        info!(?wait, "circuit breaker is open, holding the message");
+        tokio::time::delay_for(wait).await;
+        if let Err(e) = message.progress().await {
+            warn!(error = %e, "Error extending the message's ack deadline");
+        }
+    }
+}
+
+// Publishes every item to the subjects its route gives, stops at the first one the bus doesn't accept
+pub async fn publish_items<B: MessageBus>(publisher: &B, routes: &RoutingTable, items: Vec<Extracted>, trace: &TraceContext, source: &str) -> io::Result<()> {
+    for item in items {
+        debug!(?item, "extracted");
+        let subjects = routes.subjects(&item, source);
+        if subjects.is_empty() {
+            debug!(item_type = item.get_type_name(), "dropped by its route");
+            metrics::ITEMS_DROPPED.with_label_values(&[item.get_type_name()]).inc();
+            continue;
+        }
+
+        let message = match serde_json::to_string(&Envelope::new(trace.clone(), &item).with_source(source)){
+            Ok(msg) => msg,
+            Err(e) => {
+                error!(error = %e, ?item, "Error serializing the extracted item into a message");
+                metrics::ERRORS.with_label_values(&["serialize"]).inc();
+                continue;
+            }
+        };
+        
+        for destination_queue in subjects {
+            match publisher.publish(&destination_queue, message.as_bytes()).await {
+                Ok(_) => metrics::ITEMS_PUBLISHED.with_label_values(&[item.get_type_name()]).inc(),
+                Err(e) => {
+                    error!(queue = %destination_queue, error = %e, %message, "Error publishing a message");
+                    metrics::ERRORS.with_label_values(&["publish"]).inc();
+                    return Err(e);
+                }
+            };
+        }
+    }
+
+    Ok(())
+}
+
+// Monitoring messages are best effort, losing one doesn't fail the extraction
+async fn publish_monitoring<B: MessageBus, T: Serialize>(publisher: &B, subject: &str, content: &T) {
+    let message = match serde_json::to_string(content){
+        Ok(message) => message,
+        Err(e) => {
+            metrics::ERRORS.with_label_values(&["serialize"]).inc();
+            error!(queue = subject, error = %e, "Error serializing a monitoring message");
+            return;
+        },
+    };
+
+    if let Err(e) = publisher.publish(subject, message.as_bytes()).await {
+        metrics::ERRORS.with_label_values(&["publish"]).inc();
+        error!(queue = subject, error = %e, %message, "Error publishing a message");
+    }
+}
+
+// Network failures are worth another try, anything else would fail the same way again
+pub fn is_retryable(error: &Box<dyn std::error::Error>) -> bool {
+    error.is::<reqwest::Error>() || error.is::<std::io::Error>() || error.is::<CircuitOpenError>()
+}
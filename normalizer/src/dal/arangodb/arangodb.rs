@@ -4,6 +4,7 @@ use {
     anyhow::{Result, Error},
     serde::{Serialize, Deserialize},
     crate::Event,
+    crate::metrics,
     arangors::{
         Connection, 
         AqlQuery, 
@@ -46,9 +47,16 @@ impl DAL{
     }
 
     pub async fn upload(&self, event: Event) -> anyhow::Result<()>{
-        let doc = self.main_col
+        let timer = metrics::UPSERT_DURATION.start_timer();
+        let result = self.main_col
             .create_document(event, InsertOptions::builder().return_new(true).build())
-            .await?;
+            .await;
+        timer.observe_duration();
+
+        if result.is_err() {
+            metrics::ERRORS.with_label_values(&["upsert"]).inc();
+        }
+        let doc = result?;
         // println!("{:#?}", doc);
         Ok(())
     }
@@ -64,10 +72,17 @@ impl DAL{
     
         query_lines.push("RETURN u".to_owned());
 
-        let result: Vec<Document<Event>> = self.db
+        let timer = metrics::IDENTIFY_DURATION.start_timer();
+        let result: Result<Vec<Document<Event>>, _> = self.db
             .aql_bind_vars(&query_lines.join(" "), vars)
-            .await?;
-             
+            .await;
+        timer.observe_duration();
+
+        if result.is_err() {
+            metrics::ERRORS.with_label_values(&["identify"]).inc();
+        }
+        let result = result?;
+
         Ok(result
             .into_iter()
             .map(|x| x.document)
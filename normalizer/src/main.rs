@@ -2,17 +2,32 @@ extern crate tokio;
 extern crate serde;
 extern crate schema;
 extern crate dotenv;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate prometheus;
 
 mod dal;
+mod metrics;
 pub mod model;
 
 use {
     serde::{Serialize, Deserialize},
     dal::*,
     anyhow::Result,
-    chrono::{DateTime}
+    chrono::{DateTime},
+    tracing::{info, error, info_span},
+    tracing_futures::Instrument,
+    tracing_subscriber::EnvFilter,
+    common::{Shutdown, Envelope},
 };
 
+const DEFAULT_LOG_FILTER: &str = "info";
+const NATS_URL: &str = "127.0.0.1:4222";
+// Everything the extractors publish for normalizing, normalizers share it as one queue group
+const NORMALIZER_SUBJECT: &str = "normalizer.>";
+const QUEUE_GROUP: &str = "normalizer";
+
 
 
 schema::schemafy!{
@@ -25,24 +40,89 @@ schema::schemafy!{
 #[tokio::main]
 async fn main() -> Result<()>{
     dotenv::dotenv().ok();
-    let v: Event = serde_json::from_str(r#"{ 
-        "title": "Amon Tobin - ISAM", 
-        "time": "2021/10/10T20:00:00",
-        "description": "Amon Tobin’s audiovisual spectacle ISAM took over the Concert Hall at Vivid LIVE 2012 in an audiovisual spectacle like no other.", 
-        "price": 67
-    }"#)?;
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER)))
+        .init();
+    let shutdown = Shutdown::listen();
+    tokio::spawn(serve_metrics(shutdown.clone()));
 
     let db = arangodb::DAL::new().await?;
-    let result = db.identify(v).await?;
-    
-    println!("{:#?}", result);
-    // db.upload(v).await?;
-    // let mut prev = v;
-    // for result in db.identify(v).await?{
-    //     prev = v.aggregate_into(result);
-    // }
-
-    // db.upload(prev)
+    let nc = nats::asynk::connect(NATS_URL).await?;
+    let subscription = nc.queue_subscribe(NORMALIZER_SUBJECT, QUEUE_GROUP).await?;
+    info!(subject = NORMALIZER_SUBJECT, "consuming");
+
+    let stopped = shutdown.wait();
+    tokio::pin!(stopped);
+    let mut draining = false;
+    loop {
+        let message = if draining {
+            subscription.next().await
+        } else {
+            tokio::select! {
+                message = subscription.next() => message,
+                _ = &mut stopped => {
+                    // No new messages after this, the ones already sent to us are still normalized
+                    if let Err(e) = subscription.drain().await {
+                        error!(error = %e, "Error draining the subscription");
+                        break;
+                    }
+                    draining = true;
+                    continue;
+                },
+            }
+        };
+        let message = match message {
+            Some(message) => message,
+            None => break,
+        };
+
+        if let Err(e) = normalize(&db, &message.data).await {
+            metrics::ERRORS.with_label_values(&["normalize"]).inc();
+            error!(error = %e, "Error normalizing");
+        }
+    }
+    info!("stopped normalizing");
+
+    nc.close().await?;
+    // Dropping the DAL closes its pooled ArangoDB connections
+    drop(db);
+    info!("shut down");
     Ok(())
 }
 
+// Normalizes one message. The extractor wraps what it publishes in an envelope with the trace
+// it belongs to, so the normalizer's logs carry the same correlation id
+async fn normalize(db: &arangodb::DAL, message: &[u8]) -> Result<()>{
+    let (trace, payload) = Envelope::open(message);
+    let span = info_span!("normalize", correlation_id = %trace.correlation_id);
+
+    async move {
+        let v: Event = serde_json::from_slice(&payload)?;
+        let result = db.identify(v).await?;
+
+        info!(?result, "identified");
+        // db.upload(v).await?;
+        // let mut prev = v;
+        // for result in db.identify(v).await?{
+        //     prev = v.aggregate_into(result);
+        // }
+    
+        // db.upload(prev)
+        Ok(())
+    }.instrument(span).await
+}
+
+async fn serve_metrics(shutdown: Shutdown){
+    let metrics_addr = std::env::var("METRICS_ADDR").unwrap_or(metrics::DEFAULT_METRICS_ADDR.to_owned());
+    let addr = match metrics_addr.parse(){
+        Ok(addr) => addr,
+        Err(e) => {
+            error!(addr = %metrics_addr, error = %e, "Invalid metrics address");
+            return;
+        }
+    };
+
+    if let Err(e) = common::metrics::serve(addr, Vec::new(), shutdown).await {
+        error!(error = %e, "Error serving metrics");
+    }
+}
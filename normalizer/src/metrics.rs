@@ -0,0 +1,23 @@
+use {
+    prometheus::{Histogram, IntCounterVec},
+};
+
+pub const DEFAULT_METRICS_ADDR: &str = "0.0.0.0:9899";
+
+lazy_static! {
+    pub static ref IDENTIFY_DURATION: Histogram = register_histogram!(
+        "normalizer_identify_duration_seconds",
+        "Time spent identifying a document against ArangoDB"
+    ).unwrap();
+
+    pub static ref UPSERT_DURATION: Histogram = register_histogram!(
+        "normalizer_upsert_duration_seconds",
+        "Time spent writing a document to ArangoDB"
+    ).unwrap();
+
+    pub static ref ERRORS: IntCounterVec = register_int_counter_vec!(
+        "normalizer_errors_total",
+        "Errors, per kind",
+        &["kind"]
+    ).unwrap();
+}